@@ -64,6 +64,7 @@ where
     }
 }
 
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Copy, Clone, Debug, Default, From, Into, AsRef)]
 pub struct AABB<T>(Rect<T>)
 where
@@ -82,10 +83,10 @@ where
         }
 
         if point.x > self.0.bottom_right.x {
-            self.0.top_left.x = point.x
+            self.0.bottom_right.x = point.x
         }
         if point.y > self.0.bottom_right.y {
-            self.0.top_left.y = point.y
+            self.0.bottom_right.y = point.y
         }
     }
 
@@ -120,3 +121,31 @@ where
         AABB::from(*self)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn insert_point_grows_the_box() {
+        let mut aabb = AABB::from(Rect::new(Point2::new(0, 0), Point2::new(1, 1)));
+
+        aabb.insert_point(Point2::new(3, 2));
+        aabb.insert_point(Point2::new(-1, 0));
+
+        let rect = Rect::from(aabb);
+        assert_eq!(rect.top_left(), &Point2::new(-1, 0));
+        assert_eq!(rect.bottom_right(), &Point2::new(3, 2));
+    }
+
+    #[test]
+    fn insert_rect() {
+        let mut aabb = Point2::new(0, 0).as_aabb();
+        aabb.insert(Rect::new(Point2::new(1, -2), Point2::new(4, 5)));
+
+        let rect = Rect::from(aabb);
+        assert_eq!(rect.top_left(), &Point2::new(0, -2));
+        assert_eq!(rect.bottom_right(), &Point2::new(4, 5));
+        assert_eq!(rect.size(), Vector2::new(4, 7));
+    }
+}
@@ -0,0 +1,883 @@
+use std::{
+    fs::File,
+    io::{
+        BufWriter,
+        Write,
+    },
+    path::{
+        Path,
+        PathBuf,
+    },
+    str::FromStr,
+};
+
+use rust_decimal::Decimal;
+use serde::Serialize;
+use skirt_rs::{
+    constraints::ConstraintOptions,
+    consumption::ConsumptionOptions,
+    error::Error,
+    fitting::{
+        FitOptions,
+        Measurements,
+    },
+    grading::{
+        Anchor,
+        SizeChart,
+    },
+    id::{
+        PanelId,
+        ParameterName,
+    },
+    layout::LayoutOptions,
+    nesting::NestingOptions,
+    parameters::{
+        Parameters,
+        ParametersFormat,
+    },
+    pattern::{
+        CurvatureCoords,
+        Pattern,
+        Template,
+    },
+    reader::Reader,
+    render::{
+        GradingContext,
+        Overlays,
+        PatternContext,
+        Render,
+        SweepContext,
+        Target,
+        TitleBlock,
+    },
+};
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub struct Args {
+    /// format of parameter files (`toml`, `json`, `yaml` or
+    /// `specification` for the `specification.json` of a dataset sample). if
+    /// omitted, it's guessed from the file name.
+    #[structopt(long, global = true)]
+    parameters_format: Option<ParametersFormat>,
+
+    /// require parameter files to have every parameter of the template,
+    /// instead of using the default value of missing parameters.
+    #[structopt(long, global = true)]
+    strict: bool,
+
+    #[structopt(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, StructOpt)]
+enum Command {
+    Render {
+        /// render this template.
+        template: PathBuf,
+
+        /// render template with this parameter file. if omitted, the template
+        /// will be rendered with default parameters.
+        parameters: Option<PathBuf>,
+
+        /// write the SVG to this file. if omitted, it's printed to stdout.
+        #[structopt(short, long)]
+        output: Option<PathBuf>,
+
+        /// highlight stitched edges with a color and number per stitch.
+        #[structopt(long)]
+        stitches: bool,
+
+        /// draw notches at the ends and midpoints of stitched edges.
+        #[structopt(long)]
+        notches: bool,
+
+        /// draw arrows on stitched edges that show the direction they are
+        /// sewn in.
+        #[structopt(long)]
+        arrows: bool,
+
+        /// label edges with their index.
+        #[structopt(long)]
+        edge_indices: bool,
+
+        /// how many times a panel is cut, as `panel=count`. panels are cut
+        /// once by default.
+        #[structopt(long)]
+        cut: Vec<CutQuantity>,
+
+        #[structopt(flatten)]
+        layout: LayoutArgs,
+    },
+    Marker {
+        /// nest this template.
+        template: PathBuf,
+
+        /// instantiate the template with this parameter file. if omitted, the
+        /// default parameters are used.
+        parameters: Option<PathBuf>,
+
+        /// write the SVG to this file. if omitted, it's printed to stdout.
+        #[structopt(short, long)]
+        output: Option<PathBuf>,
+
+        #[structopt(flatten)]
+        nesting: NestingArgs,
+    },
+    Report {
+        /// report on this template.
+        template: PathBuf,
+
+        /// instantiate the template with this parameter file. if omitted, the
+        /// default parameters are used.
+        parameters: Option<PathBuf>,
+
+        /// write the report to this file. if omitted, it's printed to stdout.
+        #[structopt(short, long)]
+        output: Option<PathBuf>,
+
+        /// format of the report (`text`, `json` or `csv`).
+        #[structopt(long, default_value = "text")]
+        format: ReportFormat,
+
+        #[structopt(flatten)]
+        nesting: NestingArgs,
+    },
+    Constraints {
+        /// check the constraints of this template.
+        template: PathBuf,
+
+        /// instantiate the template with this parameter file. if omitted, the
+        /// default parameters are used.
+        parameters: Option<PathBuf>,
+
+        /// check this pattern instead of instantiating the template. this is
+        /// a pattern as written by `import`, or the `specification.json` of a
+        /// dataset sample.
+        #[structopt(long, conflicts_with = "parameters")]
+        pattern: Option<PathBuf>,
+
+        /// the control points of `--pattern` are in absolute coordinates.
+        /// dataset specifications say this themselves.
+        #[structopt(long)]
+        absolute_curvature: bool,
+
+        /// write the report to this file. if omitted, it's printed to stdout.
+        #[structopt(short, long)]
+        output: Option<PathBuf>,
+
+        /// format of the report (`text`, `json` or `csv`).
+        #[structopt(long, default_value = "text")]
+        format: ReportFormat,
+
+        /// largest relative deviation from a constraint's target that counts
+        /// as satisfied.
+        #[structopt(long, default_value = "0.01")]
+        tolerance: f64,
+    },
+    Fit {
+        /// fit the parameters of this template.
+        template: PathBuf,
+
+        /// toml file with the measurements to fit.
+        measurements: PathBuf,
+
+        /// write the fitted parameters to this file. if omitted, they're
+        /// printed to stdout.
+        #[structopt(short, long)]
+        output: Option<PathBuf>,
+
+        /// largest difference from a measurement that counts as a match, in
+        /// centimeters.
+        #[structopt(long, default_value = "0.1")]
+        tolerance: f64,
+    },
+    Grade {
+        /// grade this template.
+        template: PathBuf,
+
+        /// toml file with the size chart.
+        chart: PathBuf,
+
+        /// write the SVG to this file. if omitted, it's printed to stdout.
+        #[structopt(short, long)]
+        output: Option<PathBuf>,
+
+        /// point at which the sizes of a panel are aligned (`bottom-left`,
+        /// `center` or `vertex:N`).
+        #[structopt(long, default_value = "bottom-left")]
+        anchor: Anchor,
+
+        /// space between panels.
+        #[structopt(long, default_value = "5")]
+        spacing: f64,
+
+        /// largest difference from a measurement that counts as a match, in
+        /// centimeters.
+        #[structopt(long, default_value = "0.1")]
+        tolerance: f64,
+    },
+    Sweep {
+        /// sweep parameters of this template.
+        template: PathBuf,
+
+        /// take the parameters that aren't swept from this file. if omitted,
+        /// the default parameters are used.
+        parameters: Option<PathBuf>,
+
+        /// the parameter to sweep. this can be given twice with different
+        /// parameters, to sweep the first one along the columns and the second
+        /// one along the rows.
+        #[structopt(short = "p", long = "parameter", required = true, parse(from_str))]
+        swept: Vec<ParameterName>,
+
+        /// number of values per parameter, including both ends of the range.
+        #[structopt(long, default_value = "5")]
+        steps: usize,
+
+        /// write the SVG to this file. if omitted, it's printed to stdout.
+        #[structopt(short, long)]
+        output: Option<PathBuf>,
+
+        /// space between patterns on the contact sheet.
+        #[structopt(long, default_value = "10")]
+        cell_spacing: f64,
+
+        /// render an animation with this many seconds per pattern, instead of
+        /// a contact sheet.
+        #[structopt(long)]
+        animate: Option<f64>,
+
+        /// also write every pattern into its own SVG file in this directory.
+        #[structopt(long)]
+        frames: Option<PathBuf>,
+
+        #[structopt(flatten)]
+        layout: LayoutArgs,
+    },
+    Interpolate {
+        /// the template both parameter sets belong to.
+        template: PathBuf,
+
+        /// parameters at `t = 0`.
+        from: PathBuf,
+
+        /// parameters at `t = 1`.
+        to: PathBuf,
+
+        /// how far to blend from the first to the second parameter set,
+        /// between 0 and 1.
+        #[structopt(short, long, default_value = "0.5")]
+        t: Decimal,
+
+        /// write the blended parameters to this file. if omitted, they're
+        /// printed to stdout.
+        #[structopt(short, long)]
+        output: Option<PathBuf>,
+    },
+    Import {
+        /// pattern file to import.
+        input: PathBuf,
+
+        /// write the pattern as json to this file. if omitted, it's printed to
+        /// stdout.
+        #[structopt(short, long)]
+        output: Option<PathBuf>,
+
+        /// format of the input file (`seamly2d` or `freesewing`). if omitted,
+        /// it's detected from the file extension.
+        #[structopt(long)]
+        from: Option<Format>,
+
+        /// write control points in absolute panel coordinates instead of
+        /// relative curvature coordinates.
+        #[structopt(long)]
+        absolute_curvature: bool,
+    },
+    Export {
+        /// pattern json file to export.
+        pattern: PathBuf,
+
+        /// write the exported pattern to this file. if omitted, it's printed
+        /// to stdout.
+        #[structopt(short, long)]
+        output: Option<PathBuf>,
+
+        /// format to export to. only `freesewing` is supported, which can't
+        /// store stitches.
+        #[structopt(long, default_value = "freesewing")]
+        to: Format,
+
+        /// normalize panel translations and edge loops before exporting.
+        #[structopt(long)]
+        normalize: bool,
+
+        #[structopt(flatten)]
+        layout: LayoutArgs,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+struct LayoutArgs {
+    /// keep panels in their own coordinates, instead of placing them next to
+    /// each other.
+    #[structopt(long)]
+    no_layout: bool,
+
+    /// space between panels.
+    #[structopt(long, default_value = "2")]
+    spacing: Decimal,
+
+    /// width of the sheet the panels are placed on.
+    #[structopt(long, default_value = "150")]
+    sheet_width: Decimal,
+}
+
+impl LayoutArgs {
+    fn options(&self) -> Option<LayoutOptions> {
+        (!self.no_layout).then_some(LayoutOptions {
+            spacing: self.spacing,
+            sheet_width: self.sheet_width,
+        })
+    }
+}
+
+#[derive(Debug, StructOpt)]
+struct NestingArgs {
+    /// width of the fabric roll.
+    #[structopt(long, default_value = "150")]
+    fabric_width: f64,
+
+    /// space between the cutting lines of pieces.
+    #[structopt(long, default_value = "0.5")]
+    spacing: f64,
+
+    /// seam allowance around every panel.
+    #[structopt(long, default_value = "1")]
+    seam_allowance: f64,
+
+    /// how many times a panel is cut, as `panel=count`. panels are cut once
+    /// by default.
+    #[structopt(long)]
+    cut: Vec<CutQuantity>,
+
+    /// cut every second copy of a panel mirrored.
+    #[structopt(long)]
+    mirror_pairs: bool,
+
+    /// don't turn pieces by 180°.
+    #[structopt(long)]
+    no_rotation: bool,
+
+    /// size of the raster used for nesting.
+    #[structopt(long, default_value = "0.5")]
+    resolution: f64,
+}
+
+impl NestingArgs {
+    fn options(self) -> NestingOptions {
+        NestingOptions {
+            fabric_width: self.fabric_width,
+            spacing: self.spacing,
+            seam_allowance: self.seam_allowance,
+            cut: self
+                .cut
+                .into_iter()
+                .map(|cut| (cut.panel, cut.count))
+                .collect(),
+            mirror_pairs: self.mirror_pairs,
+            rotate: !self.no_rotation,
+            resolution: self.resolution,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct CutQuantity {
+    panel: PanelId,
+    count: u32,
+}
+
+impl FromStr for CutQuantity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (panel, count) = s
+            .split_once('=')
+            .ok_or_else(|| format!("expected `panel=count`: {}", s))?;
+        let count = count
+            .parse()
+            .map_err(|_| format!("invalid cut quantity: {}", count))?;
+
+        Ok(Self {
+            panel: panel.into(),
+            count,
+        })
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+enum ReportFormat {
+    Text,
+    Json,
+    Csv,
+}
+
+impl FromStr for ReportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            "csv" => Ok(Self::Csv),
+            _ => Err(format!("unknown report format: {}", s)),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+enum Format {
+    Seamly2d,
+    FreeSewing,
+}
+
+impl Format {
+    /// detects the format from the file extension: `.sm2d` and `.val` are
+    /// Seamly2D files, `.json` files are FreeSewing drafts.
+    fn from_extension(path: &Path) -> Option<Self> {
+        match path.extension().and_then(|s| s.to_str()) {
+            Some("sm2d") | Some("val") => Some(Self::Seamly2d),
+            Some("json") => Some(Self::FreeSewing),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "seamly2d" => Ok(Self::Seamly2d),
+            "freesewing" => Ok(Self::FreeSewing),
+            _ => Err(format!("unknown format: {}", s)),
+        }
+    }
+}
+
+impl Args {
+    pub fn run(self) -> Result<(), Error> {
+        let read = ReadOptions {
+            format: self.parameters_format,
+            strict: self.strict,
+        };
+
+        match self.command {
+            Command::Render {
+                template,
+                parameters,
+                output,
+                stitches,
+                notches,
+                arrows,
+                edge_indices,
+                cut,
+                layout,
+            } => {
+                let Instance {
+                    name,
+                    parameters,
+                    pattern,
+                    ..
+                } = instantiate(&template, parameters.as_deref(), &read)?;
+
+                let context = PatternContext {
+                    overlays: Overlays {
+                        stitches,
+                        notches,
+                        arrows,
+                        edge_indices,
+                    },
+                    cut: cut.into_iter().map(|cut| (cut.panel, cut.count)).collect(),
+                    title: Some(TitleBlock { name, parameters }),
+                    layout: layout.options(),
+                };
+
+                let mut target = Target::default();
+                pattern.render(&mut target, &context)?;
+                write_svg(output.as_deref(), target)?;
+            }
+            Command::Marker {
+                template,
+                parameters,
+                output,
+                nesting,
+            } => {
+                let instance = instantiate(&template, parameters.as_deref(), &read)?;
+
+                let marker = instance.pattern.nest(&nesting.options())?;
+                log::info!(
+                    "marker length: {:.1}, {:.0}% used",
+                    marker.length,
+                    marker.utilization() * 100.0
+                );
+
+                let mut target = Target::default();
+                marker.render(&mut target, &())?;
+                write_svg(output.as_deref(), target)?;
+            }
+            Command::Report {
+                template,
+                parameters,
+                output,
+                format,
+                nesting,
+            } => {
+                let instance = instantiate(&template, parameters.as_deref(), &read)?;
+
+                let options = ConsumptionOptions {
+                    units_in_meter: instance.template.properties.units_in_meter,
+                    nesting: nesting.options(),
+                };
+                let report = instance.pattern.consumption(&options)?;
+
+                match format {
+                    ReportFormat::Text => write_text(output.as_deref(), &report.to_string())?,
+                    ReportFormat::Json => write_json(output.as_deref(), &report)?,
+                    ReportFormat::Csv => write_text(output.as_deref(), &report.to_csv())?,
+                }
+            }
+            Command::Constraints {
+                template,
+                parameters,
+                pattern,
+                absolute_curvature,
+                output,
+                format,
+                tolerance,
+            } => {
+                let template = Reader::new(&template)?.template()?;
+
+                let pattern = if let Some(path) = pattern {
+                    let json = std::fs::read_to_string(&path)?;
+                    let (mut pattern, coords) = if ParametersFormat::from_path(&path)
+                        == Some(ParametersFormat::Specification)
+                    {
+                        let sample: Template = serde_json::from_str(&json)?;
+                        (sample.pattern, sample.properties.curvature_coords)
+                    }
+                    else if absolute_curvature {
+                        (serde_json::from_str(&json)?, CurvatureCoords::Absolute)
+                    }
+                    else {
+                        (serde_json::from_str(&json)?, CurvatureCoords::Relative)
+                    };
+                    pattern.convert_curvature(coords, template.properties.curvature_coords)?;
+                    pattern
+                }
+                else {
+                    let parameters = read_parameters(&template, parameters.as_deref(), &read)?;
+                    template.with_parameters(&parameters)?
+                };
+
+                let options = ConstraintOptions {
+                    units_in_meter: template.properties.units_in_meter,
+                    tolerance,
+                };
+                let report = template.check_constraints(&pattern, &options)?;
+
+                let violations = report.violations().count();
+                if violations > 0 {
+                    log::warn!("{} constraints are violated", violations);
+                }
+
+                match format {
+                    ReportFormat::Text => write_text(output.as_deref(), &report.to_string())?,
+                    ReportFormat::Json => write_json(output.as_deref(), &report)?,
+                    ReportFormat::Csv => write_text(output.as_deref(), &report.to_csv())?,
+                }
+            }
+            Command::Fit {
+                template,
+                measurements,
+                output,
+                tolerance,
+            } => {
+                let template = Reader::new(&template)?.template()?;
+                let toml = std::fs::read_to_string(measurements)?;
+                let measurements: Measurements = toml::from_str(&toml)?;
+
+                let options = FitOptions {
+                    units_in_meter: template.properties.units_in_meter,
+                    tolerance,
+                    ..Default::default()
+                };
+                let fit = template.fit(&measurements, &options)?;
+
+                eprint!("{}", fit);
+                if !fit.is_exact() {
+                    log::warn!("not all measurements can be reached within the parameter ranges");
+                }
+
+                write_parameters(output.as_deref(), &fit.parameters, read.format)?;
+            }
+            Command::Grade {
+                template,
+                chart,
+                output,
+                anchor,
+                spacing,
+                tolerance,
+            } => {
+                let template = Reader::new(&template)?.template()?;
+                let toml = std::fs::read_to_string(chart)?;
+                let chart: SizeChart = toml::from_str(&toml)?;
+
+                let options = FitOptions {
+                    units_in_meter: template.properties.units_in_meter,
+                    tolerance,
+                    ..Default::default()
+                };
+                let grading = template.grade(&chart, &options)?;
+
+                for size in &grading.sizes {
+                    if !size.fit.is_exact() {
+                        log::warn!(
+                            "size {} doesn't fit all measurements:\n{}",
+                            size.name,
+                            size.fit
+                        );
+                    }
+                }
+
+                let mut target = Target::default();
+                grading.render(&mut target, &GradingContext { anchor, spacing })?;
+                write_svg(output.as_deref(), target)?;
+            }
+            Command::Sweep {
+                template,
+                parameters,
+                swept,
+                steps,
+                output,
+                cell_spacing,
+                animate,
+                frames,
+                layout,
+            } => {
+                let template = Reader::new(&template)?.template()?;
+                let base = read_parameters(&template, parameters.as_deref(), &read)?;
+                let grid = template.sweep(&base, &swept, steps)?;
+
+                let context = SweepContext {
+                    pattern: PatternContext {
+                        layout: layout.options(),
+                        ..Default::default()
+                    },
+                    spacing: cell_spacing,
+                    frame_duration: animate,
+                };
+
+                if let Some(frames) = frames {
+                    std::fs::create_dir_all(&frames)?;
+                    for (index, (_, document)) in grid.frames(&context.pattern)?.iter().enumerate()
+                    {
+                        svg::save(frames.join(format!("frame_{:03}.svg", index)), document)?;
+                    }
+                }
+
+                let mut target = Target::default();
+                grid.render(&mut target, &context)?;
+                write_svg(output.as_deref(), target)?;
+            }
+            Command::Interpolate {
+                template,
+                from,
+                to,
+                t,
+                output,
+            } => {
+                let template = Reader::new(&template)?.template()?;
+                let from = read_parameters(&template, Some(&from), &read)?;
+                let to = read_parameters(&template, Some(&to), &read)?;
+
+                let parameters = from.interpolate(&to, t)?;
+                // make sure the blend is a valid instance of the template.
+                template.with_parameters(&parameters)?;
+
+                write_parameters(output.as_deref(), &parameters, read.format)?;
+            }
+            Command::Import {
+                input,
+                output,
+                from,
+                absolute_curvature,
+            } => {
+                let mut pattern = match from.or_else(|| Format::from_extension(&input)) {
+                    Some(Format::Seamly2d) => skirt_rs::convert::seamly2d::from_file(&input)?,
+                    Some(Format::FreeSewing) => skirt_rs::convert::freesewing::from_file(&input)?,
+                    None => return Err(Error::UnknownFormat(input)),
+                };
+                log::debug!("pattern: {:#?}", pattern);
+
+                if absolute_curvature {
+                    pattern
+                        .convert_curvature(CurvatureCoords::Relative, CurvatureCoords::Absolute)?;
+                }
+
+                write_json(output.as_deref(), &pattern)?;
+            }
+            Command::Export {
+                pattern,
+                output,
+                to,
+                normalize,
+                layout,
+            } => {
+                let json = std::fs::read_to_string(&pattern)?;
+                let mut pattern: Pattern = serde_json::from_str(&json)?;
+                log::debug!("pattern: {:#?}", pattern);
+
+                if normalize {
+                    pattern.normalize_panel_translations()?;
+                    pattern.normalize_edge_loops()?;
+                }
+
+                if let Some(options) = layout.options() {
+                    pattern = pattern.laid_out(&options)?;
+                }
+
+                match to {
+                    Format::FreeSewing => {
+                        let draft = skirt_rs::convert::freesewing::to_draft(&pattern)?;
+                        write_json(output.as_deref(), &draft)?;
+                    }
+                    Format::Seamly2d => {
+                        return Err(Error::UnsupportedExport { format: "seamly2d" })
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// how parameter files are read.
+struct ReadOptions {
+    /// guessed from the file name if `None`.
+    format: Option<ParametersFormat>,
+
+    /// see [`Template::complete_parameters`].
+    strict: bool,
+}
+
+/// a template, instantiated with some parameters.
+struct Instance {
+    name: String,
+    template: Template,
+    parameters: Parameters,
+    pattern: Pattern,
+}
+
+/// reads a template and instantiates it with the parameters in the file at
+/// `parameters`, or the default parameters.
+fn instantiate(
+    template: &Path,
+    parameters: Option<&Path>,
+    read: &ReadOptions,
+) -> Result<Instance, Error> {
+    let mut reader = Reader::new(template)?;
+    let name = reader.template_name()?;
+    let template = reader.template()?;
+    log::debug!("template: {:#?}", template);
+
+    let parameters = read_parameters(&template, parameters, read)?;
+    let pattern = template.with_parameters(&parameters)?;
+    log::debug!("pattern: {:#?}", pattern);
+
+    Ok(Instance {
+        name,
+        template,
+        parameters,
+        pattern,
+    })
+}
+
+/// reads the parameters in the file at `path`, or returns the default
+/// parameters of `template`. parameters missing from the file get their
+/// default value, unless `read.strict` is set.
+fn read_parameters(
+    template: &Template,
+    path: Option<&Path>,
+    read: &ReadOptions,
+) -> Result<Parameters, Error> {
+    let parameters = if let Some(path) = path {
+        let parameters = Parameters::read(path, read.format)?;
+        template.complete_parameters(&parameters, read.strict)?
+    }
+    else {
+        template.default_parameters()
+    };
+    log::debug!("parameters: {:#?}", parameters);
+
+    Ok(parameters)
+}
+
+/// writes the SVG document to `path`, or to stdout if `path` is `None`.
+fn write_svg(path: Option<&Path>, target: Target) -> Result<(), Error> {
+    let document = target.build()?;
+
+    if let Some(path) = path {
+        svg::save(path, &document)?;
+    }
+    else {
+        svg::write(std::io::stdout().lock(), &document)?;
+    }
+
+    Ok(())
+}
+
+/// writes `parameters` to `path`, or to stdout if `path` is `None`. if
+/// `format` is `None`, it's guessed from the file name, and stdout gets TOML.
+fn write_parameters(
+    path: Option<&Path>,
+    parameters: &Parameters,
+    format: Option<ParametersFormat>,
+) -> Result<(), Error> {
+    if let Some(path) = path {
+        parameters.write(path, format)?;
+    }
+    else {
+        let text = parameters.to_string_as(format.unwrap_or(ParametersFormat::Toml))?;
+        write_text(None, &text)?;
+    }
+
+    Ok(())
+}
+
+/// writes `text` to `path`, or to stdout if `path` is `None`.
+fn write_text(path: Option<&Path>, text: &str) -> Result<(), Error> {
+    if let Some(path) = path {
+        std::fs::write(path, text)?;
+    }
+    else {
+        std::io::stdout().lock().write_all(text.as_bytes())?;
+    }
+
+    Ok(())
+}
+
+/// writes `value` as pretty json to `path`, or to stdout if `path` is `None`.
+fn write_json<T: Serialize>(path: Option<&Path>, value: &T) -> Result<(), Error> {
+    if let Some(path) = path {
+        let writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer_pretty(writer, value)?;
+    }
+    else {
+        serde_json::to_writer_pretty(std::io::stdout().lock(), value)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,38 @@
+mod args;
+
+use color_eyre::{
+    eyre::Report,
+    Section,
+    SectionExt,
+};
+use skirt_rs::error::Error;
+use structopt::StructOpt;
+
+use crate::args::Args;
+
+fn main() -> Result<(), Report> {
+    dotenv::dotenv().ok();
+    color_eyre::install()?;
+    pretty_env_logger::init();
+
+    let args = Args::from_args();
+    args.run().map_err(report)?;
+
+    Ok(())
+}
+
+/// converts an error into a report, with extra sections for errors that carry
+/// more context.
+fn report(error: Error) -> Report {
+    match error {
+        Error::JsonFile(error) => {
+            let excerpt = error.excerpt.clone();
+            let pointer = error.pointer.clone();
+
+            Report::new(error)
+                .section(excerpt.header("Source:"))
+                .note(format!("the failing value is at `{}`", pointer))
+        }
+        error => error.into(),
+    }
+}
@@ -0,0 +1,541 @@
+//! checking a pattern against the constraints of its template.
+//!
+//! constraints say that the edges they influence should have the same length
+//! (`length_equality`) or the same curvature (`curve_equality`). like the
+//! dataset generator, the target of a constraint is the mean over all of its
+//! edges. [`Template::check_constraints`] measures every edge of every
+//! constraint and reports how far it is from the target, without changing
+//! the pattern. this works for any pattern with the template's panels, e.g.
+//! samples from the dataset or hand-edited patterns, and helps to find
+//! constraints that reference the wrong edges.
+//!
+//! lengths are reported in centimeters and converted with the template's
+//! `units_in_meter`. the curvature of an edge is the distance of its control
+//! points from the edge, relative to the edge's length, see
+//! [`crate::pattern::Edge::relative_control_point`]. if an edge has two
+//! control points, the one further from the edge counts. lines and arcs have
+//! a curvature of 0.
+
+use std::fmt;
+
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+use crate::{
+    geometry::{
+        ConversionError,
+        Converter,
+    },
+    id::{
+        EdgeHandle,
+        EdgeIndex,
+        PanelId,
+    },
+    pattern::{
+        ConstraintType,
+        CurvatureCoords,
+        Pattern,
+        RenderError,
+        Template,
+    },
+    report::{
+        csv_field,
+        units_in_meter,
+        write_csv_row,
+        InvalidUnits,
+    },
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum ConstraintError {
+    #[error(transparent)]
+    InvalidUnits(#[from] InvalidUnits),
+
+    #[error("no such constraint: {name}")]
+    NoSuchConstraint { name: String },
+
+    #[error("can't measure constraint {constraint}")]
+    Measure {
+        constraint: String,
+        #[source]
+        source: RenderError,
+    },
+
+    #[error("conversion error")]
+    Conversion(#[from] ConversionError),
+
+    #[error(transparent)]
+    Render(#[from] RenderError),
+}
+
+#[derive(Clone, Debug)]
+pub struct ConstraintOptions {
+    /// pattern units in a meter, see
+    /// [`crate::pattern::Properties::units_in_meter`].
+    pub units_in_meter: Decimal,
+
+    /// largest relative deviation from the target that counts as satisfied.
+    pub tolerance: f64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ConstraintReport {
+    /// constraints in `constraint_order`, followed by constraints that aren't
+    /// in the order, by name.
+    pub constraints: Vec<ConstraintCheck>,
+
+    pub tolerance: f64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ConstraintCheck {
+    pub name: String,
+    pub r#type: ConstraintType,
+
+    /// the mean of all measured edges. this is a length in centimeters or a
+    /// relative curvature, depending on the type.
+    pub target: f64,
+
+    /// the measured edges, grouped like the constraint's influences.
+    pub influences: Vec<MeasuredInfluence>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct MeasuredInfluence {
+    pub panel: PanelId,
+    pub edges: Vec<MeasuredEdge>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct MeasuredEdge {
+    pub edge: EdgeIndex,
+    pub value: f64,
+
+    /// `(value - target) / |target|`, or `None` if the target is 0.
+    pub deviation: Option<f64>,
+}
+
+impl Default for ConstraintOptions {
+    fn default() -> Self {
+        Self {
+            units_in_meter: Decimal::ONE_HUNDRED,
+            tolerance: 0.01,
+        }
+    }
+}
+
+impl Template {
+    /// measures the edges of every constraint on `pattern`. the pattern must
+    /// have its control points in the template's curvature coordinates, like
+    /// patterns made with [`Template::with_parameters`].
+    pub fn check_constraints(
+        &self,
+        pattern: &Pattern,
+        options: &ConstraintOptions,
+    ) -> Result<ConstraintReport, ConstraintError> {
+        let units_in_meter = units_in_meter(options.units_in_meter)?;
+        let mut converter = Converter::lossy();
+
+        let mut relative;
+        let pattern = if self.properties.curvature_coords == CurvatureCoords::Absolute {
+            relative = pattern.clone();
+            relative.convert_curvature(CurvatureCoords::Absolute, CurvatureCoords::Relative)?;
+            &relative
+        }
+        else {
+            pattern
+        };
+
+        let mut names = self.constraint_order.clone();
+        let mut unordered = self
+            .constraints
+            .keys()
+            .filter(|name| !self.constraint_order.contains(name))
+            .cloned()
+            .collect::<Vec<_>>();
+        unordered.sort();
+        names.extend(unordered);
+
+        let constraints = names
+            .into_iter()
+            .map(|name| {
+                let constraint = self
+                    .constraints
+                    .get(&name)
+                    .ok_or_else(|| ConstraintError::NoSuchConstraint { name: name.clone() })?;
+
+                let mut measure = |handle: &EdgeHandle| -> Result<f64, RenderError> {
+                    match constraint.r#type {
+                        ConstraintType::LengthEquality => {
+                            Ok(pattern.edge_segment(handle)?.length() / units_in_meter * 100.0)
+                        }
+                        ConstraintType::CurveEquality => {
+                            let (_, edge) = pattern.edge(handle)?;
+                            let mut curvature: f64 = 0.0;
+                            for control in edge.curve.control_points() {
+                                let across = converter.to_f64(control[1])?;
+                                if across.abs() > curvature.abs() {
+                                    curvature = across;
+                                }
+                            }
+                            Ok(curvature)
+                        }
+                    }
+                };
+
+                let mut influences = vec![];
+                let mut sum = 0.0;
+                let mut count = 0;
+                for influence in &constraint.influence {
+                    let mut edges = vec![];
+                    for (handle, _) in influence.edges() {
+                        let value = measure(&handle).map_err(|source| {
+                            ConstraintError::Measure {
+                                constraint: name.clone(),
+                                source,
+                            }
+                        })?;
+                        sum += value;
+                        count += 1;
+                        edges.push(MeasuredEdge {
+                            edge: handle.edge,
+                            value,
+                            deviation: None,
+                        });
+                    }
+                    influences.push(MeasuredInfluence {
+                        panel: influence.panel.clone(),
+                        edges,
+                    });
+                }
+
+                let target = if count > 0 { sum / count as f64 } else { 0.0 };
+                for edge in influences
+                    .iter_mut()
+                    .flat_map(|influence| &mut influence.edges)
+                {
+                    edge.deviation = (target != 0.0).then(|| (edge.value - target) / target.abs());
+                }
+
+                Ok(ConstraintCheck {
+                    name,
+                    r#type: constraint.r#type,
+                    target,
+                    influences,
+                })
+            })
+            .collect::<Result<_, ConstraintError>>()?;
+
+        Ok(ConstraintReport {
+            constraints,
+            tolerance: options.tolerance,
+        })
+    }
+}
+
+impl ConstraintCheck {
+    /// the largest deviation of an edge from the target, ignoring its sign.
+    /// edges of constraints with a target of 0 deviate infinitely, unless
+    /// they are 0 too.
+    pub fn max_deviation(&self) -> f64 {
+        self.influences
+            .iter()
+            .flat_map(|influence| &influence.edges)
+            .map(|edge| {
+                edge.deviation
+                    .map(f64::abs)
+                    .unwrap_or(if edge.value == 0.0 {
+                        0.0
+                    }
+                    else {
+                        f64::INFINITY
+                    })
+            })
+            .fold(0.0, f64::max)
+    }
+
+    pub fn is_satisfied(&self, tolerance: f64) -> bool {
+        self.max_deviation() <= tolerance
+    }
+}
+
+impl ConstraintReport {
+    /// the constraints whose edges deviate more than the tolerance.
+    pub fn violations(&self) -> impl Iterator<Item = &ConstraintCheck> {
+        self.constraints
+            .iter()
+            .filter(|constraint| !constraint.is_satisfied(self.tolerance))
+    }
+
+    /// the measured edges as CSV, one row per edge with a header row.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("constraint,type,panel,edge,value,target,deviation\n");
+
+        for constraint in &self.constraints {
+            for influence in &constraint.influences {
+                for edge in &influence.edges {
+                    write_csv_row(
+                        &mut csv,
+                        format_args!(
+                            "{},{},{},{},{:.4},{:.4},{}",
+                            csv_field(&constraint.name),
+                            type_name(constraint.r#type),
+                            csv_field(influence.panel.as_str()),
+                            edge.edge.0,
+                            edge.value,
+                            constraint.target,
+                            edge.deviation
+                                .map(|deviation| format!("{:.6}", deviation))
+                                .unwrap_or_default(),
+                        ),
+                    );
+                }
+            }
+        }
+
+        csv
+    }
+}
+
+impl fmt::Display for ConstraintReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for constraint in &self.constraints {
+            let status = if constraint.is_satisfied(self.tolerance) {
+                "ok"
+            }
+            else {
+                "violated"
+            };
+            writeln!(
+                f,
+                "{} ({}, target {:.4}): {}",
+                constraint.name,
+                type_name(constraint.r#type),
+                constraint.target,
+                status
+            )?;
+
+            for influence in &constraint.influences {
+                for edge in &influence.edges {
+                    let deviation = edge
+                        .deviation
+                        .map(|deviation| format!("{:+.2}%", deviation * 100.0))
+                        .unwrap_or_else(|| "-".to_owned());
+                    writeln!(
+                        f,
+                        "  {:<16} {:>12.4} {:>10}",
+                        EdgeHandle::new(influence.panel.clone(), edge.edge).to_string(),
+                        edge.value,
+                        deviation
+                    )?;
+                }
+            }
+        }
+
+        let violations = self.violations().count();
+        writeln!(
+            f,
+            "{} of {} constraints violated (tolerance {}%)",
+            violations,
+            self.constraints.len(),
+            self.tolerance * 100.0
+        )
+    }
+}
+
+fn type_name(r#type: ConstraintType) -> &'static str {
+    match r#type {
+        ConstraintType::LengthEquality => "length_equality",
+        ConstraintType::CurveEquality => "curve_equality",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::testing::{
+        pattern_json,
+        rectangle,
+    };
+
+    /// a 40x50 front and a 30x50 back with equal sides, different hems, and
+    /// curved edges of different depth.
+    fn template() -> Template {
+        let mut front = rectangle(40, 50);
+        front["edges"][3]["curvature"] = json!([0.5, 0.1]);
+        let mut back = rectangle(30, 50);
+        back["edges"][1]["curvature"] = json!([0.5, 0.2]);
+
+        serde_json::from_value(json!({
+            "pattern": pattern_json(vec![("front", front), ("back", back)], json!([])),
+            "properties": {
+                "curvature_coords": "relative",
+                "normalize_panel_translation": false,
+                "units_in_meter": 100,
+                "normalized_edge_loops": true,
+            },
+            "parameters": {},
+            "parameter_order": [],
+            "constraints": {
+                "sides": {
+                    "type": "length_equality",
+                    "influence": [
+                        {"panel": "front", "edge_list": [1]},
+                        {"panel": "back", "edge_list": [3]},
+                    ],
+                },
+                "hem": {
+                    "type": "length_equality",
+                    "influence": [
+                        {"panel": "front", "edge_list": [0]},
+                        {"panel": "back", "edge_list": [0]},
+                    ],
+                },
+                "curves": {
+                    "type": "curve_equality",
+                    "influence": [
+                        {"panel": "front", "edge_list": [3]},
+                        {"panel": "back", "edge_list": [1]},
+                    ],
+                },
+            },
+            "constraint_order": ["hem"],
+        }))
+        .unwrap()
+    }
+
+    fn report() -> ConstraintReport {
+        let template = template();
+        template
+            .check_constraints(&template.pattern, &ConstraintOptions::default())
+            .unwrap()
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "expected {}, got {}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn measures_constraints() {
+        let report = report();
+
+        // constraints in the order, then the others by name.
+        let names = report
+            .constraints
+            .iter()
+            .map(|constraint| constraint.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["hem", "curves", "sides"]);
+
+        let hem = &report.constraints[0];
+        assert_close(hem.target, 35.0);
+        assert_close(hem.influences[0].edges[0].value, 40.0);
+        assert_close(hem.influences[0].edges[0].deviation.unwrap(), 1.0 / 7.0);
+        assert_close(hem.influences[1].edges[0].deviation.unwrap(), -1.0 / 7.0);
+        assert_close(hem.max_deviation(), 1.0 / 7.0);
+
+        let curves = &report.constraints[1];
+        assert_close(curves.target, 0.15);
+        assert_close(curves.influences[1].edges[0].value, 0.2);
+
+        let sides = &report.constraints[2];
+        assert_close(sides.target, 50.0);
+        assert_close(sides.max_deviation(), 0.0);
+    }
+
+    #[test]
+    fn finds_violations() {
+        let report = report();
+        let violations = report
+            .violations()
+            .map(|constraint| constraint.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(violations, ["hem", "curves"]);
+
+        assert!(report.constraints[0].is_satisfied(0.15));
+    }
+
+    #[test]
+    fn zero_targets() {
+        let check = ConstraintCheck {
+            name: "flat".to_owned(),
+            r#type: ConstraintType::CurveEquality,
+            target: 0.0,
+            influences: vec![MeasuredInfluence {
+                panel: PanelId::from("front"),
+                edges: vec![MeasuredEdge {
+                    edge: EdgeIndex(0),
+                    value: 0.0,
+                    deviation: None,
+                }],
+            }],
+        };
+        assert!(check.is_satisfied(0.0));
+    }
+
+    #[test]
+    fn writes_csv() {
+        let csv = report().to_csv();
+        let lines = csv.lines().collect::<Vec<_>>();
+
+        assert_eq!(
+            lines,
+            [
+                "constraint,type,panel,edge,value,target,deviation",
+                "hem,length_equality,front,0,40.0000,35.0000,0.142857",
+                "hem,length_equality,back,0,30.0000,35.0000,-0.142857",
+                "curves,curve_equality,front,3,0.1000,0.1500,-0.333333",
+                "curves,curve_equality,back,1,0.2000,0.1500,0.333333",
+                "sides,length_equality,front,1,50.0000,50.0000,0.000000",
+                "sides,length_equality,back,3,50.0000,50.0000,0.000000",
+            ]
+        );
+    }
+
+    #[test]
+    fn displays_checks() {
+        let text = report().to_string();
+        let lines = text.lines().collect::<Vec<_>>();
+
+        assert_eq!(lines[0], "hem (length_equality, target 35.0000): violated");
+        assert!(lines[1].starts_with("  front:0"));
+        assert!(lines[1].ends_with("+14.29%"));
+        assert_eq!(lines[6], "sides (length_equality, target 50.0000): ok");
+        assert_eq!(
+            lines.last(),
+            Some(&"2 of 3 constraints violated (tolerance 1%)")
+        );
+    }
+
+    #[test]
+    fn no_such_constraint() {
+        let mut template = template();
+        template.constraint_order.push("missing".to_owned());
+
+        assert!(matches!(
+            template.check_constraints(&template.pattern, &ConstraintOptions::default()),
+            Err(ConstraintError::NoSuchConstraint { name }) if name == "missing"
+        ));
+    }
+
+    #[test]
+    fn invalid_units() {
+        let template = template();
+        let options = ConstraintOptions {
+            units_in_meter: Decimal::NEGATIVE_ONE,
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            template.check_constraints(&template.pattern, &options),
+            Err(ConstraintError::InvalidUnits(_))
+        ));
+    }
+}
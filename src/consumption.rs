@@ -0,0 +1,373 @@
+//! fabric consumption of a [`Pattern`], for costing.
+//!
+//! patterns don't know their units, so the report is made with the
+//! `units_in_meter` of the template (100 for templates and imported
+//! patterns). panel dimensions are reported in centimeters, areas in square
+//! meters, and the fabric needed in meters and yards. the fabric length is
+//! estimated by nesting the panels with [`Pattern::nest`].
+
+use std::fmt;
+
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+use crate::{
+    geometry::{
+        signed_area,
+        Converter,
+    },
+    id::{
+        EdgeHandle,
+        EdgeIndex,
+        PanelId,
+    },
+    nesting::{
+        NestingError,
+        NestingOptions,
+    },
+    pattern::{
+        Pattern,
+        RenderError,
+    },
+    report::{
+        csv_field,
+        units_in_meter,
+        write_csv_row,
+        InvalidUnits,
+    },
+};
+
+/// meters in a yard.
+const YARD: f64 = 0.9144;
+
+/// curves are flattened into points this far apart (in pattern units) to
+/// compute areas.
+const AREA_STEP: f64 = 0.1;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ConsumptionError {
+    #[error(transparent)]
+    InvalidUnits(#[from] InvalidUnits),
+
+    #[error(transparent)]
+    Nesting(#[from] NestingError),
+
+    #[error(transparent)]
+    Render(#[from] RenderError),
+}
+
+#[derive(Clone, Debug)]
+pub struct ConsumptionOptions {
+    /// pattern units in a meter, see
+    /// [`crate::pattern::Properties::units_in_meter`].
+    pub units_in_meter: Decimal,
+
+    /// how the panels are nested to estimate the fabric length. this also
+    /// has the fabric width and how many times each panel is cut.
+    pub nesting: NestingOptions,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ConsumptionReport {
+    pub panels: Vec<PanelConsumption>,
+
+    /// area of all pieces, with cut quantities.
+    pub total_area_m2: f64,
+
+    pub fabric_width_cm: f64,
+
+    /// length of fabric needed for all pieces.
+    pub fabric_length_m: f64,
+    pub fabric_length_yd: f64,
+
+    /// area of the pieces relative to the area of fabric needed. this
+    /// includes seam allowances.
+    pub utilization: f64,
+}
+
+/// measurements of a single panel, without seam allowance.
+#[derive(Clone, Debug, Serialize)]
+pub struct PanelConsumption {
+    pub panel: PanelId,
+
+    /// how many times the panel is cut.
+    pub cut: u32,
+
+    pub area_m2: f64,
+
+    /// size of the panel's bounding box, in its own coordinates.
+    pub width_cm: f64,
+    pub height_cm: f64,
+
+    pub perimeter_cm: f64,
+
+    /// length of the edges that are stitched to other edges.
+    pub seam_length_cm: f64,
+
+    /// length of the edges that aren't stitched, e.g. hems.
+    pub free_length_cm: f64,
+}
+
+impl Default for ConsumptionOptions {
+    fn default() -> Self {
+        Self {
+            units_in_meter: Decimal::ONE_HUNDRED,
+            nesting: NestingOptions::default(),
+        }
+    }
+}
+
+impl Pattern {
+    /// measures the panels and estimates how much fabric is needed.
+    pub fn consumption(
+        &self,
+        options: &ConsumptionOptions,
+    ) -> Result<ConsumptionReport, ConsumptionError> {
+        let units_in_meter = units_in_meter(options.units_in_meter)?;
+
+        let meters = |length: f64| length / units_in_meter;
+        let centimeters = |length: f64| meters(length) * 100.0;
+
+        let geometry = self.geometry(&mut Converter::lossy())?;
+        let graph = self.stitch_graph()?;
+
+        let mut panels = Vec::with_capacity(geometry.panels.len());
+        for (name, panel) in &geometry.panels {
+            let outline = panel.outline(AREA_STEP).ok_or_else(|| {
+                RenderError::NotALoop {
+                    panel: name.clone(),
+                }
+            })?;
+            let area = signed_area(&outline).abs();
+            let (min, max) = panel.bounding_box().unwrap_or_default();
+
+            let mut perimeter = 0.0;
+            let mut seam_length = 0.0;
+            for (index, edge) in panel.edges.iter().enumerate() {
+                let length = edge.segment.length();
+                perimeter += length;
+                if graph.is_stitched(&EdgeHandle::new(name.clone(), EdgeIndex(index))) {
+                    seam_length += length;
+                }
+            }
+
+            panels.push(PanelConsumption {
+                panel: name.clone(),
+                cut: options.nesting.cut.get(name).copied().unwrap_or(1),
+                area_m2: meters(meters(area)),
+                width_cm: centimeters(max.x - min.x),
+                height_cm: centimeters(max.y - min.y),
+                perimeter_cm: centimeters(perimeter),
+                seam_length_cm: centimeters(seam_length),
+                free_length_cm: centimeters(perimeter - seam_length),
+            });
+        }
+
+        let marker = self.nest(&options.nesting)?;
+        let fabric_length = meters(marker.length);
+
+        Ok(ConsumptionReport {
+            total_area_m2: panels
+                .iter()
+                .map(|panel| panel.area_m2 * f64::from(panel.cut))
+                .sum(),
+            fabric_width_cm: centimeters(marker.fabric_width),
+            fabric_length_m: fabric_length,
+            fabric_length_yd: fabric_length / YARD,
+            utilization: marker.utilization(),
+            panels,
+        })
+    }
+}
+
+impl ConsumptionReport {
+    /// the panels as CSV, one row per panel with a header row. the totals
+    /// aren't included.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from(
+            "panel,cut,area_m2,width_cm,height_cm,perimeter_cm,seam_length_cm,free_length_cm\n",
+        );
+
+        for panel in &self.panels {
+            write_csv_row(
+                &mut csv,
+                format_args!(
+                    "{},{},{:.4},{:.1},{:.1},{:.1},{:.1},{:.1}",
+                    csv_field(panel.panel.as_str()),
+                    panel.cut,
+                    panel.area_m2,
+                    panel.width_cm,
+                    panel.height_cm,
+                    panel.perimeter_cm,
+                    panel.seam_length_cm,
+                    panel.free_length_cm,
+                ),
+            );
+        }
+
+        csv
+    }
+}
+
+impl fmt::Display for ConsumptionReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:<16} {:>4} {:>10} {:>16} {:>14} {:>10} {:>10}",
+            "panel", "cut", "area (m²)", "size (cm)", "perimeter (cm)", "seam (cm)", "free (cm)"
+        )?;
+
+        for panel in &self.panels {
+            writeln!(
+                f,
+                "{:<16} {:>4} {:>10.4} {:>16} {:>14.1} {:>10.1} {:>10.1}",
+                panel.panel.as_str(),
+                panel.cut,
+                panel.area_m2,
+                format!("{:.1} x {:.1}", panel.width_cm, panel.height_cm),
+                panel.perimeter_cm,
+                panel.seam_length_cm,
+                panel.free_length_cm,
+            )?;
+        }
+
+        writeln!(f)?;
+        writeln!(f, "total area: {:.4} m²", self.total_area_m2)?;
+        writeln!(
+            f,
+            "fabric: {:.2} m ({:.2} yd) at {:.0} cm wide, {:.0}% used",
+            self.fabric_length_m,
+            self.fabric_length_yd,
+            self.fabric_width_cm,
+            self.utilization * 100.0
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::testing::{
+        self,
+        rectangle,
+    };
+
+    /// a 40x50 front, cut twice, stitched along one side to a 30x50 back.
+    fn pattern() -> Pattern {
+        testing::pattern(
+            vec![
+                ("front, left", rectangle(40, 50)),
+                ("back", rectangle(30, 50)),
+            ],
+            json!([[
+                {"panel": "front, left", "edge": 1},
+                {"panel": "back", "edge": 3},
+            ]]),
+        )
+    }
+
+    fn options() -> ConsumptionOptions {
+        ConsumptionOptions {
+            nesting: NestingOptions {
+                fabric_width: 100.0,
+                cut: [(PanelId::from("front, left"), 2)].into_iter().collect(),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "expected {}, got {}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn measures_panels() {
+        let report = pattern().consumption(&options()).unwrap();
+
+        let front = &report.panels[0];
+        assert_eq!(front.panel.as_str(), "front, left");
+        assert_eq!(front.cut, 2);
+        assert_close(front.area_m2, 0.2);
+        assert_close(front.width_cm, 40.0);
+        assert_close(front.height_cm, 50.0);
+        assert_close(front.perimeter_cm, 180.0);
+        assert_close(front.seam_length_cm, 50.0);
+        assert_close(front.free_length_cm, 130.0);
+
+        let back = &report.panels[1];
+        assert_eq!(back.cut, 1);
+        assert_close(back.area_m2, 0.15);
+
+        assert_close(report.total_area_m2, 0.55);
+        assert_close(report.fabric_width_cm, 100.0);
+        assert!(report.fabric_length_m > 0.0);
+        assert_close(report.fabric_length_yd, report.fabric_length_m / YARD);
+        assert!(report.utilization > 0.0 && report.utilization <= 1.0);
+    }
+
+    #[test]
+    fn units_in_meter_scale_the_report() {
+        let report = pattern()
+            .consumption(&ConsumptionOptions {
+                units_in_meter: Decimal::ONE_THOUSAND,
+                ..options()
+            })
+            .unwrap();
+
+        assert_close(report.panels[0].width_cm, 4.0);
+        assert_close(report.panels[0].area_m2, 0.002);
+    }
+
+    #[test]
+    fn invalid_units() {
+        let result = pattern().consumption(&ConsumptionOptions {
+            units_in_meter: Decimal::ZERO,
+            ..options()
+        });
+
+        assert!(matches!(
+            result,
+            Err(ConsumptionError::InvalidUnits(InvalidUnits { units_in_meter })) if units_in_meter.is_zero()
+        ));
+    }
+
+    #[test]
+    fn writes_csv() {
+        let csv = pattern().consumption(&options()).unwrap().to_csv();
+        let lines = csv.lines().collect::<Vec<_>>();
+
+        assert_eq!(
+            lines,
+            [
+                "panel,cut,area_m2,width_cm,height_cm,perimeter_cm,seam_length_cm,free_length_cm",
+                "\"front, left\",2,0.2000,40.0,50.0,180.0,50.0,130.0",
+                "back,1,0.1500,30.0,50.0,160.0,50.0,110.0",
+            ]
+        );
+    }
+
+    #[test]
+    fn displays_a_table() {
+        let report = pattern().consumption(&options()).unwrap();
+        let text = report.to_string();
+        let lines = text.lines().collect::<Vec<_>>();
+
+        assert!(lines[0].starts_with("panel"));
+        assert!(lines[1].starts_with("front, left"));
+        assert!(lines[1].contains("40.0 x 50.0"));
+        assert!(lines[2].starts_with("back"));
+        assert_eq!(lines[4], "total area: 0.5500 m²");
+        assert!(lines[5].starts_with(&format!(
+            "fabric: {:.2} m ({:.2} yd) at 100 cm wide",
+            report.fabric_length_m, report.fabric_length_yd
+        )));
+    }
+}
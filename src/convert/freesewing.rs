@@ -0,0 +1,576 @@
+//! conversion between [`Pattern`] and FreeSewing-style json drafts.
+//!
+//! every panel becomes a part with a single path named `seam`, which traces the
+//! panel's edges in loop order. FreeSewing paths only have cubic `curve`
+//! operations, so quadratic edges are converted into the equivalent cubic curve
+//! and arcs are approximated with cubic curves. when importing, curves become
+//! cubic edges.
+//!
+//! drafts use millimeters with the y axis pointing down, patterns use
+//! centimeters (i.e. `units_in_meter = 100`) with the y axis pointing up.
+//!
+//! a panel is a single outline, so parts whose path has more than one subpath
+//! (e.g. holes) can't be imported. stitches aren't part of the draft format
+//! and are dropped with a warning when exporting.
+
+use std::path::Path as FilePath;
+
+use indexmap::IndexMap;
+use nalgebra::{
+    Vector2,
+    Vector3,
+};
+use rust_decimal::Decimal;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::{
+    convert::{
+        Outline,
+        OutlineError,
+    },
+    curve::Segment,
+    error::Error,
+    geometry::Converter,
+    id::PanelId,
+    pattern::{
+        Pattern,
+        RenderError,
+    },
+};
+
+/// millimeters per centimeter.
+const SCALE: f64 = 10.0;
+
+/// name of the path that is used as a panel's outline.
+const OUTLINE_PATH: &str = "seam";
+
+#[derive(Debug, thiserror::Error)]
+pub enum FreeSewingError {
+    #[error("json error")]
+    Json(#[from] serde_json::Error),
+    #[error("invalid pattern")]
+    Pattern(#[from] RenderError),
+    #[error("part has no paths: {part}")]
+    NoOutline { part: String },
+    #[error("path of part {part} doesn't start with a move")]
+    MissingMove { part: String },
+    #[error("path of part {part} has more than one subpath")]
+    MultipleSubpaths { part: String },
+    #[error("invalid part: {part}")]
+    InvalidPart {
+        part: String,
+        #[source]
+        source: OutlineError,
+    },
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Draft {
+    pub parts: IndexMap<String, Part>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Part {
+    #[serde(default)]
+    pub points: IndexMap<String, Point>,
+
+    #[serde(default)]
+    pub paths: IndexMap<String, Path>,
+
+    /// placement of the panel in 3D. this isn't part of FreeSewing's format,
+    /// it's only written so that patterns survive a round-trip.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub placement: Option<Placement>,
+}
+
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+pub struct Point {
+    pub x: f64,
+    pub y: f64,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Path {
+    pub ops: Vec<Operation>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Operation {
+    Move { to: Point },
+    Line { to: Point },
+    Curve { cp1: Point, cp2: Point, to: Point },
+    Close,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Placement {
+    pub translation: Vector3<Decimal>,
+    pub rotation: Vector3<Decimal>,
+}
+
+impl From<Vector2<f64>> for Point {
+    fn from(vector: Vector2<f64>) -> Self {
+        Self {
+            x: vector.x,
+            y: vector.y,
+        }
+    }
+}
+
+impl From<Point> for Vector2<f64> {
+    fn from(point: Point) -> Self {
+        Vector2::new(point.x, point.y)
+    }
+}
+
+/// converts a pattern into a draft. stitches can't be stored in a draft, so
+/// they are dropped. this fails if the edges of a panel don't form a single
+/// loop.
+pub fn to_draft(pattern: &Pattern) -> Result<Draft, FreeSewingError> {
+    if !pattern.stitches.is_empty() {
+        log::warn!(
+            "freesewing drafts can't store stitches, dropping {} stitches",
+            pattern.stitches.len()
+        );
+    }
+
+    let mut draft = Draft::default();
+    let mut converter = Converter::lossy();
+
+    for (name, panel) in pattern.panels()? {
+        // the geometry checks that the edges' endpoints exist.
+        let geometry = panel.geometry(name, &mut converter)?;
+        let vertices = geometry
+            .vertices
+            .iter()
+            .map(to_draft_f64)
+            .collect::<Vec<_>>();
+
+        let points = vertices
+            .iter()
+            .enumerate()
+            .map(|(i, vertex)| (format!("v{}", i), Point::from(*vertex)))
+            .collect();
+
+        // the edges are exported in loop order, so that the path has a single
+        // subpath even if the edges are stored in another order.
+        let edges = geometry.edge_loop().ok_or_else(|| {
+            RenderError::NotALoop {
+                panel: name.clone(),
+            }
+        })?;
+
+        let mut operations = vec![Operation::Move {
+            to: vertices[geometry.edges[0].endpoints[0].0].into(),
+        }];
+
+        for (index, reversed) in edges {
+            let edge = &geometry.edges[index];
+            let end = if reversed {
+                vertices[edge.endpoints[0].0]
+            }
+            else {
+                vertices[edge.endpoints[1].0]
+            };
+
+            // the segment is resolved in pattern coordinates, so the control
+            // points are converted like vertices.
+            let mut cubics = match edge.segment {
+                Segment::Line { .. } => {
+                    operations.push(Operation::Line { to: end.into() });
+                    continue;
+                }
+                segment => {
+                    segment
+                        .approximate_cubics()
+                        .into_iter()
+                        .map(|(controls, to)| {
+                            (
+                                controls.map(|control| to_draft_f64(&control)),
+                                to_draft_f64(&to),
+                            )
+                        })
+                        .collect::<Vec<_>>()
+                }
+            };
+
+            if reversed {
+                // every cubic now ends where the one before it started, and the
+                // first one ends at the start of the edge, which is `end`.
+                let mut to = end;
+                for (controls, point) in &mut cubics {
+                    controls.reverse();
+                    std::mem::swap(point, &mut to);
+                }
+                cubics.reverse();
+            }
+
+            let count = cubics.len();
+            for (i, ([cp1, cp2], to)) in cubics.into_iter().enumerate() {
+                // the last curve ends exactly at the vertex.
+                let to = if i + 1 == count { end } else { to };
+
+                operations.push(Operation::Curve {
+                    cp1: cp1.into(),
+                    cp2: cp2.into(),
+                    to: to.into(),
+                });
+            }
+        }
+
+        operations.push(Operation::Close);
+
+        let mut paths = IndexMap::new();
+        paths.insert(OUTLINE_PATH.to_owned(), Path { ops: operations });
+
+        draft.parts.insert(
+            name.to_string(),
+            Part {
+                points,
+                paths,
+                placement: Some(Placement {
+                    translation: panel.translation,
+                    rotation: panel.rotation,
+                }),
+            },
+        );
+    }
+
+    log::debug!("exported freesewing draft: {}", converter.report());
+
+    Ok(draft)
+}
+
+pub fn from_draft(draft: &Draft) -> Result<Pattern, FreeSewingError> {
+    let mut pattern = Pattern {
+        panels: Default::default(),
+        panel_order: vec![],
+        stitches: vec![],
+    };
+
+    let mut converter = Converter::lossy();
+
+    for (name, part) in &draft.parts {
+        // use the seam path, or the first path if there is none.
+        let path = part
+            .paths
+            .get(OUTLINE_PATH)
+            .or_else(|| part.paths.values().next())
+            .ok_or_else(|| {
+                FreeSewingError::NoOutline {
+                    part: name.to_owned(),
+                }
+            })?;
+
+        let mut outline = Outline::default();
+
+        for (i, operation) in path.ops.iter().enumerate() {
+            match operation {
+                Operation::Move { to } if i == 0 => outline.line_to((*to).into()),
+                Operation::Move { .. } => {
+                    return Err(FreeSewingError::MultipleSubpaths {
+                        part: name.to_owned(),
+                    });
+                }
+                _ if i == 0 => {
+                    return Err(FreeSewingError::MissingMove {
+                        part: name.to_owned(),
+                    });
+                }
+                Operation::Line { to } => outline.line_to((*to).into()),
+                Operation::Curve { cp1, cp2, to } => {
+                    outline.curve_to((*cp1).into(), (*cp2).into(), (*to).into())
+                }
+                // anything after closing the path starts another subpath.
+                Operation::Close if i + 1 < path.ops.len() => {
+                    return Err(FreeSewingError::MultipleSubpaths {
+                        part: name.to_owned(),
+                    });
+                }
+                Operation::Close => {}
+            }
+        }
+
+        let mut panel = outline
+            .into_panel(1.0 / SCALE, &mut converter)
+            .map_err(|source| {
+                FreeSewingError::InvalidPart {
+                    part: name.to_owned(),
+                    source,
+                }
+            })?;
+
+        if let Some(placement) = &part.placement {
+            panel.translation = placement.translation;
+            panel.rotation = placement.rotation;
+        }
+
+        let name = PanelId::from(name.as_str());
+        pattern.panel_order.push(name.clone());
+        pattern.panels.insert(name, panel);
+    }
+
+    log::debug!("imported freesewing draft: {}", converter.report());
+
+    Ok(pattern)
+}
+
+pub fn from_str(json: &str) -> Result<Pattern, FreeSewingError> {
+    let draft: Draft = serde_json::from_str(json)?;
+    from_draft(&draft)
+}
+
+pub fn from_file(path: impl AsRef<FilePath>) -> Result<Pattern, Error> {
+    let json = std::fs::read_to_string(path)?;
+    Ok(from_str(&json)?)
+}
+
+pub fn to_string(pattern: &Pattern) -> Result<String, FreeSewingError> {
+    Ok(serde_json::to_string_pretty(&to_draft(pattern)?)?)
+}
+
+/// converts a point in pattern coordinates into draft coordinates.
+fn to_draft_f64(point: &Vector2<f64>) -> Vector2<f64> {
+    Vector2::new(point.x * SCALE, -point.y * SCALE)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::curve::Curve;
+
+    /// a 20 x 10 panel with a curved top, placed at (5, 0, 2).
+    fn pattern() -> Pattern {
+        serde_json::from_value(json!({
+            "panels": {
+                "front": {
+                    "translation": [5, 0, 2],
+                    "rotation": [0, 0, 0],
+                    "vertices": [[0, 0], [20, 0], [20, 10], [0, 10]],
+                    "edges": [
+                        {"endpoints": [0, 1]},
+                        {"endpoints": [1, 2]},
+                        {"endpoints": [2, 3], "curvature": [0.5, 0.3]},
+                        {"endpoints": [3, 0]},
+                    ],
+                },
+            },
+            "panel_order": ["front"],
+            "stitches": [],
+        }))
+        .unwrap()
+    }
+
+    fn point(x: f64, y: f64) -> Point {
+        Point { x, y }
+    }
+
+    fn assert_point_eq(actual: &Point, expected: &Point) {
+        assert!(
+            (actual.x - expected.x).abs() < 1e-9 && (actual.y - expected.y).abs() < 1e-9,
+            "expected {:?}, got {:?}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn exports_parts() {
+        let draft = to_draft(&pattern()).unwrap();
+        let part = &draft.parts["front"];
+
+        assert_eq!(part.points.len(), 4);
+        assert_point_eq(&part.points["v2"], &point(200.0, -100.0));
+
+        let ops = &part.paths[OUTLINE_PATH].ops;
+        assert_eq!(ops.len(), 6);
+        assert!(matches!(ops[0], Operation::Move { to } if to.x == 0.0 && to.y == 0.0));
+        assert!(matches!(ops[1], Operation::Line { .. }));
+        assert!(matches!(ops[2], Operation::Line { .. }));
+        match &ops[3] {
+            Operation::Curve { cp1, cp2, to } => {
+                // the quadratic control point is at (10, 4). the cubic control
+                // points are two thirds of the way from the ends to it, in
+                // millimeters and with the y axis pointing down.
+                assert_point_eq(cp1, &point(400.0 / 3.0, -60.0));
+                assert_point_eq(cp2, &point(200.0 / 3.0, -60.0));
+                assert_point_eq(to, &point(0.0, -100.0));
+            }
+            operation => panic!("expected a curve, got {:?}", operation),
+        }
+        assert!(matches!(ops[4], Operation::Line { .. }));
+        assert!(matches!(ops[5], Operation::Close));
+    }
+
+    #[test]
+    fn round_trip() {
+        let pattern = pattern();
+        let imported = from_draft(&to_draft(&pattern).unwrap()).unwrap();
+
+        assert_eq!(imported.panel_order, pattern.panel_order);
+
+        let original = &pattern.panels[&PanelId::from("front")];
+        let panel = &imported.panels[&PanelId::from("front")];
+        assert_eq!(panel.translation, original.translation);
+        assert_eq!(panel.rotation, original.rotation);
+        assert_eq!(panel.vertices, original.vertices);
+
+        let endpoints = panel
+            .edges
+            .iter()
+            .map(|edge| edge.endpoints)
+            .collect::<Vec<_>>();
+        let original_endpoints = original
+            .edges
+            .iter()
+            .map(|edge| edge.endpoints)
+            .collect::<Vec<_>>();
+        assert_eq!(endpoints, original_endpoints);
+
+        // the quadratic curve comes back as the same curve in cubic form.
+        assert_eq!(
+            panel.edges[2].curve,
+            Curve::Cubic([
+                [Decimal::new(3333, 4), Decimal::new(2, 1)],
+                [Decimal::new(6667, 4), Decimal::new(2, 1)]
+            ])
+        );
+    }
+
+    #[test]
+    fn drops_stitches() {
+        let mut pattern = pattern();
+        pattern.stitches = serde_json::from_value(json!([[
+            {"panel": "front", "edge": 1},
+            {"panel": "front", "edge": 3},
+        ]]))
+        .unwrap();
+
+        // the export doesn't fail, and still has all edges.
+        let draft = to_draft(&pattern).unwrap();
+        assert_eq!(draft.parts["front"].paths[OUTLINE_PATH].ops.len(), 6);
+
+        let imported = from_draft(&draft).unwrap();
+        assert!(imported.stitches.is_empty());
+        assert_eq!(imported.panels[&PanelId::from("front")].edges.len(), 4);
+    }
+
+    #[test]
+    fn exports_edges_in_loop_order() {
+        // the same panel as `pattern`, but the edges are shuffled and two of
+        // them point the other way. the curve is reversed with them.
+        let mut pattern = pattern();
+        pattern
+            .panels
+            .get_mut(&PanelId::from("front"))
+            .unwrap()
+            .edges = serde_json::from_value(json!([
+            {"endpoints": [0, 1]},
+            {"endpoints": [3, 2], "curvature": [0.5, -0.3]},
+            {"endpoints": [0, 3]},
+            {"endpoints": [1, 2]},
+        ]))
+        .unwrap();
+
+        let draft = to_draft(&pattern).unwrap();
+        let ops = &draft.parts["front"].paths[OUTLINE_PATH].ops;
+        assert_eq!(ops.len(), 6);
+        match &ops[3] {
+            Operation::Curve { cp1, cp2, to } => {
+                assert_point_eq(cp1, &point(400.0 / 3.0, -60.0));
+                assert_point_eq(cp2, &point(200.0 / 3.0, -60.0));
+                assert_point_eq(to, &point(0.0, -100.0));
+            }
+            operation => panic!("expected a curve, got {:?}", operation),
+        }
+
+        let imported = from_draft(&draft).unwrap();
+        let panel = &imported.panels[&PanelId::from("front")];
+        assert_eq!(
+            panel.vertices,
+            pattern.panels[&PanelId::from("front")].vertices
+        );
+        assert_eq!(
+            panel.edges[2].curve,
+            Curve::Cubic([
+                [Decimal::new(3333, 4), Decimal::new(2, 1)],
+                [Decimal::new(6667, 4), Decimal::new(2, 1)]
+            ])
+        );
+    }
+
+    #[test]
+    fn edges_must_form_a_loop() {
+        let mut pattern = pattern();
+        pattern
+            .panels
+            .get_mut(&PanelId::from("front"))
+            .unwrap()
+            .edges[3]
+            .endpoints[1] = crate::id::VertexIndex(2);
+
+        assert!(matches!(
+            to_draft(&pattern),
+            Err(FreeSewingError::Pattern(RenderError::NotALoop { .. }))
+        ));
+    }
+
+    #[test]
+    fn multiple_subpaths() {
+        let json = json!({
+            "parts": {
+                "front": {
+                    "paths": {
+                        "seam": {
+                            "ops": [
+                                {"type": "move", "to": {"x": 0, "y": 0}},
+                                {"type": "line", "to": {"x": 10, "y": 0}},
+                                {"type": "line", "to": {"x": 10, "y": 10}},
+                                {"type": "close"},
+                                {"type": "move", "to": {"x": 2, "y": 2}},
+                                {"type": "line", "to": {"x": 4, "y": 2}},
+                                {"type": "line", "to": {"x": 4, "y": 4}},
+                                {"type": "close"},
+                            ],
+                        },
+                    },
+                },
+            },
+        });
+
+        assert!(matches!(
+            from_str(&json.to_string()),
+            Err(FreeSewingError::MultipleSubpaths { part }) if part == "front"
+        ));
+    }
+
+    #[test]
+    fn missing_move() {
+        let json = json!({
+            "parts": {
+                "front": {
+                    "paths": {
+                        "seam": {
+                            "ops": [
+                                {"type": "line", "to": {"x": 10, "y": 0}},
+                                {"type": "line", "to": {"x": 10, "y": 10}},
+                                {"type": "close"},
+                            ],
+                        },
+                    },
+                },
+            },
+        });
+
+        assert!(matches!(
+            from_str(&json.to_string()),
+            Err(FreeSewingError::MissingMove { .. })
+        ));
+    }
+}
@@ -0,0 +1,249 @@
+//! conversion between [`crate::pattern::Pattern`] and pattern formats of other
+//! tools.
+
+pub mod freesewing;
+#[cfg(feature = "seamly2d")]
+pub mod seamly2d;
+
+use nalgebra::{
+    Vector2,
+    Vector3,
+};
+use rust_decimal::Decimal;
+
+use crate::{
+    curve::Curve,
+    geometry::{
+        ConversionError,
+        Converter,
+    },
+    id::{
+        EdgeIndex,
+        VertexIndex,
+    },
+    pattern::{
+        Edge,
+        Panel,
+    },
+};
+
+/// number of decimal places kept when converting coordinates to decimals.
+const DECIMAL_PLACES: u32 = 4;
+
+/// points closer than this are considered the same vertex.
+const EPSILON: f64 = 1e-6;
+
+#[derive(Debug, thiserror::Error)]
+pub enum OutlineError {
+    #[error("outline has less than 3 vertices")]
+    Degenerate,
+    #[error("curved edge {edge} has the same start and end point")]
+    DegenerateCurve { edge: EdgeIndex },
+    #[error("conversion error")]
+    Conversion(#[from] ConversionError),
+}
+
+/// a point on an [`Outline`].
+#[derive(Debug)]
+struct Anchor {
+    position: Vector2<f64>,
+
+    /// control points of the cubic curve ending at this anchor. if `None`, the
+    /// outline reaches this anchor with a straight line.
+    incoming: Option<[Vector2<f64>; 2]>,
+}
+
+/// closed outline made of straight lines and cubic curves. formats that
+/// describe panels this way build an outline and then convert it into a
+/// [`Panel`].
+///
+/// the outline is closed implicitly, but it may also end with its first
+/// point.
+#[derive(Debug, Default)]
+pub(crate) struct Outline {
+    anchors: Vec<Anchor>,
+}
+
+impl Outline {
+    /// adds a straight line to `position`. the first point of the outline is
+    /// also added with this.
+    pub fn line_to(&mut self, position: Vector2<f64>) {
+        self.push(position, None);
+    }
+
+    /// adds a cubic curve to `position`.
+    pub fn curve_to(
+        &mut self,
+        control_start: Vector2<f64>,
+        control_end: Vector2<f64>,
+        position: Vector2<f64>,
+    ) {
+        self.push(position, Some([control_start, control_end]));
+    }
+
+    fn push(&mut self, position: Vector2<f64>, incoming: Option<[Vector2<f64>; 2]>) {
+        if let Some(last) = self.anchors.last() {
+            if same_point(&last.position, &position) {
+                return;
+            }
+        }
+
+        self.anchors.push(Anchor { position, incoming });
+    }
+
+    /// converts the outline into a panel.
+    ///
+    /// `scale` converts the outline's unit into centimeters. the outline's y
+    /// axis is expected to point down, so it's flipped. coordinates are
+    /// rounded to [`DECIMAL_PLACES`] by `converter`. this fails if the ends of
+    /// a curve are the same point after rounding, since the curve's control
+    /// points can't be made relative to it then.
+    pub fn into_panel(
+        mut self,
+        scale: f64,
+        converter: &mut Converter,
+    ) -> Result<Panel, OutlineError> {
+        let transform = |point: &Vector2<f64>| Vector2::new(point.x * scale, -point.y * scale);
+
+        for anchor in &mut self.anchors {
+            anchor.position = transform(&anchor.position);
+            anchor.incoming = anchor
+                .incoming
+                .map(|controls| controls.map(|control| transform(&control)));
+        }
+
+        let mut anchors = self.anchors;
+
+        if anchors.len() > 1
+            && same_point(&anchors[0].position, &anchors[anchors.len() - 1].position)
+        {
+            let last = anchors.pop().unwrap();
+            if anchors[0].incoming.is_none() {
+                anchors[0].incoming = last.incoming;
+            }
+        }
+
+        if anchors.len() < 3 {
+            return Err(OutlineError::Degenerate);
+        }
+
+        let vertices = anchors
+            .iter()
+            .map(|anchor| converter.to_decimal_point(&anchor.position, DECIMAL_PLACES))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let edges = (0..anchors.len())
+            .map(|from| {
+                let to = (from + 1) % anchors.len();
+
+                let curve = match anchors[to].incoming {
+                    Some(controls) => {
+                        let mut relative = [[Decimal::ZERO; 2]; 2];
+                        for (relative, control) in relative.iter_mut().zip(&controls) {
+                            let control = converter.to_decimal_point(control, DECIMAL_PLACES)?;
+                            *relative = Edge::relative_control_point(
+                                &vertices[from],
+                                &vertices[to],
+                                &control,
+                            )
+                            .ok_or(OutlineError::DegenerateCurve {
+                                edge: EdgeIndex(from),
+                            })?
+                            .map(|value| value.round_dp(DECIMAL_PLACES));
+                        }
+                        Curve::Cubic(relative)
+                    }
+                    None => Curve::Line,
+                };
+
+                Ok(Edge {
+                    endpoints: [VertexIndex(from), VertexIndex(to)],
+                    curve,
+                })
+            })
+            .collect::<Result<Vec<_>, OutlineError>>()?;
+
+        Ok(Panel {
+            translation: Vector3::zeros(),
+            rotation: Vector3::zeros(),
+            edges,
+            vertices,
+        })
+    }
+}
+
+pub(crate) fn same_point(a: &Vector2<f64>, b: &Vector2<f64>) -> bool {
+    (a - b).norm() < EPSILON
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_outline() {
+        let mut outline = Outline::default();
+        outline.line_to(Vector2::new(0.0, 0.0));
+        outline.line_to(Vector2::new(10.0, 0.0));
+        outline.curve_to(
+            Vector2::new(10.0, 2.5),
+            Vector2::new(10.0, 7.5),
+            Vector2::new(10.0, 10.0),
+        );
+        outline.line_to(Vector2::new(0.0, 0.0));
+
+        let panel = outline.into_panel(0.1, &mut Converter::lossy()).unwrap();
+
+        // the y axis is flipped and the closing point is dropped.
+        assert_eq!(
+            panel.vertices,
+            [
+                Vector2::new(Decimal::ZERO, Decimal::ZERO),
+                Vector2::new(Decimal::ONE, Decimal::ZERO),
+                Vector2::new(Decimal::ONE, Decimal::NEGATIVE_ONE),
+            ]
+        );
+        assert_eq!(panel.edges[0].curve, Curve::Line);
+        assert_eq!(
+            panel.edges[1].curve,
+            Curve::Cubic([
+                [Decimal::new(25, 2), Decimal::ZERO],
+                [Decimal::new(75, 2), Decimal::ZERO],
+            ])
+        );
+        assert_eq!(panel.edges[2].curve, Curve::Line);
+    }
+
+    #[test]
+    fn degenerate_outline() {
+        let mut outline = Outline::default();
+        outline.line_to(Vector2::new(0.0, 0.0));
+        outline.line_to(Vector2::new(10.0, 0.0));
+        outline.line_to(Vector2::new(0.0, 0.0));
+
+        assert!(matches!(
+            outline.into_panel(1.0, &mut Converter::lossy()),
+            Err(OutlineError::Degenerate)
+        ));
+    }
+
+    #[test]
+    fn curve_collapses_when_rounded() {
+        // the ends of the curve are further apart than `EPSILON`, but the same
+        // after rounding to `DECIMAL_PLACES`.
+        let mut outline = Outline::default();
+        outline.line_to(Vector2::new(0.0, 0.0));
+        outline.line_to(Vector2::new(10.0, 0.0));
+        outline.curve_to(
+            Vector2::new(11.0, 1.0),
+            Vector2::new(11.0, -1.0),
+            Vector2::new(10.00001, 0.0),
+        );
+        outline.line_to(Vector2::new(0.0, 10.0));
+
+        assert!(matches!(
+            outline.into_panel(1.0, &mut Converter::lossy()),
+            Err(OutlineError::DegenerateCurve { edge: EdgeIndex(1) })
+        ));
+    }
+}
@@ -0,0 +1,833 @@
+//! importer for Seamly2D and Valentina pattern files (`.sm2d`, `.val`).
+//!
+//! only the geometry of the pattern is read: points, lines, splines and
+//! pieces. every piece becomes a [`Panel`] and the outline of the piece
+//! becomes its edge loop. lines aren't objects on their own in this format,
+//! they are implied by two consecutive points of a piece. splines become cubic
+//! edges.
+//!
+//! coordinates are converted to centimeters (i.e. `units_in_meter = 100`) and
+//! the y axis is flipped, so that it points up like in the template format.
+
+use std::{
+    collections::HashMap,
+    path::Path,
+};
+
+use nalgebra::Vector2;
+use roxmltree::{
+    Document,
+    Node,
+};
+
+use crate::{
+    convert::{
+        Outline,
+        OutlineError,
+    },
+    error::Error,
+    geometry::Converter,
+    id::PanelId,
+    pattern::{
+        Panel,
+        Pattern,
+    },
+};
+
+/// lines with a smaller cross product of their directions are considered
+/// parallel.
+const EPSILON: f64 = 1e-9;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Seamly2dError {
+    #[error("xml error")]
+    Xml(#[from] roxmltree::Error),
+    #[error("unknown unit: {unit}")]
+    UnknownUnit { unit: String },
+    #[error("missing attribute: <{element}> has no `{attribute}`")]
+    MissingAttribute {
+        element: String,
+        attribute: &'static str,
+    },
+    #[error("invalid number: {value}")]
+    InvalidNumber { value: String },
+    #[error("no such object: {id}")]
+    NoSuchObject { id: u32 },
+    #[error("object {id} is not a {expected}")]
+    UnexpectedObject { id: u32, expected: &'static str },
+    #[error("object {id} can't be used: {reason}")]
+    InvalidObject { id: u32, reason: String },
+    #[error("unsupported tool: <{element} type=\"{kind}\">")]
+    UnsupportedTool { element: String, kind: String },
+    #[error("invalid formula `{formula}`: {reason}")]
+    InvalidFormula { formula: String, reason: String },
+    #[error("unknown variable: {name}")]
+    UnknownVariable { name: String },
+    #[error("lines don't intersect: {id}")]
+    NoIntersection { id: u32 },
+    #[error("invalid piece: {piece}")]
+    InvalidPiece {
+        piece: String,
+        #[source]
+        source: OutlineError,
+    },
+}
+
+#[derive(Clone, Debug)]
+enum Object {
+    Point(Vector2<f64>),
+
+    /// cubic bezier segments, each given as start point, two control points
+    /// and end point.
+    Spline(Vec<[Vector2<f64>; 4]>),
+
+    /// the tool couldn't be evaluated. this is only an error if a piece uses
+    /// the object.
+    Invalid {
+        reason: String,
+    },
+}
+
+#[derive(Debug, Default)]
+struct Context {
+    /// all objects by their id. the coordinates are in the file's unit and
+    /// with the y axis pointing down.
+    objects: HashMap<u32, Object>,
+
+    /// point positions by their name. used for `Line_A_B` variables.
+    names: HashMap<String, Vector2<f64>>,
+
+    /// values of increments (`#name`).
+    increments: HashMap<String, f64>,
+
+    /// converts piece coordinates into decimals.
+    converter: Converter,
+}
+
+pub fn from_str(xml: &str) -> Result<Pattern, Seamly2dError> {
+    let document = Document::parse(xml)?;
+    let root = document.root_element();
+    let scale = unit_scale(root)?;
+
+    let mut context = Context::default();
+
+    for increment in root
+        .descendants()
+        .filter(|node| node.has_tag_name("increment"))
+    {
+        let name = attribute(increment, "name")?;
+        let value = context.evaluate(attribute(increment, "formula")?)?;
+        context.increments.insert(name.to_owned(), value);
+    }
+
+    let draws = root
+        .children()
+        .filter(|node| node.has_tag_name("draw"))
+        .collect::<Vec<_>>();
+
+    // object ids are global, so all draws are evaluated before any piece is
+    // converted.
+    for draw in &draws {
+        for section in draw.children().filter(Node::is_element) {
+            match section.tag_name().name() {
+                "calculation" => {
+                    for node in section.children().filter(Node::is_element) {
+                        context.calculation(node)?;
+                    }
+                }
+                "modeling" => {
+                    for node in section.children().filter(Node::is_element) {
+                        context.modeling(node)?;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let mut pattern = Pattern {
+        panels: Default::default(),
+        panel_order: vec![],
+        stitches: vec![],
+    };
+
+    for draw in &draws {
+        let pieces = draw
+            .children()
+            .filter(|node| node.has_tag_name("details") || node.has_tag_name("pieces"))
+            .flat_map(|node| node.children())
+            .filter(|node| node.has_tag_name("detail") || node.has_tag_name("piece"));
+
+        for piece in pieces {
+            let mut name = piece
+                .attribute("name")
+                .map(ToOwned::to_owned)
+                .unwrap_or_else(|| format!("piece {}", piece.attribute("id").unwrap_or("?")));
+
+            let mut suffix = 2;
+            while pattern.panels.contains_key(name.as_str()) {
+                name = format!("{} {}", piece.attribute("name").unwrap_or("piece"), suffix);
+                suffix += 1;
+            }
+
+            let panel = context.piece(piece, &name, scale)?;
+
+            let name = PanelId(name);
+            pattern.panel_order.push(name.clone());
+            pattern.panels.insert(name, panel);
+        }
+    }
+
+    log::debug!("imported seamly2d pattern: {}", context.converter.report());
+
+    Ok(pattern)
+}
+
+pub fn from_file(path: impl AsRef<Path>) -> Result<Pattern, Error> {
+    let xml = std::fs::read_to_string(path)?;
+    Ok(from_str(&xml)?)
+}
+
+impl Context {
+    fn object(&self, id: u32) -> Result<&Object, Seamly2dError> {
+        self.objects
+            .get(&id)
+            .ok_or(Seamly2dError::NoSuchObject { id })
+    }
+
+    fn point(&self, id: u32) -> Result<Vector2<f64>, Seamly2dError> {
+        match self.object(id)? {
+            Object::Point(position) => Ok(*position),
+            Object::Spline(_) => {
+                Err(Seamly2dError::UnexpectedObject {
+                    id,
+                    expected: "point",
+                })
+            }
+            Object::Invalid { reason } => {
+                Err(Seamly2dError::InvalidObject {
+                    id,
+                    reason: reason.clone(),
+                })
+            }
+        }
+    }
+
+    fn point_attribute(
+        &self,
+        node: Node,
+        name: &'static str,
+    ) -> Result<Vector2<f64>, Seamly2dError> {
+        self.point(id_attribute(node, name)?)
+    }
+
+    fn formula_attribute(&self, node: Node, name: &'static str) -> Result<f64, Seamly2dError> {
+        self.evaluate(attribute(node, name)?)
+    }
+
+    /// copies the object a node of the `<modeling>` section refers to. like
+    /// with [`Context::calculation`], a missing object is only an error if a
+    /// piece uses the node.
+    fn modeling(&mut self, node: Node) -> Result<(), Seamly2dError> {
+        let id = id_attribute(node, "id")?;
+        let source = id_attribute(node, "idObject")?;
+
+        let object = self.object(source).cloned().unwrap_or_else(|e| {
+            log::warn!("can't copy object {} into {}: {}", source, id, e);
+            Object::Invalid {
+                reason: e.to_string(),
+            }
+        });
+        self.objects.insert(id, object);
+
+        Ok(())
+    }
+
+    /// evaluates a tool from the `<calculation>` section and stores the
+    /// resulting object. tools that fail to evaluate are stored as
+    /// [`Object::Invalid`], so that patterns only fail to import if a piece
+    /// depends on them.
+    fn calculation(&mut self, node: Node) -> Result<(), Seamly2dError> {
+        let object = match node.tag_name().name() {
+            "point" => self.calculate_point(node).map(Object::Point),
+            "spline" => self.calculate_spline(node).map(Object::Spline),
+            // lines are only drawn, they don't create objects.
+            "line" => return Ok(()),
+            element => {
+                Err(Seamly2dError::UnsupportedTool {
+                    element: element.to_owned(),
+                    kind: node.attribute("type").unwrap_or_default().to_owned(),
+                })
+            }
+        };
+
+        let id = id_attribute(node, "id")?;
+
+        let object = object.unwrap_or_else(|e| {
+            log::warn!("can't evaluate object {}: {}", id, e);
+            Object::Invalid {
+                reason: e.to_string(),
+            }
+        });
+
+        if let (Object::Point(position), Some(name)) = (&object, node.attribute("name")) {
+            self.names.insert(name.to_owned(), *position);
+        }
+
+        self.objects.insert(id, object);
+
+        Ok(())
+    }
+
+    fn calculate_point(&self, node: Node) -> Result<Vector2<f64>, Seamly2dError> {
+        let kind = node.attribute("type").unwrap_or_default();
+
+        match kind {
+            "single" => {
+                Ok(Vector2::new(
+                    number_attribute(node, "x")?,
+                    number_attribute(node, "y")?,
+                ))
+            }
+            "endLine" => {
+                let base = self.point_attribute(node, "basePoint")?;
+                let angle = self.formula_attribute(node, "angle")?;
+                let length = self.formula_attribute(node, "length")?;
+                Ok(base + polar(angle, length))
+            }
+            "alongLine" => {
+                let first = self.point_attribute(node, "firstPoint")?;
+                let second = self.point_attribute(node, "secondPoint")?;
+                let length = self.formula_attribute(node, "length")?;
+                Ok(first + polar(angle_between(&first, &second), length))
+            }
+            "normal" => {
+                let first = self.point_attribute(node, "firstPoint")?;
+                let second = self.point_attribute(node, "secondPoint")?;
+                let length = self.formula_attribute(node, "length")?;
+                let angle = number_attribute(node, "angle").unwrap_or_default();
+                Ok(first + polar(angle_between(&first, &second) + 90.0 + angle, length))
+            }
+            "pointOfIntersection" => {
+                let first = self.point_attribute(node, "firstPoint")?;
+                let second = self.point_attribute(node, "secondPoint")?;
+                Ok(Vector2::new(first.x, second.y))
+            }
+            "lineIntersect" => {
+                let a = self.point_attribute(node, "p1Line1")?;
+                let b = self.point_attribute(node, "p2Line1")?;
+                let c = self.point_attribute(node, "p1Line2")?;
+                let d = self.point_attribute(node, "p2Line2")?;
+
+                let ab = b - a;
+                let cd = d - c;
+                let denominator = ab.perp(&cd);
+                if denominator.abs() < EPSILON {
+                    return Err(Seamly2dError::NoIntersection {
+                        id: id_attribute(node, "id")?,
+                    });
+                }
+
+                let t = (c - a).perp(&cd) / denominator;
+                Ok(a + ab * t)
+            }
+            _ => {
+                Err(Seamly2dError::UnsupportedTool {
+                    element: "point".to_owned(),
+                    kind: kind.to_owned(),
+                })
+            }
+        }
+    }
+
+    fn calculate_spline(&self, node: Node) -> Result<Vec<[Vector2<f64>; 4]>, Seamly2dError> {
+        let kind = node.attribute("type").unwrap_or_default();
+
+        match kind {
+            "simpleInteractive" => {
+                let start = self.point_attribute(node, "point1")?;
+                let end = self.point_attribute(node, "point4")?;
+
+                let control_start = start
+                    + polar(
+                        self.formula_attribute(node, "angle1")?,
+                        self.formula_attribute(node, "length1")?,
+                    );
+                let control_end = end
+                    + polar(
+                        self.formula_attribute(node, "angle2")?,
+                        self.formula_attribute(node, "length2")?,
+                    );
+
+                Ok(vec![[start, control_start, control_end, end]])
+            }
+            "pathInteractive" => {
+                // every path point has an incoming (`1`) and outgoing (`2`) control point.
+                let path_points = node
+                    .children()
+                    .filter(|node| node.has_tag_name("pathPoint"))
+                    .map(|node| {
+                        let position = self.point_attribute(node, "pSpline")?;
+                        let incoming = position
+                            + polar(
+                                self.formula_attribute(node, "angle1")?,
+                                self.formula_attribute(node, "length1")?,
+                            );
+                        let outgoing = position
+                            + polar(
+                                self.formula_attribute(node, "angle2")?,
+                                self.formula_attribute(node, "length2")?,
+                            );
+                        Ok((position, incoming, outgoing))
+                    })
+                    .collect::<Result<Vec<_>, Seamly2dError>>()?;
+
+                Ok(path_points
+                    .windows(2)
+                    .map(|pair| [pair[0].0, pair[0].2, pair[1].1, pair[1].0])
+                    .collect())
+            }
+            _ => {
+                Err(Seamly2dError::UnsupportedTool {
+                    element: "spline".to_owned(),
+                    kind: kind.to_owned(),
+                })
+            }
+        }
+    }
+
+    fn piece(&mut self, node: Node, name: &str, scale: f64) -> Result<Panel, Seamly2dError> {
+        let nodes = node
+            .children()
+            .filter(|node| node.has_tag_name("nodes"))
+            .flat_map(|node| node.children())
+            .filter(|node| node.has_tag_name("node"))
+            .filter(|node| node.attribute("excluded") != Some("true"));
+
+        let mut outline = Outline::default();
+
+        for node in nodes {
+            let id = id_attribute(node, "idObject")?;
+
+            match self.object(id)? {
+                Object::Point(position) => {
+                    outline.line_to(*position);
+                }
+                Object::Spline(segments) => {
+                    let mut segments = segments.clone();
+                    if matches!(node.attribute("reverse"), Some("1") | Some("true")) {
+                        segments.reverse();
+                        for segment in &mut segments {
+                            segment.reverse();
+                        }
+                    }
+
+                    for [start, control_start, control_end, end] in segments {
+                        outline.line_to(start);
+                        outline.curve_to(control_start, control_end, end);
+                    }
+                }
+                Object::Invalid { reason } => {
+                    return Err(Seamly2dError::InvalidObject {
+                        id,
+                        reason: reason.clone(),
+                    });
+                }
+            }
+        }
+
+        outline
+            .into_panel(scale, &mut self.converter)
+            .map_err(|source| {
+                Seamly2dError::InvalidPiece {
+                    piece: name.to_owned(),
+                    source,
+                }
+            })
+    }
+
+    /// evaluates a formula. only numbers, arithmetic (`+`, `-`, `*`, `/`, `^`
+    /// and parentheses), `sqrt`, `abs`, increments and `Line_A_B` (the
+    /// distance between the points named `A` and `B`) are supported.
+    fn evaluate(&self, formula: &str) -> Result<f64, Seamly2dError> {
+        let mut parser = FormulaParser {
+            context: self,
+            formula,
+            position: 0,
+        };
+
+        let value = parser.expression()?;
+
+        parser.skip_whitespace();
+        if parser.position < formula.len() {
+            return Err(parser.error("unexpected input"));
+        }
+
+        Ok(value)
+    }
+
+    fn variable(&self, name: &str) -> Result<f64, Seamly2dError> {
+        if let Some(value) = self.increments.get(name) {
+            return Ok(*value);
+        }
+
+        if let Some(points) = name.strip_prefix("Line_") {
+            // point names can contain underscores too, so try every split.
+            for (i, _) in points.match_indices('_') {
+                if let (Some(a), Some(b)) = (
+                    self.names.get(&points[..i]),
+                    self.names.get(&points[i + 1..]),
+                ) {
+                    return Ok((b - a).norm());
+                }
+            }
+        }
+
+        Err(Seamly2dError::UnknownVariable {
+            name: name.to_owned(),
+        })
+    }
+}
+
+struct FormulaParser<'a> {
+    context: &'a Context,
+    formula: &'a str,
+    position: usize,
+}
+
+impl<'a> FormulaParser<'a> {
+    fn error(&self, reason: &str) -> Seamly2dError {
+        Seamly2dError::InvalidFormula {
+            formula: self.formula.to_owned(),
+            reason: format!("{} at {}", reason, self.position),
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.formula[self.position..].chars().next()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(c) = self.peek().filter(|c| c.is_whitespace()) {
+            self.position += c.len_utf8();
+        }
+    }
+
+    /// skips whitespace and consumes `expected`, if it's the next character.
+    fn eat(&mut self, expected: char) -> bool {
+        self.skip_whitespace();
+        if self.peek() == Some(expected) {
+            self.position += expected.len_utf8();
+            true
+        }
+        else {
+            false
+        }
+    }
+
+    fn take_while(&mut self, predicate: impl Fn(char) -> bool) -> &'a str {
+        let start = self.position;
+        while let Some(c) = self.peek().filter(|c| predicate(*c)) {
+            self.position += c.len_utf8();
+        }
+        &self.formula[start..self.position]
+    }
+
+    fn expression(&mut self) -> Result<f64, Seamly2dError> {
+        let mut value = self.term()?;
+        loop {
+            if self.eat('+') {
+                value += self.term()?;
+            }
+            else if self.eat('-') {
+                value -= self.term()?;
+            }
+            else {
+                return Ok(value);
+            }
+        }
+    }
+
+    fn term(&mut self) -> Result<f64, Seamly2dError> {
+        let mut value = self.factor()?;
+        loop {
+            if self.eat('*') {
+                value *= self.factor()?;
+            }
+            else if self.eat('/') {
+                value /= self.factor()?;
+            }
+            else {
+                return Ok(value);
+            }
+        }
+    }
+
+    fn factor(&mut self) -> Result<f64, Seamly2dError> {
+        let base = self.unary()?;
+        if self.eat('^') {
+            Ok(base.powf(self.factor()?))
+        }
+        else {
+            Ok(base)
+        }
+    }
+
+    fn unary(&mut self) -> Result<f64, Seamly2dError> {
+        if self.eat('-') {
+            Ok(-self.unary()?)
+        }
+        else if self.eat('+') {
+            self.unary()
+        }
+        else {
+            self.primary()
+        }
+    }
+
+    fn primary(&mut self) -> Result<f64, Seamly2dError> {
+        if self.eat('(') {
+            let value = self.expression()?;
+            if !self.eat(')') {
+                return Err(self.error("expected `)`"));
+            }
+            return Ok(value);
+        }
+
+        match self.peek() {
+            Some(c) if c.is_ascii_digit() || c == '.' => {
+                let number = self.take_while(|c| c.is_ascii_digit() || c == '.');
+                number.parse().map_err(|_| self.error("invalid number"))
+            }
+            Some(c) if c.is_alphabetic() || c == '_' || c == '#' || c == '@' => {
+                let name =
+                    self.take_while(|c| c.is_alphanumeric() || c == '_' || c == '#' || c == '@');
+
+                if self.eat('(') {
+                    let argument = self.expression()?;
+                    if !self.eat(')') {
+                        return Err(self.error("expected `)`"));
+                    }
+
+                    match name {
+                        "sqrt" => Ok(argument.sqrt()),
+                        "abs" => Ok(argument.abs()),
+                        _ => Err(self.error("unknown function")),
+                    }
+                }
+                else {
+                    self.context.variable(name)
+                }
+            }
+            _ => Err(self.error("expected a number")),
+        }
+    }
+}
+
+/// offset by `length` in the direction `angle` (in degrees, counter-clockwise,
+/// y axis pointing down).
+fn polar(angle: f64, length: f64) -> Vector2<f64> {
+    let angle = angle.to_radians();
+    Vector2::new(angle.cos(), -angle.sin()) * length
+}
+
+/// angle (in degrees) of the line from `a` to `b`, as used by [`polar`].
+fn angle_between(a: &Vector2<f64>, b: &Vector2<f64>) -> f64 {
+    (a.y - b.y).atan2(b.x - a.x).to_degrees()
+}
+
+/// factor to convert from the pattern's unit to centimeters.
+fn unit_scale(root: Node) -> Result<f64, Seamly2dError> {
+    let unit = root
+        .children()
+        .find(|node| node.has_tag_name("unit"))
+        .and_then(|node| node.text())
+        .unwrap_or("cm")
+        .trim();
+
+    match unit {
+        "mm" => Ok(0.1),
+        "cm" => Ok(1.0),
+        "inch" => Ok(2.54),
+        _ => {
+            Err(Seamly2dError::UnknownUnit {
+                unit: unit.to_owned(),
+            })
+        }
+    }
+}
+
+fn attribute<'a>(node: Node<'a, '_>, name: &'static str) -> Result<&'a str, Seamly2dError> {
+    node.attribute(name).ok_or_else(|| {
+        Seamly2dError::MissingAttribute {
+            element: node.tag_name().name().to_owned(),
+            attribute: name,
+        }
+    })
+}
+
+fn number_attribute(node: Node, name: &'static str) -> Result<f64, Seamly2dError> {
+    let value = attribute(node, name)?;
+    value.trim().parse().map_err(|_| {
+        Seamly2dError::InvalidNumber {
+            value: value.to_owned(),
+        }
+    })
+}
+
+fn id_attribute(node: Node, name: &'static str) -> Result<u32, Seamly2dError> {
+    let value = attribute(node, name)?;
+    value.trim().parse().map_err(|_| {
+        Seamly2dError::InvalidNumber {
+            value: value.to_owned(),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal::Decimal;
+
+    use super::*;
+    use crate::curve::Curve;
+
+    /// a rectangle of 20 x 10 cm with a curved bottom, in millimeters. the
+    /// arc isn't supported, but no piece uses it.
+    const PATTERN: &str = r##"<?xml version="1.0" encoding="UTF-8"?>
+<pattern>
+    <version>0.4.0</version>
+    <unit>mm</unit>
+    <increments><increment name="#w" formula="100"/></increments>
+    <draw name="p">
+        <calculation>
+            <point id="1" name="A" type="single" x="0" y="0"/>
+            <point id="2" name="B" type="endLine" basePoint="1" angle="0" length="#w*2"/>
+            <point id="3" name="C" type="endLine" basePoint="2" angle="270" length="Line_A_B/2"/>
+            <point id="4" name="D" type="endLine" basePoint="1" angle="270" length="100"/>
+            <line id="5" firstPoint="1" secondPoint="2"/>
+            <spline id="6" type="simpleInteractive" point1="3" point4="4" angle1="270" angle2="270" length1="50" length2="50"/>
+            <arc id="9" type="simple" center="1"/>
+        </calculation>
+        <modeling>
+            <point id="7" idObject="1" type="modeling"/>
+            <spline id="8" idObject="6" type="modelingSpline"/>
+        </modeling>
+        <details>
+            <detail id="10" name="Front"><nodes>
+                <node idObject="7" type="NodePoint"/>
+                <node idObject="2" type="NodePoint"/>
+                <node idObject="8" type="NodeSpline"/>
+            </nodes></detail>
+        </details>
+    </draw>
+</pattern>"##;
+
+    fn decimal(value: i64) -> Decimal {
+        Decimal::from(value)
+    }
+
+    #[test]
+    fn imports_pieces() {
+        let pattern = from_str(PATTERN).unwrap();
+        assert_eq!(pattern.panel_order, vec![PanelId::from("Front")]);
+
+        let panel = &pattern.panels[&PanelId::from("Front")];
+        assert_eq!(
+            panel.vertices,
+            vec![
+                Vector2::new(decimal(0), decimal(0)),
+                Vector2::new(decimal(20), decimal(0)),
+                Vector2::new(decimal(20), decimal(-10)),
+                Vector2::new(decimal(0), decimal(-10)),
+            ]
+        );
+
+        let curves = panel
+            .edges
+            .iter()
+            .map(|edge| edge.curve.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            curves,
+            vec![
+                Curve::Line,
+                Curve::Line,
+                Curve::Cubic([
+                    [decimal(0), Decimal::new(25, 2)],
+                    [decimal(1), Decimal::new(25, 2)]
+                ]),
+                Curve::Line,
+            ]
+        );
+    }
+
+    #[test]
+    fn writes_coordinates_as_numbers() {
+        let pattern = from_str(PATTERN).unwrap();
+        let json = serde_json::to_value(&pattern).unwrap();
+
+        let vertex = &json["panels"]["Front"]["vertices"][1];
+        assert_eq!(vertex, &serde_json::json!([20.0, 0.0]));
+    }
+
+    #[test]
+    fn evaluates_formulas() {
+        let mut context = Context::default();
+        context.increments.insert("#w".to_owned(), 4.0);
+
+        assert_eq!(context.evaluate("1 + 2 * 3").unwrap(), 7.0);
+        assert_eq!(context.evaluate("(1 + 2) * 3").unwrap(), 9.0);
+        assert_eq!(context.evaluate("-#w / 2 + 2^3").unwrap(), 6.0);
+        assert_eq!(context.evaluate("sqrt(#w) + abs(-1)").unwrap(), 3.0);
+        assert!(context.evaluate("1 +").is_err());
+        assert!(context.evaluate("1 2").is_err());
+    }
+
+    #[test]
+    fn unknown_unit() {
+        let xml = PATTERN.replace("<unit>mm</unit>", "<unit>furlong</unit>");
+        assert!(matches!(
+            from_str(&xml),
+            Err(Seamly2dError::UnknownUnit { unit }) if unit == "furlong"
+        ));
+    }
+
+    #[test]
+    fn piece_uses_unsupported_tool() {
+        let xml = PATTERN.replace(
+            r#"<node idObject="2" type="NodePoint"/>"#,
+            r#"<node idObject="9" type="NodePoint"/>"#,
+        );
+        assert!(matches!(
+            from_str(&xml),
+            Err(Seamly2dError::InvalidObject { id: 9, .. })
+        ));
+    }
+
+    #[test]
+    fn unknown_variable() {
+        let xml = PATTERN.replace("#w*2", "#h*2");
+        assert!(matches!(
+            from_str(&xml),
+            Err(Seamly2dError::InvalidObject { id: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn unused_modeling_node_without_object() {
+        let xml = PATTERN.replace(
+            "</modeling>",
+            r#"<point id="11" idObject="99" type="modeling"/></modeling>"#,
+        );
+        assert!(from_str(&xml).is_ok());
+
+        let xml = xml.replace(
+            r#"<node idObject="2" type="NodePoint"/>"#,
+            r#"<node idObject="11" type="NodePoint"/>"#,
+        );
+        assert!(matches!(
+            from_str(&xml),
+            Err(Seamly2dError::InvalidObject { id: 11, reason }) if reason == "no such object: 99"
+        ));
+    }
+}
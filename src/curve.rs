@@ -0,0 +1,821 @@
+//! the kinds of curves an [`Edge`] can describe, and
+//! their geometry.
+//!
+//! control points are stored in relative curvature coordinates (see
+//! [`Edge::relative_control_point`]), so that curves follow their endpoints
+//! when those are moved. for geometry, a curve is resolved into a [`Segment`]
+//! with absolute `f64` coordinates.
+
+use std::f64::consts::{
+    FRAC_PI_2,
+    TAU,
+};
+
+use nalgebra::Vector2;
+use rust_decimal::Decimal;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::{
+    geometry::{
+        ConversionError,
+        Converter,
+    },
+    pattern::Edge,
+};
+
+/// number of line segments used to measure the length of bezier curves.
+const LENGTH_SAMPLES: usize = 64;
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(from = "Option<RawCurve>", into = "Option<RawCurve>")]
+pub enum Curve {
+    /// straight line.
+    #[default]
+    Line,
+
+    /// quadratic bezier curve with one control point.
+    Quadratic([Decimal; 2]),
+
+    /// cubic bezier curve with two control points. the first control point
+    /// belongs to the start of the edge.
+    Cubic([[Decimal; 2]; 2]),
+
+    /// circular arc. the flags have the same meaning as in SVG: `large_arc`
+    /// selects the arc that spans more than 180°, `sweep` selects the arc that
+    /// is traced with increasing angle.
+    ///
+    /// if the radius is too small to connect the endpoints, it's scaled up
+    /// until it does.
+    Arc {
+        radius: Decimal,
+        large_arc: bool,
+        sweep: bool,
+    },
+}
+
+/// json representation of a curve. quadratic curves are written as a bare
+/// control point, like in the template format, other curves are tagged with
+/// their type.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+enum RawCurve {
+    Quadratic([Decimal; 2]),
+    Tagged(TaggedCurve),
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum TaggedCurve {
+    Quadratic {
+        control: [Decimal; 2],
+    },
+    Cubic {
+        controls: [[Decimal; 2]; 2],
+    },
+    Arc {
+        radius: Decimal,
+        large_arc: bool,
+        sweep: bool,
+    },
+}
+
+impl From<Option<RawCurve>> for Curve {
+    fn from(raw: Option<RawCurve>) -> Self {
+        match raw {
+            None => Self::Line,
+            Some(RawCurve::Quadratic(control)) => Self::Quadratic(control),
+            Some(RawCurve::Tagged(TaggedCurve::Quadratic { control })) => Self::Quadratic(control),
+            Some(RawCurve::Tagged(TaggedCurve::Cubic { controls })) => Self::Cubic(controls),
+            Some(RawCurve::Tagged(TaggedCurve::Arc {
+                radius,
+                large_arc,
+                sweep,
+            })) => {
+                Self::Arc {
+                    radius,
+                    large_arc,
+                    sweep,
+                }
+            }
+        }
+    }
+}
+
+impl From<Curve> for Option<RawCurve> {
+    fn from(curve: Curve) -> Self {
+        match curve {
+            Curve::Line => None,
+            Curve::Quadratic(control) => Some(RawCurve::Quadratic(control)),
+            Curve::Cubic(controls) => Some(RawCurve::Tagged(TaggedCurve::Cubic { controls })),
+            Curve::Arc {
+                radius,
+                large_arc,
+                sweep,
+            } => {
+                Some(RawCurve::Tagged(TaggedCurve::Arc {
+                    radius,
+                    large_arc,
+                    sweep,
+                }))
+            }
+        }
+    }
+}
+
+impl Curve {
+    pub fn is_line(&self) -> bool {
+        matches!(self, Curve::Line)
+    }
+
+    /// the same curve, traced from the end of the edge to its start.
+    pub fn reversed(&self) -> Curve {
+        let reverse = |[along, across]: [Decimal; 2]| [Decimal::ONE - along, -across];
+
+        match self {
+            Curve::Line => Curve::Line,
+            Curve::Quadratic(control) => Curve::Quadratic(reverse(*control)),
+            Curve::Cubic([first, second]) => Curve::Cubic([reverse(*second), reverse(*first)]),
+            Curve::Arc {
+                radius,
+                large_arc,
+                sweep,
+            } => {
+                Curve::Arc {
+                    radius: *radius,
+                    large_arc: *large_arc,
+                    sweep: !sweep,
+                }
+            }
+        }
+    }
+
+    /// the control points of bezier curves. lines and arcs don't have any.
+    pub fn control_points(&self) -> &[[Decimal; 2]] {
+        match self {
+            Curve::Quadratic(control) => std::slice::from_ref(control),
+            Curve::Cubic(controls) => controls,
+            Curve::Line | Curve::Arc { .. } => &[],
+        }
+    }
+
+    /// the control points of bezier curves, for changing them.
+    pub fn control_points_mut(&mut self) -> &mut [[Decimal; 2]] {
+        match self {
+            Curve::Quadratic(control) => std::slice::from_mut(control),
+            Curve::Cubic(controls) => controls,
+            Curve::Line | Curve::Arc { .. } => &mut [],
+        }
+    }
+
+    /// resolves the curve between `start` and `end` into absolute
+    /// coordinates. this fails if a value can't be converted into a float,
+    /// use [`Curve::segment_with`] to keep track of the precision.
+    pub fn segment(
+        &self,
+        start: &Vector2<Decimal>,
+        end: &Vector2<Decimal>,
+    ) -> Result<Segment, ConversionError> {
+        self.segment_with(start, end, &mut Converter::lossy())
+    }
+
+    /// resolves the curve between `start` and `end` into absolute
+    /// coordinates, converting them with `converter`.
+    pub fn segment_with(
+        &self,
+        start: &Vector2<Decimal>,
+        end: &Vector2<Decimal>,
+        converter: &mut Converter,
+    ) -> Result<Segment, ConversionError> {
+        let start_f64 = converter.to_f64_point(start)?;
+        let end_f64 = converter.to_f64_point(end)?;
+        let mut control = |relative: &[Decimal; 2]| {
+            converter.to_f64_point(&Edge::absolute_control_point(start, end, relative))
+        };
+
+        let segment = match self {
+            Curve::Line => {
+                Segment::Line {
+                    start: start_f64,
+                    end: end_f64,
+                }
+            }
+            Curve::Quadratic(relative) => {
+                Segment::Quadratic {
+                    start: start_f64,
+                    control: control(relative)?,
+                    end: end_f64,
+                }
+            }
+            Curve::Cubic([first, second]) => {
+                Segment::Cubic {
+                    start: start_f64,
+                    controls: [control(first)?, control(second)?],
+                    end: end_f64,
+                }
+            }
+            Curve::Arc {
+                radius,
+                large_arc,
+                sweep,
+            } => {
+                let radius = converter.to_f64(*radius)?;
+                Segment::arc(start_f64, end_f64, radius, *large_arc, *sweep)
+            }
+        };
+
+        Ok(segment)
+    }
+}
+
+/// a curve with absolute coordinates.
+#[derive(Copy, Clone, Debug)]
+pub enum Segment {
+    Line {
+        start: Vector2<f64>,
+        end: Vector2<f64>,
+    },
+    Quadratic {
+        start: Vector2<f64>,
+        control: Vector2<f64>,
+        end: Vector2<f64>,
+    },
+    Cubic {
+        start: Vector2<f64>,
+        controls: [Vector2<f64>; 2],
+        end: Vector2<f64>,
+    },
+    Arc {
+        start: Vector2<f64>,
+        end: Vector2<f64>,
+        center: Vector2<f64>,
+
+        /// the radius, after it was scaled up to connect the endpoints.
+        radius: f64,
+
+        /// angle of `start` around `center`.
+        start_angle: f64,
+
+        /// angle from `start` to `end`. positive if the arc is traced with
+        /// increasing angle.
+        sweep_angle: f64,
+    },
+}
+
+impl Segment {
+    /// computes the center parameterization of an arc, following the SVG
+    /// implementation notes (with both radii equal and no rotation).
+    pub fn arc(
+        start: Vector2<f64>,
+        end: Vector2<f64>,
+        radius: f64,
+        large_arc: bool,
+        sweep: bool,
+    ) -> Self {
+        let half = (start - end) / 2.0;
+        let middle = (start + end) / 2.0;
+
+        let mut radius = radius.abs();
+        let half_squared = half.norm_squared();
+        if radius * radius < half_squared {
+            radius = half_squared.sqrt();
+        }
+
+        let mut factor = ((radius * radius - half_squared) / half_squared)
+            .max(0.0)
+            .sqrt();
+        if large_arc == sweep {
+            factor = -factor;
+        }
+        if !factor.is_finite() {
+            factor = 0.0;
+        }
+
+        let center = middle + Vector2::new(half.y, -half.x) * factor;
+
+        let from = start - center;
+        let to = end - center;
+        let start_angle = from.y.atan2(from.x);
+        let mut sweep_angle = to.y.atan2(to.x) - start_angle;
+
+        if sweep && sweep_angle < 0.0 {
+            sweep_angle += TAU;
+        }
+        else if !sweep && sweep_angle > 0.0 {
+            sweep_angle -= TAU;
+        }
+
+        Segment::Arc {
+            start,
+            end,
+            center,
+            radius,
+            start_angle,
+            sweep_angle,
+        }
+    }
+
+    pub fn start(&self) -> Vector2<f64> {
+        match self {
+            Segment::Line { start, .. }
+            | Segment::Quadratic { start, .. }
+            | Segment::Cubic { start, .. }
+            | Segment::Arc { start, .. } => *start,
+        }
+    }
+
+    pub fn end(&self) -> Vector2<f64> {
+        match self {
+            Segment::Line { end, .. }
+            | Segment::Quadratic { end, .. }
+            | Segment::Cubic { end, .. }
+            | Segment::Arc { end, .. } => *end,
+        }
+    }
+
+    /// the same segment, moved by `offset`.
+    pub fn translated(&self, offset: &Vector2<f64>) -> Segment {
+        match *self {
+            Segment::Line { start, end } => {
+                Segment::Line {
+                    start: start + offset,
+                    end: end + offset,
+                }
+            }
+            Segment::Quadratic {
+                start,
+                control,
+                end,
+            } => {
+                Segment::Quadratic {
+                    start: start + offset,
+                    control: control + offset,
+                    end: end + offset,
+                }
+            }
+            Segment::Cubic {
+                start,
+                controls,
+                end,
+            } => {
+                Segment::Cubic {
+                    start: start + offset,
+                    controls: controls.map(|control| control + offset),
+                    end: end + offset,
+                }
+            }
+            Segment::Arc {
+                start,
+                end,
+                center,
+                radius,
+                start_angle,
+                sweep_angle,
+            } => {
+                Segment::Arc {
+                    start: start + offset,
+                    end: end + offset,
+                    center: center + offset,
+                    radius,
+                    start_angle,
+                    sweep_angle,
+                }
+            }
+        }
+    }
+
+    /// whether all coordinates of the segment are finite.
+    pub fn is_finite(&self) -> bool {
+        let finite = |point: &Vector2<f64>| point.iter().all(|value| value.is_finite());
+
+        match self {
+            Segment::Line { start, end } => finite(start) && finite(end),
+            Segment::Quadratic {
+                start,
+                control,
+                end,
+            } => finite(start) && finite(control) && finite(end),
+            Segment::Cubic {
+                start,
+                controls,
+                end,
+            } => finite(start) && controls.iter().all(finite) && finite(end),
+            Segment::Arc {
+                start,
+                end,
+                center,
+                radius,
+                ..
+            } => finite(start) && finite(end) && finite(center) && radius.is_finite(),
+        }
+    }
+
+    /// evaluates the curve at `t` (from `0` at the start to `1` at the end).
+    pub fn point_at(&self, t: f64) -> Vector2<f64> {
+        let s = 1.0 - t;
+
+        match self {
+            Segment::Line { start, end } => start * s + end * t,
+            Segment::Quadratic {
+                start,
+                control,
+                end,
+            } => start * (s * s) + control * (2.0 * s * t) + end * (t * t),
+            Segment::Cubic {
+                start,
+                controls,
+                end,
+            } => {
+                start * (s * s * s)
+                    + controls[0] * (3.0 * s * s * t)
+                    + controls[1] * (3.0 * s * t * t)
+                    + end * (t * t * t)
+            }
+            Segment::Arc {
+                center,
+                radius,
+                start_angle,
+                sweep_angle,
+                ..
+            } => {
+                let angle = start_angle + sweep_angle * t;
+                center + Vector2::new(angle.cos(), angle.sin()) * *radius
+            }
+        }
+    }
+
+    /// direction of the curve at `t`, as a unit vector. for degenerate curves
+    /// this is the direction from start to end, or zero if they are the same.
+    pub fn direction_at(&self, t: f64) -> Vector2<f64> {
+        let s = 1.0 - t;
+
+        let derivative = match self {
+            Segment::Line { start, end } => end - start,
+            Segment::Quadratic {
+                start,
+                control,
+                end,
+            } => (control - start) * (2.0 * s) + (end - control) * (2.0 * t),
+            Segment::Cubic {
+                start,
+                controls,
+                end,
+            } => {
+                (controls[0] - start) * (3.0 * s * s)
+                    + (controls[1] - controls[0]) * (6.0 * s * t)
+                    + (end - controls[1]) * (3.0 * t * t)
+            }
+            Segment::Arc {
+                start_angle,
+                sweep_angle,
+                ..
+            } => {
+                let angle = start_angle + sweep_angle * t;
+                Vector2::new(-angle.sin(), angle.cos()) * sweep_angle.signum()
+            }
+        };
+
+        derivative
+            .try_normalize(f64::EPSILON)
+            .or_else(|| (self.end() - self.start()).try_normalize(f64::EPSILON))
+            .unwrap_or_else(Vector2::zeros)
+    }
+
+    /// length of the curve. bezier curves are measured with a polyline, arcs
+    /// are measured exactly.
+    pub fn length(&self) -> f64 {
+        match self {
+            Segment::Line { start, end } => (end - start).norm(),
+            Segment::Arc {
+                radius,
+                sweep_angle,
+                ..
+            } => radius * sweep_angle.abs(),
+            _ => {
+                let mut length = 0.0;
+                let mut previous = self.start();
+                for i in 1..=LENGTH_SAMPLES {
+                    let point = self.point_at(i as f64 / LENGTH_SAMPLES as f64);
+                    length += (point - previous).norm();
+                    previous = point;
+                }
+                length
+            }
+        }
+    }
+
+    /// points along the curve, from start to end, at most `step` apart along
+    /// the curve. lines only yield their endpoints.
+    pub fn polyline(&self, step: f64) -> Vec<Vector2<f64>> {
+        if let Segment::Line { start, end } = self {
+            return vec![*start, *end];
+        }
+
+        let count = (self.length() / step).ceil();
+        let count = if count.is_finite() {
+            (count as usize).max(1)
+        }
+        else {
+            1
+        };
+
+        (0..=count)
+            .map(|i| self.point_at(i as f64 / count as f64))
+            .collect()
+    }
+
+    /// returns the minimum and maximum corner of the curve's bounding box.
+    pub fn bounding_box(&self) -> (Vector2<f64>, Vector2<f64>) {
+        let mut min = self.start().inf(&self.end());
+        let mut max = self.start().sup(&self.end());
+
+        let mut insert = |t: f64| {
+            if t > 0.0 && t < 1.0 {
+                let point = self.point_at(t);
+                min = min.inf(&point);
+                max = max.sup(&point);
+            }
+        };
+
+        match self {
+            Segment::Line { .. } => {}
+            Segment::Quadratic {
+                start,
+                control,
+                end,
+            } => {
+                // the derivative is linear, with one root per axis.
+                for axis in 0..2 {
+                    let denominator = start[axis] - 2.0 * control[axis] + end[axis];
+                    if denominator != 0.0 {
+                        insert((start[axis] - control[axis]) / denominator);
+                    }
+                }
+            }
+            Segment::Cubic {
+                start,
+                controls,
+                end,
+            } => {
+                // the derivative is quadratic: a t² + b t + c
+                for axis in 0..2 {
+                    let (p0, p1, p2, p3) =
+                        (start[axis], controls[0][axis], controls[1][axis], end[axis]);
+                    let a = -p0 + 3.0 * p1 - 3.0 * p2 + p3;
+                    let b = 2.0 * (p0 - 2.0 * p1 + p2);
+                    let c = p1 - p0;
+
+                    if a.abs() < f64::EPSILON {
+                        if b != 0.0 {
+                            insert(-c / b);
+                        }
+                        continue;
+                    }
+
+                    let discriminant = b * b - 4.0 * a * c;
+                    if discriminant >= 0.0 {
+                        let root = discriminant.sqrt();
+                        insert((-b + root) / (2.0 * a));
+                        insert((-b - root) / (2.0 * a));
+                    }
+                }
+            }
+            Segment::Arc {
+                start_angle,
+                sweep_angle,
+                ..
+            } => {
+                // the extremes are at multiples of 90°.
+                let (from, to) = if *sweep_angle >= 0.0 {
+                    (*start_angle, start_angle + sweep_angle)
+                }
+                else {
+                    (start_angle + sweep_angle, *start_angle)
+                };
+
+                let mut angle = (from / FRAC_PI_2).ceil() * FRAC_PI_2;
+                while angle < to {
+                    insert((angle - start_angle) / sweep_angle);
+                    angle += FRAC_PI_2;
+                }
+            }
+        }
+
+        (min, max)
+    }
+
+    /// approximates the segment with cubic bezier curves. returns the control
+    /// points and end point of every curve. lines are returned as a cubic
+    /// curve, too.
+    pub fn approximate_cubics(&self) -> Vec<([Vector2<f64>; 2], Vector2<f64>)> {
+        match self {
+            Segment::Line { start, end } => vec![([*start, *end], *end)],
+            Segment::Quadratic {
+                start,
+                control,
+                end,
+            } => {
+                vec![(
+                    [
+                        start + (control - start) * (2.0 / 3.0),
+                        end + (control - end) * (2.0 / 3.0),
+                    ],
+                    *end,
+                )]
+            }
+            Segment::Cubic { controls, end, .. } => vec![(*controls, *end)],
+            Segment::Arc {
+                center,
+                radius,
+                start_angle,
+                sweep_angle,
+                end,
+                ..
+            } => {
+                // at most 90° per curve
+                let count = (sweep_angle.abs() / FRAC_PI_2).ceil().max(1.0) as usize;
+                let step = sweep_angle / count as f64;
+                let k = 4.0 / 3.0 * (step / 4.0).tan() * radius;
+
+                (0..count)
+                    .map(|i| {
+                        let a0 = start_angle + step * i as f64;
+                        let a1 = a0 + step;
+                        let p0 = center + Vector2::new(a0.cos(), a0.sin()) * *radius;
+                        let p1 = if i + 1 == count {
+                            *end
+                        }
+                        else {
+                            center + Vector2::new(a1.cos(), a1.sin()) * *radius
+                        };
+
+                        let c0 = p0 + Vector2::new(-a0.sin(), a0.cos()) * k;
+                        let c1 = p1 - Vector2::new(-a1.sin(), a1.cos()) * k;
+
+                        ([c0, c1], p1)
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use super::*;
+
+    const EPSILON: f64 = 1e-9;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < EPSILON,
+            "expected {}, got {}",
+            expected,
+            actual
+        );
+    }
+
+    fn assert_point_close(actual: &Vector2<f64>, expected: &Vector2<f64>) {
+        assert!(
+            (actual - expected).norm() < EPSILON,
+            "expected {}, got {}",
+            expected,
+            actual
+        );
+    }
+
+    fn arc(radius: f64, large_arc: bool, sweep: bool) -> Segment {
+        Segment::arc(
+            Vector2::new(0.0, 0.0),
+            Vector2::new(2.0, 0.0),
+            radius,
+            large_arc,
+            sweep,
+        )
+    }
+
+    #[test]
+    fn arc_center() {
+        match arc(1.0, false, true) {
+            Segment::Arc { center, radius, .. } => {
+                assert_point_close(&center, &Vector2::new(1.0, 0.0));
+                assert_close(radius, 1.0);
+            }
+            segment => panic!("expected an arc, got {:?}", segment),
+        }
+
+        // the smaller arc has its center on the other side than the larger
+        // one.
+        let small = arc(2.0, false, true);
+        let large = arc(2.0, true, true);
+        match (small, large) {
+            (Segment::Arc { center: small, .. }, Segment::Arc { center: large, .. }) => {
+                let offset = 3.0_f64.sqrt();
+                assert_point_close(&small, &Vector2::new(1.0, offset));
+                assert_point_close(&large, &Vector2::new(1.0, -offset));
+            }
+            segments => panic!("expected arcs, got {:?}", segments),
+        }
+    }
+
+    #[test]
+    fn arc_radius_is_scaled_up() {
+        match arc(0.5, false, true) {
+            Segment::Arc { radius, .. } => assert_close(radius, 1.0),
+            segment => panic!("expected an arc, got {:?}", segment),
+        }
+    }
+
+    #[test]
+    fn arc_length() {
+        assert_close(arc(1.0, false, true).length(), PI);
+        assert_close(arc(2.0, false, true).length(), 2.0 * PI / 3.0);
+        assert_close(arc(2.0, true, true).length(), 10.0 * PI / 3.0);
+    }
+
+    #[test]
+    fn arc_bounding_box() {
+        // the half circle is traced with increasing angle, i.e. below the
+        // chord.
+        let (min, max) = arc(1.0, false, true).bounding_box();
+        assert_point_close(&min, &Vector2::new(0.0, -1.0));
+        assert_point_close(&max, &Vector2::new(2.0, 0.0));
+
+        let (min, max) = arc(1.0, false, false).bounding_box();
+        assert_point_close(&min, &Vector2::new(0.0, 0.0));
+        assert_point_close(&max, &Vector2::new(2.0, 1.0));
+    }
+
+    #[test]
+    fn quadratic_bounding_box() {
+        let segment = Segment::Quadratic {
+            start: Vector2::new(0.0, 0.0),
+            control: Vector2::new(1.0, 2.0),
+            end: Vector2::new(2.0, 0.0),
+        };
+        let (min, max) = segment.bounding_box();
+        assert_point_close(&min, &Vector2::new(0.0, 0.0));
+        assert_point_close(&max, &Vector2::new(2.0, 1.0));
+    }
+
+    #[test]
+    fn cubic_bounding_box() {
+        let segment = Segment::Cubic {
+            start: Vector2::new(0.0, 0.0),
+            controls: [Vector2::new(0.0, 4.0), Vector2::new(2.0, -4.0)],
+            end: Vector2::new(2.0, 0.0),
+        };
+        let (min, max) = segment.bounding_box();
+        let extent = 2.0 / 3.0_f64.sqrt();
+        assert_point_close(&min, &Vector2::new(0.0, -extent));
+        assert_point_close(&max, &Vector2::new(2.0, extent));
+    }
+
+    #[test]
+    fn relative_control_points() {
+        let start = Vector2::new(Decimal::ZERO, Decimal::ZERO);
+        let end = Vector2::new(Decimal::TWO, Decimal::ZERO);
+        let half = Decimal::new(5, 1);
+
+        match Curve::Quadratic([half, half])
+            .segment(&start, &end)
+            .unwrap()
+        {
+            Segment::Quadratic { control, .. } => {
+                assert_point_close(&control, &Vector2::new(1.0, 1.0))
+            }
+            segment => panic!("expected a quadratic curve, got {:?}", segment),
+        }
+    }
+
+    #[test]
+    fn reversed_curve_traces_the_same_points() {
+        let start = Vector2::new(Decimal::ZERO, Decimal::ZERO);
+        let end = Vector2::new(Decimal::TWO, Decimal::ONE);
+        let curves = [
+            Curve::Quadratic([Decimal::new(3, 1), Decimal::new(4, 1)]),
+            Curve::Cubic([
+                [Decimal::new(2, 1), Decimal::new(-3, 1)],
+                [Decimal::new(7, 1), Decimal::new(5, 1)],
+            ]),
+            Curve::Arc {
+                radius: Decimal::TWO,
+                large_arc: false,
+                sweep: true,
+            },
+        ];
+
+        for curve in curves {
+            let forward = curve.segment(&start, &end).unwrap();
+            let backward = curve.reversed().segment(&end, &start).unwrap();
+            for i in 0..=4 {
+                let t = i as f64 / 4.0;
+                assert_point_close(&forward.point_at(t), &backward.point_at(1.0 - t));
+            }
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use serde_json::error::Category;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -5,18 +6,89 @@ pub enum Error {
     #[error("io error")]
     Io(#[from] std::io::Error),
 
+    #[cfg(feature = "zip")]
     #[error("zip error")]
     Zip(#[from] zip::result::ZipError),
 
-    #[error("json error")]
+    #[error("{}", json_message(.0))]
     Json(#[from] serde_json::Error),
 
-    #[error("toml error")]
+    #[cfg(feature = "zip")]
+    #[error(transparent)]
+    JsonFile(#[from] crate::reader::JsonError),
+
+    #[error("invalid toml")]
     Toml(#[from] toml::de::Error),
 
+    #[error("can't write toml")]
+    TomlWrite(#[from] toml::ser::Error),
+
+    #[error("parameters error")]
+    Parameters(#[from] crate::parameters::ParametersError),
+
     #[error("template not found")]
     TemplatesNotFound,
 
-    #[error("render rror")]
+    #[error("render error")]
     RenderError(#[from] crate::pattern::RenderError),
+
+    #[cfg(feature = "seamly2d")]
+    #[error("seamly2d import error")]
+    Seamly2d(#[from] crate::convert::seamly2d::Seamly2dError),
+
+    #[error("constraint report error")]
+    Constraint(#[from] crate::constraints::ConstraintError),
+
+    #[error("consumption report error")]
+    Consumption(#[from] crate::consumption::ConsumptionError),
+
+    #[error("fitting error")]
+    Fit(#[from] crate::fitting::FitError),
+
+    #[error("grading error")]
+    Grade(#[from] crate::grading::GradeError),
+
+    #[error("interpolation error")]
+    Interpolation(#[from] crate::interpolation::InterpolationError),
+
+    #[error("sweep error")]
+    Sweep(#[from] crate::sweep::SweepError),
+
+    #[error("nesting error")]
+    Nesting(#[from] crate::nesting::NestingError),
+
+    #[error("freesewing conversion error")]
+    FreeSewing(#[from] crate::convert::freesewing::FreeSewingError),
+
+    #[error("unknown pattern format: {}", .0.display())]
+    UnknownFormat(std::path::PathBuf),
+
+    #[error("exporting to {format} is not supported")]
+    UnsupportedExport { format: &'static str },
+}
+
+/// describes what went wrong with json, the details are in the source.
+fn json_message(error: &serde_json::Error) -> &'static str {
+    match error.classify() {
+        Category::Io => "can't read or write json",
+        Category::Syntax | Category::Eof => "malformed json",
+        Category::Data => "json doesn't have the expected structure",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_messages() {
+        let malformed = serde_json::from_str::<Vec<u32>>("[1,").unwrap_err();
+        assert_eq!(Error::from(malformed).to_string(), "malformed json");
+
+        let unexpected = serde_json::from_str::<Vec<u32>>("[true]").unwrap_err();
+        assert_eq!(
+            Error::from(unexpected).to_string(),
+            "json doesn't have the expected structure"
+        );
+    }
 }
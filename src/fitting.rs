@@ -0,0 +1,493 @@
+//! fitting template parameters to body measurements.
+//!
+//! the parameters of a template are abstract scale factors, but customers
+//! give measurements like their waist or hip circumference. [`Measurements`]
+//! say which edges of the pattern make up each measurement, e.g. all edges of
+//! the waistband, and how long they should be in total. [`Template::fit`]
+//! searches for parameter values within their ranges, so that the measured
+//! lengths match the targets as closely as possible, and reports what's left
+//! over where the ranges aren't enough.
+//!
+//! measurements are in centimeters and converted with the template's
+//! `units_in_meter`.
+
+use std::fmt;
+
+use indexmap::IndexMap;
+use nalgebra::{
+    DMatrix,
+    DVector,
+};
+use rust_decimal::Decimal;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::{
+    geometry::{
+        ConversionError,
+        Converter,
+    },
+    id::{
+        EdgeHandle,
+        ParameterName,
+    },
+    parameters::Parameters,
+    pattern::{
+        Pattern,
+        RenderError,
+        Template,
+    },
+    report::{
+        units_in_meter,
+        InvalidUnits,
+    },
+};
+
+/// fitted parameter values are rounded to this many decimal places.
+const DECIMAL_PLACES: u32 = 6;
+
+/// finite differences use this fraction of a parameter's range as step.
+const DIFFERENCE_STEP: f64 = 1e-3;
+
+#[derive(Debug, thiserror::Error)]
+pub enum FitError {
+    #[error(transparent)]
+    InvalidUnits(#[from] InvalidUnits),
+
+    #[error("conversion error")]
+    Conversion(#[from] ConversionError),
+
+    #[error(transparent)]
+    Render(#[from] RenderError),
+}
+
+/// file format for measurements. this maps measurement names to the edges
+/// that are measured, e.g.
+///
+/// ```toml
+/// [waist]
+/// target = 72
+/// edges = ["front:2", "back:2"]
+/// ```
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Measurements {
+    #[serde(flatten)]
+    pub measurements: IndexMap<String, Measurement>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Measurement {
+    /// the length the edges should have, in centimeters.
+    pub target: Decimal,
+
+    /// edges whose lengths are added up.
+    pub edges: Vec<EdgeHandle>,
+}
+
+#[derive(Clone, Debug)]
+pub struct FitOptions {
+    /// pattern units in a meter, see
+    /// [`crate::pattern::Properties::units_in_meter`].
+    pub units_in_meter: Decimal,
+
+    /// largest difference from a target that counts as a match, in
+    /// centimeters.
+    pub tolerance: f64,
+
+    pub max_iterations: usize,
+}
+
+/// the result of [`Template::fit`].
+#[derive(Clone, Debug)]
+pub struct Fit {
+    pub parameters: Parameters,
+
+    /// the measurements of the fitted pattern, in the order of the
+    /// measurements file.
+    pub residuals: Vec<Residual>,
+
+    /// parameters that are at the limit of their range.
+    pub limited: Vec<ParameterName>,
+
+    pub tolerance: f64,
+}
+
+/// how well a measurement was matched. lengths are in centimeters.
+#[derive(Clone, Debug)]
+pub struct Residual {
+    pub measurement: String,
+    pub target: f64,
+    pub achieved: f64,
+}
+
+/// a parameter that is fitted.
+#[derive(Clone, Debug)]
+struct Free {
+    name: ParameterName,
+    range: [f64; 2],
+
+    /// the range as given in the template, which rounded values are clamped
+    /// to.
+    exact_range: [Decimal; 2],
+}
+
+impl Default for FitOptions {
+    fn default() -> Self {
+        Self {
+            units_in_meter: Decimal::ONE_HUNDRED,
+            tolerance: 0.1,
+            max_iterations: 100,
+        }
+    }
+}
+
+impl Residual {
+    /// achieved minus target length.
+    pub fn difference(&self) -> f64 {
+        self.achieved - self.target
+    }
+}
+
+impl Fit {
+    /// whether all measurements are matched within the tolerance.
+    pub fn is_exact(&self) -> bool {
+        self.residuals
+            .iter()
+            .all(|residual| residual.difference().abs() <= self.tolerance)
+    }
+}
+
+impl Template {
+    /// fits the parameters to `measurements`, starting from the default
+    /// parameters. parameters that don't affect any measurement keep their
+    /// default value.
+    ///
+    /// this minimizes the squared differences with a damped gauss-newton
+    /// method, keeping parameters within their range.
+    pub fn fit(&self, measurements: &Measurements, options: &FitOptions) -> Result<Fit, FitError> {
+        let units_in_meter = units_in_meter(options.units_in_meter)?;
+        let mut converter = Converter::lossy();
+
+        let mut base = self.default_parameters();
+        let mut free = vec![];
+        let mut x = vec![];
+        for (name, parameter) in self.parameters()? {
+            let range = [
+                converter.to_f64(parameter.range[0])?,
+                converter.to_f64(parameter.range[1])?,
+            ];
+            if range[1] > range[0] {
+                free.push(Free {
+                    name: name.clone(),
+                    range,
+                    exact_range: parameter.range,
+                });
+                x.push(converter.to_f64(parameter.value)?.clamp(range[0], range[1]));
+            }
+        }
+
+        let targets = measurements
+            .measurements
+            .values()
+            .map(|measurement| converter.to_f64(measurement.target))
+            .collect::<Result<Vec<_>, _>>()?;
+        let targets = DVector::from_vec(targets);
+
+        let mut evaluate = |x: &[f64]| -> Result<DVector<f64>, FitError> {
+            for (free, value) in free.iter().zip(x) {
+                let [min, max] = free.exact_range;
+                let value = converter
+                    .to_decimal(*value, DECIMAL_PLACES)?
+                    .clamp(min, max);
+                base.parameters.insert(free.name.clone(), value);
+            }
+            let pattern = self.with_parameters(&base)?;
+            let lengths = measure(&pattern, measurements, units_in_meter)?;
+            Ok(DVector::from_vec(lengths) - &targets)
+        };
+
+        let mut residual = evaluate(&x)?;
+        let mut damping = 1e-3;
+
+        for _ in 0..options.max_iterations {
+            if residual.amax() <= options.tolerance {
+                break;
+            }
+
+            // jacobian with forward differences, or backward differences at
+            // the upper end of the range.
+            let mut jacobian = DMatrix::zeros(residual.len(), free.len());
+            for (column, parameter) in free.iter().enumerate() {
+                let mut step = (parameter.range[1] - parameter.range[0]) * DIFFERENCE_STEP;
+                if x[column] + step > parameter.range[1] {
+                    step = -step;
+                }
+
+                let mut moved = x.clone();
+                moved[column] += step;
+                let difference = (evaluate(&moved)? - &residual) / step;
+                jacobian.set_column(column, &difference);
+            }
+
+            // parameters at the limit of their range that would have to move
+            // out of it are kept where they are.
+            let gradient = jacobian.transpose() * &residual;
+            let active = (0..free.len())
+                .filter(|column| {
+                    let [min, max] = free[*column].range;
+                    !(x[*column] <= min && gradient[*column] > 0.0
+                        || x[*column] >= max && gradient[*column] < 0.0)
+                })
+                .collect::<Vec<_>>();
+            if active.is_empty() {
+                break;
+            }
+
+            let jacobian = jacobian.select_columns(&active);
+            let gradient = jacobian.transpose() * &residual;
+            let normal = jacobian.transpose() * &jacobian;
+
+            let mut improved = false;
+            while damping < 1e12 {
+                let mut damped = normal.clone();
+                for i in 0..active.len() {
+                    damped[(i, i)] += damping * normal[(i, i)].max(f64::EPSILON);
+                }
+
+                let Some(step) = damped.lu().solve(&-&gradient)
+                else {
+                    damping *= 10.0;
+                    continue;
+                };
+
+                let mut candidate = x.clone();
+                for (i, column) in active.iter().enumerate() {
+                    let [min, max] = free[*column].range;
+                    candidate[*column] = (x[*column] + step[i]).clamp(min, max);
+                }
+
+                let candidate_residual = evaluate(&candidate)?;
+                if candidate_residual.norm_squared() < residual.norm_squared() {
+                    x = candidate;
+                    residual = candidate_residual;
+                    damping = (damping / 10.0).max(1e-12);
+                    improved = true;
+                    break;
+                }
+
+                damping *= 10.0;
+            }
+
+            if !improved {
+                break;
+            }
+        }
+
+        // evaluate once more, so that `base` has the best parameters.
+        let residual = evaluate(&x)?;
+
+        let limited = free
+            .iter()
+            .zip(&x)
+            .filter(|(free, value)| **value <= free.range[0] || **value >= free.range[1])
+            .map(|(free, _)| free.name.clone())
+            .collect();
+
+        let residuals = measurements
+            .measurements
+            .keys()
+            .zip(targets.iter().zip(residual.iter()))
+            .map(|(name, (target, difference))| {
+                Residual {
+                    measurement: name.clone(),
+                    target: *target,
+                    achieved: target + difference,
+                }
+            })
+            .collect();
+
+        Ok(Fit {
+            parameters: base,
+            residuals,
+            limited,
+            tolerance: options.tolerance,
+        })
+    }
+}
+
+/// measures the total length of the edges of every measurement, in
+/// centimeters.
+fn measure(
+    pattern: &Pattern,
+    measurements: &Measurements,
+    units_in_meter: f64,
+) -> Result<Vec<f64>, RenderError> {
+    measurements
+        .measurements
+        .values()
+        .map(|measurement| {
+            let mut length = 0.0;
+            for edge in &measurement.edges {
+                length += pattern.edge_segment(edge)?.length();
+            }
+            Ok(length / units_in_meter * 100.0)
+        })
+        .collect()
+}
+
+impl fmt::Display for Fit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:<16} {:>12} {:>12} {:>12}",
+            "measurement", "target (cm)", "fitted (cm)", "difference"
+        )?;
+
+        for residual in &self.residuals {
+            let status = if residual.difference().abs() <= self.tolerance {
+                ""
+            }
+            else {
+                "  not reached"
+            };
+            writeln!(
+                f,
+                "{:<16} {:>12.2} {:>12.2} {:>+12.2}{}",
+                residual.measurement,
+                residual.target,
+                residual.achieved,
+                residual.difference(),
+                status
+            )?;
+        }
+
+        for name in &self.limited {
+            writeln!(f, "parameter {} is at the limit of its range", name)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{
+        json,
+        Value,
+    };
+
+    use super::*;
+    use crate::testing::{
+        self,
+        pattern_json,
+        rectangle,
+    };
+
+    /// a 40x50 panel whose hem is scaled by `width`, within 0.5 .. `max`.
+    fn template_up_to(max: Value) -> Template {
+        testing::template(
+            pattern_json(vec![("front", rectangle(40, 50))], json!([])),
+            vec![(
+                "width",
+                json!({
+                    "influence": [{"panel": "front", "edge_list": [0]}],
+                    "range": [0.5, max],
+                    "type": "length",
+                    "value": 1,
+                }),
+            )],
+        )
+    }
+
+    fn template() -> Template {
+        template_up_to(json!(2))
+    }
+
+    fn measurements(target: i64, edges: &[&str]) -> Measurements {
+        serde_json::from_value(json!({
+            "hem": {"target": target, "edges": edges},
+        }))
+        .unwrap()
+    }
+
+    fn width(fit: &Fit) -> f64 {
+        let name = ParameterName("width".to_owned());
+        Converter::lossy()
+            .to_f64(fit.parameters.parameters[&name])
+            .unwrap()
+    }
+
+    #[test]
+    fn recovers_parameter() {
+        let fit = template()
+            .fit(&measurements(60, &["front:0"]), &FitOptions::default())
+            .unwrap();
+
+        assert!(fit.is_exact(), "{}", fit);
+        assert!((width(&fit) - 1.5).abs() < 1e-3, "{}", width(&fit));
+        assert!(fit.limited.is_empty());
+        assert_eq!(fit.residuals[0].measurement, "hem");
+        assert!((fit.residuals[0].achieved - 60.0).abs() <= 0.1);
+    }
+
+    #[test]
+    fn clamps_to_range() {
+        let fit = template()
+            .fit(&measurements(100, &["front:0"]), &FitOptions::default())
+            .unwrap();
+
+        assert!(!fit.is_exact());
+        assert_eq!(width(&fit), 2.0);
+        assert_eq!(fit.limited.len(), 1);
+        assert_eq!(fit.limited[0].to_string(), "width");
+        assert!((fit.residuals[0].achieved - 80.0).abs() < 1e-6);
+        assert!(fit.to_string().contains("not reached"));
+    }
+
+    #[test]
+    fn rounding_stays_in_range() {
+        // rounded to 6 decimal places, the upper limit would be 2.
+        let template = template_up_to(json!("1.9999996"));
+        let fit = template
+            .fit(&measurements(100, &["front:0"]), &FitOptions::default())
+            .unwrap();
+
+        let name = ParameterName("width".to_owned());
+        assert_eq!(
+            fit.parameters.parameters[&name],
+            "1.9999996".parse::<Decimal>().unwrap()
+        );
+        assert_eq!(fit.limited.len(), 1);
+    }
+
+    #[test]
+    fn unknown_edge_fails() {
+        let template = template();
+        let options = FitOptions::default();
+
+        let error = template
+            .fit(&measurements(60, &["back:0"]), &options)
+            .unwrap_err();
+        assert!(matches!(error, FitError::Render(_)), "{:?}", error);
+
+        let error = template
+            .fit(&measurements(60, &["front:4"]), &options)
+            .unwrap_err();
+        assert!(matches!(error, FitError::Render(_)), "{:?}", error);
+    }
+
+    #[test]
+    fn invalid_units_fail() {
+        let options = FitOptions {
+            units_in_meter: Decimal::ZERO,
+            ..FitOptions::default()
+        };
+        let error = template()
+            .fit(&measurements(60, &["front:0"]), &options)
+            .unwrap_err();
+        assert!(matches!(error, FitError::InvalidUnits(_)), "{:?}", error);
+    }
+}
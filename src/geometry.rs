@@ -0,0 +1,549 @@
+//! `f64` working representation of patterns.
+//!
+//! patterns store coordinates as [`Decimal`]s, so that the numbers of template
+//! files round-trip exactly. geometry (tessellation, layout, rendering,
+//! simulation) works on floats instead, which is what nalgebra is made for.
+//! [`Pattern::geometry`] resolves a pattern into its working representation.
+//!
+//! all conversions between the two go through a [`Converter`], which keeps
+//! track of how much precision was lost, and can refuse conversions that lose
+//! more than a given tolerance.
+
+use std::fmt;
+
+use indexmap::IndexMap;
+use nalgebra::{
+    Rotation3,
+    Vector2,
+    Vector3,
+};
+use rust_decimal::{
+    prelude::{
+        FromPrimitive,
+        ToPrimitive,
+    },
+    Decimal,
+};
+
+use crate::{
+    curve::Segment,
+    id::{
+        PanelId,
+        VertexIndex,
+    },
+    pattern::{
+        Panel,
+        Pattern,
+        RenderError,
+    },
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum ConversionError {
+    #[error("value is not representable as float: {value}")]
+    NotFloat { value: Decimal },
+    #[error("value is not representable as decimal: {value}")]
+    NotDecimal { value: f64 },
+    #[error("converting {value} is off by {error}, which exceeds the tolerance of {tolerance}")]
+    PrecisionLoss {
+        value: f64,
+        error: f64,
+        tolerance: f64,
+    },
+}
+
+/// projected up vectors shorter than this don't define a grain direction.
+const GRAIN_EPSILON: f64 = 1e-3;
+
+/// converts values between [`Decimal`] and `f64`.
+#[derive(Clone, Debug)]
+pub struct Converter {
+    /// largest absolute error that is accepted for a single conversion.
+    tolerance: f64,
+
+    report: PrecisionReport,
+}
+
+/// how much precision was lost by a [`Converter`].
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct PrecisionReport {
+    /// number of converted values.
+    pub conversions: usize,
+
+    /// number of values that weren't converted exactly.
+    pub lossy: usize,
+
+    /// largest absolute error of a single conversion.
+    pub max_error: f64,
+}
+
+impl Converter {
+    /// a converter that accepts any loss of precision, but still records it.
+    pub fn lossy() -> Self {
+        Self::with_tolerance(f64::INFINITY)
+    }
+
+    /// a converter that fails if a single conversion is off by more than
+    /// `tolerance`.
+    pub fn with_tolerance(tolerance: f64) -> Self {
+        Self {
+            tolerance,
+            report: PrecisionReport::default(),
+        }
+    }
+
+    pub fn report(&self) -> PrecisionReport {
+        self.report
+    }
+
+    pub fn to_f64(&mut self, value: Decimal) -> Result<f64, ConversionError> {
+        let converted = value.to_f64().ok_or(ConversionError::NotFloat { value })?;
+
+        // floats are converted back without rounding, so this is the exact
+        // error.
+        let error = Decimal::from_f64_retain(converted)
+            .and_then(|back| (back - value).abs().to_f64())
+            .unwrap_or(f64::INFINITY);
+
+        self.record(converted, error)?;
+        Ok(converted)
+    }
+
+    pub fn to_f64_point(
+        &mut self,
+        point: &Vector2<Decimal>,
+    ) -> Result<Vector2<f64>, ConversionError> {
+        Ok(Vector2::new(self.to_f64(point.x)?, self.to_f64(point.y)?))
+    }
+
+    pub fn to_f64_vector(
+        &mut self,
+        vector: &Vector3<Decimal>,
+    ) -> Result<Vector3<f64>, ConversionError> {
+        Ok(Vector3::new(
+            self.to_f64(vector.x)?,
+            self.to_f64(vector.y)?,
+            self.to_f64(vector.z)?,
+        ))
+    }
+
+    /// converts `value` into a decimal that is rounded to `decimal_places`.
+    pub fn to_decimal(
+        &mut self,
+        value: f64,
+        decimal_places: u32,
+    ) -> Result<Decimal, ConversionError> {
+        let converted = Decimal::from_f64(value)
+            .map(|value| value.round_dp(decimal_places).normalize())
+            .ok_or(ConversionError::NotDecimal { value })?;
+
+        let error = converted
+            .to_f64()
+            .map(|converted| (converted - value).abs())
+            .unwrap_or(f64::INFINITY);
+
+        self.record(value, error)?;
+        Ok(converted)
+    }
+
+    pub fn to_decimal_point(
+        &mut self,
+        point: &Vector2<f64>,
+        decimal_places: u32,
+    ) -> Result<Vector2<Decimal>, ConversionError> {
+        Ok(Vector2::new(
+            self.to_decimal(point.x, decimal_places)?,
+            self.to_decimal(point.y, decimal_places)?,
+        ))
+    }
+
+    pub fn to_decimal_vector(
+        &mut self,
+        vector: &Vector3<f64>,
+        decimal_places: u32,
+    ) -> Result<Vector3<Decimal>, ConversionError> {
+        Ok(Vector3::new(
+            self.to_decimal(vector.x, decimal_places)?,
+            self.to_decimal(vector.y, decimal_places)?,
+            self.to_decimal(vector.z, decimal_places)?,
+        ))
+    }
+
+    fn record(&mut self, value: f64, error: f64) -> Result<(), ConversionError> {
+        if error > self.tolerance {
+            return Err(ConversionError::PrecisionLoss {
+                value,
+                error,
+                tolerance: self.tolerance,
+            });
+        }
+
+        self.report.conversions += 1;
+        if error > 0.0 {
+            self.report.lossy += 1;
+            self.report.max_error = self.report.max_error.max(error);
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for Converter {
+    fn default() -> Self {
+        Self::lossy()
+    }
+}
+
+impl fmt::Display for PrecisionReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} of {} conversions lost precision, max error {:e}",
+            self.lossy, self.conversions, self.max_error
+        )
+    }
+}
+
+/// a [`Panel`] with `f64` coordinates and its edges resolved into
+/// [`Segment`]s.
+#[derive(Clone, Debug)]
+pub struct PanelGeometry {
+    pub translation: Vector3<f64>,
+
+    /// euler angles in degrees.
+    pub rotation: Vector3<f64>,
+
+    pub vertices: Vec<Vector2<f64>>,
+    pub edges: Vec<EdgeGeometry>,
+}
+
+#[derive(Clone, Debug)]
+pub struct EdgeGeometry {
+    pub endpoints: [VertexIndex; 2],
+    pub segment: Segment,
+}
+
+/// the panels of a [`Pattern`] in `panel_order`.
+#[derive(Clone, Debug, Default)]
+pub struct PatternGeometry {
+    pub panels: IndexMap<PanelId, PanelGeometry>,
+}
+
+impl PanelGeometry {
+    /// bounding box of all edges, or `None` if the panel has no edges.
+    pub fn bounding_box(&self) -> Option<(Vector2<f64>, Vector2<f64>)> {
+        self.edges
+            .iter()
+            .map(|edge| edge.segment.bounding_box())
+            .reduce(|(min, max), (edge_min, edge_max)| (min.inf(&edge_min), max.sup(&edge_max)))
+    }
+
+    pub fn rotation_matrix(&self) -> Rotation3<f64> {
+        let angles = self.rotation.map(f64::to_radians);
+        Rotation3::from_euler_angles(angles.x, angles.y, angles.z)
+    }
+
+    /// direction of the grain line in panel coordinates. the grain runs along
+    /// the garment's vertical axis, i.e. the world's y axis, so this is the
+    /// world's y axis projected onto the panel. panels that lie flat use their
+    /// own y axis.
+    pub fn grain_direction(&self) -> Vector2<f64> {
+        let up = self.rotation_matrix().inverse() * Vector3::y();
+        Vector2::new(up.x, up.y)
+            .try_normalize(GRAIN_EPSILON)
+            .unwrap_or_else(Vector2::y)
+    }
+
+    /// the outline of the panel as a closed polygon, with curves flattened
+    /// into points at most `step` apart. the edges are followed like in
+    /// [`PanelGeometry::edge_loop`]. returns `None` if the edges don't form a
+    /// single loop.
+    pub fn outline(&self, step: f64) -> Option<Vec<Vector2<f64>>> {
+        let mut outline = vec![];
+
+        for (index, reversed) in self.edge_loop()? {
+            let mut points = self.edges[index].segment.polyline(step);
+            if reversed {
+                points.reverse();
+            }
+            points.pop();
+            outline.extend(points);
+        }
+
+        Some(outline)
+    }
+
+    /// the edges in the order they form a loop, starting with edge 0 and
+    /// following it from its start to its end. every edge is given with its
+    /// index, and whether it is followed from its end to its start. returns
+    /// `None` if the edges don't form a single loop.
+    pub fn edge_loop(&self) -> Option<Vec<(usize, bool)>> {
+        let first = self.edges.first()?;
+        let mut used = vec![false; self.edges.len()];
+        used[0] = true;
+
+        let mut edges = vec![(0, false)];
+        let mut current = first.endpoints[1];
+
+        while current != first.endpoints[0] {
+            let (index, reversed) = self.edges.iter().enumerate().find_map(|(index, edge)| {
+                if used[index] {
+                    None
+                }
+                else if edge.endpoints[0] == current {
+                    Some((index, false))
+                }
+                else if edge.endpoints[1] == current {
+                    Some((index, true))
+                }
+                else {
+                    None
+                }
+            })?;
+            used[index] = true;
+            edges.push((index, reversed));
+
+            let edge = &self.edges[index];
+            current = if reversed {
+                edge.endpoints[0]
+            }
+            else {
+                edge.endpoints[1]
+            };
+        }
+
+        used.iter().all(|used| *used).then_some(edges)
+    }
+
+    /// places a point of the panel in 3D.
+    pub fn to_world(&self, point: &Vector2<f64>) -> Vector3<f64> {
+        self.rotation_matrix() * Vector3::new(point.x, point.y, 0.0) + self.translation
+    }
+}
+
+impl Panel {
+    /// resolves the panel into its working representation. `name` is the name
+    /// of this panel and used for errors.
+    pub fn geometry(
+        &self,
+        name: &PanelId,
+        converter: &mut Converter,
+    ) -> Result<PanelGeometry, RenderError> {
+        let vertices = self
+            .vertices
+            .iter()
+            .map(|vertex| converter.to_f64_point(vertex))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let edges = self
+            .indexed_edges()
+            .map(|(index, edge)| {
+                Ok(EdgeGeometry {
+                    endpoints: edge.endpoints,
+                    segment: self.resolve_edge_with(name, index, converter)?,
+                })
+            })
+            .collect::<Result<Vec<_>, RenderError>>()?;
+
+        Ok(PanelGeometry {
+            translation: converter.to_f64_vector(&self.translation)?,
+            rotation: converter.to_f64_vector(&self.rotation)?,
+            vertices,
+            edges,
+        })
+    }
+}
+
+impl Pattern {
+    /// resolves all panels into their working representation.
+    pub fn geometry(&self, converter: &mut Converter) -> Result<PatternGeometry, RenderError> {
+        let panels = self
+            .panels()?
+            .map(|(name, panel)| Ok((name.clone(), panel.geometry(name, converter)?)))
+            .collect::<Result<_, RenderError>>()?;
+
+        Ok(PatternGeometry { panels })
+    }
+}
+
+/// area of a polygon, positive if it's counter-clockwise.
+pub fn signed_area(polygon: &[Vector2<f64>]) -> f64 {
+    polygon
+        .iter()
+        .zip(polygon.iter().cycle().skip(1))
+        .map(|(a, b)| a.x * b.y - b.x * a.y)
+        .sum::<f64>()
+        / 2.0
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::testing::rectangle;
+
+    fn geometry(panel: serde_json::Value) -> PanelGeometry {
+        let panel: Panel = serde_json::from_value(panel).unwrap();
+        panel
+            .geometry(&PanelId::from("front"), &mut Converter::lossy())
+            .unwrap()
+    }
+
+    fn square(rotation: [i64; 3]) -> PanelGeometry {
+        let mut panel = rectangle(10, 10);
+        panel["rotation"] = json!(rotation);
+        geometry(panel)
+    }
+
+    fn assert_close(actual: Vector2<f64>, expected: Vector2<f64>) {
+        assert!(
+            (actual - expected).norm() < 1e-9,
+            "expected {}, got {}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn converter_records_precision() {
+        let mut converter = Converter::lossy();
+        assert_eq!(converter.to_f64(Decimal::new(5, 1)).unwrap(), 0.5);
+        assert_eq!(
+            converter.report(),
+            PrecisionReport {
+                conversions: 1,
+                lossy: 0,
+                max_error: 0.0,
+            }
+        );
+
+        // 0.1 isn't exact as a float, and 1.23456 is rounded.
+        converter.to_f64(Decimal::new(1, 1)).unwrap();
+        assert_eq!(
+            converter.to_decimal(1.23456, 2).unwrap(),
+            Decimal::new(123, 2)
+        );
+
+        let report = converter.report();
+        assert_eq!(report.conversions, 3);
+        assert_eq!(report.lossy, 2);
+        assert!((report.max_error - 0.00456).abs() < 1e-12);
+        assert_eq!(
+            report.to_string(),
+            format!(
+                "2 of 3 conversions lost precision, max error {:e}",
+                report.max_error
+            )
+        );
+    }
+
+    #[test]
+    fn converter_tolerance() {
+        let mut converter = Converter::with_tolerance(1e-3);
+        assert_eq!(
+            converter.to_decimal(1.2304, 2).unwrap(),
+            Decimal::new(123, 2)
+        );
+        assert!(matches!(
+            converter.to_decimal(1.23456, 2),
+            Err(ConversionError::PrecisionLoss { .. })
+        ));
+        assert!(converter.to_f64(Decimal::new(1, 1)).is_ok());
+
+        let mut exact = Converter::with_tolerance(0.0);
+        assert!(exact.to_f64(Decimal::new(5, 1)).is_ok());
+        assert!(exact.to_f64(Decimal::new(1, 1)).is_err());
+
+        // failed conversions aren't recorded.
+        assert_eq!(converter.report().conversions, 2);
+        assert_eq!(exact.report().conversions, 1);
+    }
+
+    #[test]
+    fn outline_follows_the_edges() {
+        let mut panel = rectangle(10, 10);
+        // shuffled, with an edge pointing the other way.
+        panel["edges"] = json!([
+            {"endpoints": [0, 1]},
+            {"endpoints": [3, 2]},
+            {"endpoints": [3, 0]},
+            {"endpoints": [1, 2]},
+        ]);
+        let geometry = geometry(panel);
+
+        assert_eq!(
+            geometry.edge_loop(),
+            Some(vec![(0, false), (3, false), (1, true), (2, false)])
+        );
+
+        let outline = geometry.outline(100.0).unwrap();
+        let expected = [(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)];
+        assert_eq!(outline.len(), expected.len());
+        for (point, (x, y)) in outline.iter().zip(expected) {
+            assert_close(*point, Vector2::new(x, y));
+        }
+        assert_eq!(signed_area(&outline), 100.0);
+    }
+
+    #[test]
+    fn outline_of_open_panel() {
+        let mut panel = rectangle(10, 10);
+        panel["edges"] = json!([
+            {"endpoints": [0, 1]},
+            {"endpoints": [1, 2]},
+            {"endpoints": [2, 3]},
+        ]);
+        let geometry = geometry(panel);
+
+        assert_eq!(geometry.edge_loop(), None);
+        assert_eq!(geometry.outline(1.0), None);
+    }
+
+    #[test]
+    fn outline_of_two_loops() {
+        let mut panel = rectangle(10, 10);
+        panel["vertices"] = json!([[0, 0], [1, 0], [0, 1], [5, 5], [6, 5], [5, 6]]);
+        panel["edges"] = json!([
+            {"endpoints": [0, 1]},
+            {"endpoints": [1, 2]},
+            {"endpoints": [2, 0]},
+            {"endpoints": [3, 4]},
+            {"endpoints": [4, 5]},
+            {"endpoints": [5, 3]},
+        ]);
+
+        assert_eq!(geometry(panel).outline(1.0), None);
+    }
+
+    #[test]
+    fn signed_area_orientation() {
+        let counter_clockwise = [
+            Vector2::new(0.0, 0.0),
+            Vector2::new(4.0, 0.0),
+            Vector2::new(4.0, 3.0),
+        ];
+        let mut clockwise = counter_clockwise;
+        clockwise.reverse();
+
+        assert_eq!(signed_area(&counter_clockwise), 6.0);
+        assert_eq!(signed_area(&clockwise), -6.0);
+        assert_eq!(signed_area(&[]), 0.0);
+    }
+
+    #[test]
+    fn grain_direction() {
+        // a panel that isn't rotated hangs with its y axis up.
+        assert_close(square([0, 0, 0]).grain_direction(), Vector2::y());
+
+        // turning the panel around the world's y axis doesn't change that.
+        assert_close(square([0, 90, 0]).grain_direction(), Vector2::y());
+
+        // turned by 90° in its plane, the panel's x axis points up.
+        assert_close(square([0, 0, 90]).grain_direction(), Vector2::x());
+        assert_close(square([0, 0, -90]).grain_direction(), -Vector2::x());
+
+        // a panel lying flat has no up direction, so its y axis is used.
+        assert_close(square([90, 0, 0]).grain_direction(), Vector2::y());
+    }
+}
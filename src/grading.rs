@@ -0,0 +1,372 @@
+//! grading a template into a range of sizes.
+//!
+//! a [`SizeChart`] says which edges make up each measurement and has a row of
+//! measurements for every size, e.g.
+//!
+//! ```toml
+//! [measurements.waist]
+//! edges = ["front:2", "back:2"]
+//!
+//! [[sizes]]
+//! name = "S"
+//! waist = 70
+//!
+//! [[sizes]]
+//! name = "M"
+//! waist = 76
+//! ```
+//!
+//! every size is fitted with [`Template::fit`] and instantiated into its own
+//! [`Pattern`].
+
+use indexmap::IndexMap;
+use nalgebra::Vector2;
+use rust_decimal::Decimal;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::{
+    fitting::{
+        Fit,
+        FitError,
+        FitOptions,
+        Measurement,
+        Measurements,
+    },
+    geometry::PanelGeometry,
+    id::{
+        EdgeHandle,
+        VertexIndex,
+    },
+    pattern::{
+        Pattern,
+        RenderError,
+        Template,
+    },
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum GradeError {
+    #[error("size {size} has no value for measurement {measurement}")]
+    MissingMeasurement { size: String, measurement: String },
+
+    #[error("size {size} has a value for unknown measurement {measurement}")]
+    UnknownMeasurement { size: String, measurement: String },
+
+    #[error("invalid anchor, expected `bottom-left`, `center` or `vertex:N`: {anchor}")]
+    InvalidAnchor { anchor: String },
+
+    #[error("fitting size {size} failed")]
+    Fit {
+        size: String,
+        #[source]
+        source: FitError,
+    },
+
+    #[error(transparent)]
+    Render(#[from] RenderError),
+}
+
+/// file format for size charts.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SizeChart {
+    /// which edges are measured for every measurement.
+    pub measurements: IndexMap<String, MeasuredEdges>,
+
+    /// sizes, from smallest to largest.
+    pub sizes: Vec<Size>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MeasuredEdges {
+    /// edges whose lengths are added up.
+    pub edges: Vec<EdgeHandle>,
+}
+
+/// a row of the size chart.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Size {
+    pub name: String,
+
+    /// measurements in centimeters.
+    #[serde(flatten)]
+    pub measurements: IndexMap<String, Decimal>,
+}
+
+/// a template graded into all sizes of a chart.
+#[derive(Clone, Debug)]
+pub struct Grading {
+    pub sizes: Vec<GradedSize>,
+}
+
+#[derive(Clone, Debug)]
+pub struct GradedSize {
+    pub name: String,
+    pub fit: Fit,
+    pub pattern: Pattern,
+}
+
+/// the point at which the sizes of a panel are aligned.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Anchor {
+    /// the bottom left corner of the panel's bounding box.
+    #[default]
+    BottomLeft,
+
+    /// the center of the panel's bounding box.
+    Center,
+
+    /// a vertex of the panel. sizes of a template have the same vertices, so
+    /// this is the same point on every size.
+    Vertex(VertexIndex),
+}
+
+impl SizeChart {
+    /// the measurements of a single size.
+    pub fn measurements(&self, size: &Size) -> Result<Measurements, GradeError> {
+        if let Some(measurement) = size
+            .measurements
+            .keys()
+            .find(|name| !self.measurements.contains_key(*name))
+        {
+            return Err(GradeError::UnknownMeasurement {
+                size: size.name.clone(),
+                measurement: measurement.clone(),
+            });
+        }
+
+        let measurements = self
+            .measurements
+            .iter()
+            .map(|(name, measured)| {
+                let target = size.measurements.get(name).ok_or_else(|| {
+                    GradeError::MissingMeasurement {
+                        size: size.name.clone(),
+                        measurement: name.clone(),
+                    }
+                })?;
+
+                Ok((
+                    name.clone(),
+                    Measurement {
+                        target: *target,
+                        edges: measured.edges.clone(),
+                    },
+                ))
+            })
+            .collect::<Result<_, GradeError>>()?;
+
+        Ok(Measurements { measurements })
+    }
+}
+
+impl Template {
+    /// fits and instantiates every size of `chart`.
+    pub fn grade(&self, chart: &SizeChart, options: &FitOptions) -> Result<Grading, GradeError> {
+        let sizes = chart
+            .sizes
+            .iter()
+            .map(|size| {
+                let measurements = chart.measurements(size)?;
+                let fit = self.fit(&measurements, options).map_err(|source| {
+                    GradeError::Fit {
+                        size: size.name.clone(),
+                        source,
+                    }
+                })?;
+                let pattern = self.with_parameters(&fit.parameters)?;
+
+                Ok(GradedSize {
+                    name: size.name.clone(),
+                    fit,
+                    pattern,
+                })
+            })
+            .collect::<Result<_, GradeError>>()?;
+
+        Ok(Grading { sizes })
+    }
+}
+
+impl Anchor {
+    /// the anchor point of a panel, or `None` if the panel has no edges or
+    /// the vertex doesn't exist.
+    pub fn point(&self, geometry: &PanelGeometry) -> Option<Vector2<f64>> {
+        match self {
+            Anchor::BottomLeft => geometry.bounding_box().map(|(min, _)| min),
+            Anchor::Center => geometry.bounding_box().map(|(min, max)| (min + max) / 2.0),
+            Anchor::Vertex(index) => geometry.vertices.get(index.0).copied(),
+        }
+    }
+}
+
+impl std::str::FromStr for Anchor {
+    type Err = GradeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            GradeError::InvalidAnchor {
+                anchor: s.to_owned(),
+            }
+        };
+
+        match s {
+            "bottom-left" => Ok(Anchor::BottomLeft),
+            "center" => Ok(Anchor::Center),
+            _ => {
+                let index = s
+                    .strip_prefix("vertex:")
+                    .and_then(|index| index.parse::<usize>().ok())
+                    .ok_or_else(invalid)?;
+                Ok(Anchor::Vertex(VertexIndex(index)))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        geometry::Converter,
+        id::PanelId,
+        testing::hem_template,
+    };
+
+    /// a chart that measures the hem and the top, with a row of
+    /// measurements for every size.
+    fn chart(sizes: serde_json::Value) -> SizeChart {
+        serde_json::from_value(json!({
+            "measurements": {
+                "hem": {"edges": ["front:0"]},
+                "top": {"edges": ["front:2"]},
+            },
+            "sizes": sizes,
+        }))
+        .unwrap()
+    }
+
+    /// sizes S, M and L, with a hem of 30, 40 and 60.
+    fn sizes() -> SizeChart {
+        chart(json!([
+            {"name": "S", "hem": 30, "top": 40},
+            {"name": "M", "hem": 40, "top": 40},
+            {"name": "L", "hem": 60, "top": 40},
+        ]))
+    }
+
+    #[test]
+    fn measurements_of_a_size() {
+        let chart = chart(json!([{"name": "S", "top": 42, "hem": 30}]));
+        let measurements = chart.measurements(&chart.sizes[0]).unwrap();
+
+        // in the order of the chart's measurements.
+        let names = measurements.measurements.keys().collect::<Vec<_>>();
+        assert_eq!(names, ["hem", "top"]);
+
+        let hem = &measurements.measurements["hem"];
+        assert_eq!(hem.target, Decimal::from(30));
+        assert_eq!(hem.edges, ["front:0".parse::<EdgeHandle>().unwrap()]);
+        assert_eq!(measurements.measurements["top"].target, Decimal::from(42));
+    }
+
+    #[test]
+    fn missing_measurement() {
+        let chart = chart(json!([{"name": "S", "hem": 30}]));
+        match chart.measurements(&chart.sizes[0]) {
+            Err(GradeError::MissingMeasurement { size, measurement }) => {
+                assert_eq!(size, "S");
+                assert_eq!(measurement, "top");
+            }
+            other => panic!("expected a missing measurement, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn unknown_measurement() {
+        let chart = chart(json!([{"name": "S", "hem": 30, "top": 40, "hips": 90}]));
+        match chart.measurements(&chart.sizes[0]) {
+            Err(GradeError::UnknownMeasurement { size, measurement }) => {
+                assert_eq!(size, "S");
+                assert_eq!(measurement, "hips");
+            }
+            other => panic!("expected an unknown measurement, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parse_anchor() {
+        assert_eq!("bottom-left".parse::<Anchor>().unwrap(), Anchor::BottomLeft);
+        assert_eq!("center".parse::<Anchor>().unwrap(), Anchor::Center);
+        assert_eq!(
+            "vertex:2".parse::<Anchor>().unwrap(),
+            Anchor::Vertex(VertexIndex(2))
+        );
+
+        for anchor in ["", "top", "vertex:", "vertex:x", "vertex:-1", "2"] {
+            match anchor.parse::<Anchor>() {
+                Err(GradeError::InvalidAnchor { anchor: error }) => assert_eq!(error, anchor),
+                other => panic!("expected {:?} to be invalid, got {:?}", anchor, other),
+            }
+        }
+    }
+
+    #[test]
+    fn anchor_points() {
+        let geometry = hem_template()
+            .pattern
+            .geometry(&mut Converter::lossy())
+            .unwrap();
+        let panel = &geometry.panels[&PanelId::from("front")];
+
+        assert_eq!(
+            Anchor::BottomLeft.point(panel),
+            Some(Vector2::new(0.0, 0.0))
+        );
+        assert_eq!(Anchor::Center.point(panel), Some(Vector2::new(20.0, 25.0)));
+        assert_eq!(
+            Anchor::Vertex(VertexIndex(2)).point(panel),
+            Some(Vector2::new(40.0, 50.0))
+        );
+        assert_eq!(Anchor::Vertex(VertexIndex(4)).point(panel), None);
+    }
+
+    #[test]
+    fn grades_every_size() {
+        let chart = sizes();
+        let grading = hem_template()
+            .grade(&chart, &FitOptions::default())
+            .unwrap();
+
+        let names = grading
+            .sizes
+            .iter()
+            .map(|size| size.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["S", "M", "L"]);
+
+        let handle = "front:0".parse::<EdgeHandle>().unwrap();
+        for (size, hem) in grading.sizes.iter().zip([30.0, 40.0, 60.0]) {
+            assert!(size.fit.is_exact(), "{}", size.fit);
+            let length = size.pattern.edge_segment(&handle).unwrap().length();
+            assert!((length - hem).abs() <= 0.1, "{}: {}", size.name, length);
+        }
+    }
+
+    #[test]
+    fn failed_fit_names_the_size() {
+        let mut chart = sizes();
+        chart.measurements["top"].edges = vec!["back:0".parse().unwrap()];
+
+        match hem_template().grade(&chart, &FitOptions::default()) {
+            Err(GradeError::Fit { size, .. }) => assert_eq!(size, "S"),
+            other => panic!("expected a failed fit, got {:?}", other),
+        }
+    }
+}
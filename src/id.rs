@@ -0,0 +1,255 @@
+//! typed identifiers for the parts of a pattern.
+//!
+//! panels and parameters are referenced by name, edges and vertices by their
+//! index in the panel. the identifiers are serialized as the bare name or
+//! index, so they can be used directly in the template format.
+
+use std::{
+    borrow::Borrow,
+    fmt,
+    str::FromStr,
+};
+
+use derive_more::{
+    Display,
+    From,
+    Into,
+};
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+/// name of a panel in [`crate::pattern::Pattern::panels`].
+#[derive(
+    Clone, Debug, Display, From, Into, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(transparent)]
+pub struct PanelId(pub String);
+
+/// name of a parameter in [`crate::pattern::Template::parameters`].
+#[derive(
+    Clone, Debug, Display, From, Into, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(transparent)]
+pub struct ParameterName(pub String);
+
+#[derive(Debug, thiserror::Error)]
+#[error("invalid edge handle, expected `panel:edge`: {handle}")]
+pub struct InvalidEdgeHandle {
+    pub handle: String,
+}
+
+/// index of an edge in [`crate::pattern::Panel::edges`].
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    Display,
+    From,
+    Into,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+)]
+#[serde(transparent)]
+pub struct EdgeIndex(pub usize);
+
+/// index of a vertex in [`crate::pattern::Panel::vertices`].
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    Display,
+    From,
+    Into,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+)]
+#[serde(transparent)]
+pub struct VertexIndex(pub usize);
+
+/// an edge of a specific panel. this can be looked up with
+/// [`crate::pattern::Pattern::edge`]. it's written as `panel:edge`, e.g.
+/// `front:2`.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct EdgeHandle {
+    pub panel: PanelId,
+    pub edge: EdgeIndex,
+}
+
+macro_rules! impl_name {
+    ($name:ident) => {
+        impl $name {
+            pub fn as_str(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl From<&str> for $name {
+            fn from(name: &str) -> Self {
+                Self(name.to_owned())
+            }
+        }
+
+        impl Borrow<str> for $name {
+            fn borrow(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl AsRef<str> for $name {
+            fn as_ref(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl PartialEq<str> for $name {
+            fn eq(&self, other: &str) -> bool {
+                self.0 == other
+            }
+        }
+    };
+}
+
+impl_name!(PanelId);
+impl_name!(ParameterName);
+
+impl EdgeIndex {
+    /// iterates over all edge indices of a panel with `count` edges.
+    pub fn all(count: usize) -> impl Iterator<Item = Self> {
+        (0..count).map(Self)
+    }
+}
+
+impl EdgeHandle {
+    pub fn new(panel: impl Into<PanelId>, edge: impl Into<EdgeIndex>) -> Self {
+        Self {
+            panel: panel.into(),
+            edge: edge.into(),
+        }
+    }
+}
+
+impl fmt::Display for EdgeHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.panel, self.edge)
+    }
+}
+
+impl FromStr for EdgeHandle {
+    type Err = InvalidEdgeHandle;
+
+    /// parses `panel:edge`. panel names can contain `:`, the edge index is
+    /// after the last one.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.rsplit_once(':')
+            .and_then(|(panel, edge)| Some(Self::new(panel, edge.parse::<usize>().ok()?)))
+            .ok_or_else(|| {
+                InvalidEdgeHandle {
+                    handle: s.to_owned(),
+                }
+            })
+    }
+}
+
+impl TryFrom<String> for EdgeHandle {
+    type Error = InvalidEdgeHandle;
+
+    fn try_from(handle: String) -> Result<Self, Self::Error> {
+        handle.parse()
+    }
+}
+
+impl From<EdgeHandle> for String {
+    fn from(handle: EdgeHandle) -> Self {
+        handle.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn edge_handle_round_trip() {
+        for (text, panel, edge) in [
+            ("front:2", "front", 2),
+            ("left:front:0", "left:front", 0),
+            ("a b:12", "a b", 12),
+        ] {
+            let handle = text.parse::<EdgeHandle>().unwrap();
+            assert_eq!(handle, EdgeHandle::new(panel, edge));
+            assert_eq!(handle.to_string(), text);
+        }
+    }
+
+    #[test]
+    fn invalid_edge_handles() {
+        for text in ["front", "front:", "front:x", "front:-1", "front:1.5", ""] {
+            let error = text.parse::<EdgeHandle>().unwrap_err();
+            assert_eq!(error.handle, text);
+        }
+    }
+
+    #[test]
+    fn edge_handle_is_serialized_as_string() {
+        let handle = EdgeHandle::new("left:front", 3);
+        assert_eq!(
+            serde_json::to_value(&handle).unwrap(),
+            json!("left:front:3")
+        );
+        assert_eq!(
+            serde_json::from_value::<EdgeHandle>(json!("left:front:3")).unwrap(),
+            handle
+        );
+
+        assert!(serde_json::from_value::<EdgeHandle>(json!("front")).is_err());
+        assert!(serde_json::from_value::<EdgeHandle>(json!(3)).is_err());
+    }
+
+    #[test]
+    fn names() {
+        let name = PanelId::from("front");
+        assert_eq!(name.as_str(), "front");
+        assert_eq!(name.to_string(), "front");
+        assert!(name == *"front");
+        assert_eq!(serde_json::to_value(&name).unwrap(), json!("front"));
+        assert_eq!(
+            serde_json::from_value::<ParameterName>(json!("length")).unwrap(),
+            ParameterName::from("length")
+        );
+
+        // names can be looked up by `&str`.
+        let mut map = BTreeMap::new();
+        map.insert(ParameterName::from("length"), 1);
+        assert_eq!(map.get("length"), Some(&1));
+    }
+
+    #[test]
+    fn indices() {
+        assert_eq!(serde_json::to_value(EdgeIndex(2)).unwrap(), json!(2));
+        assert_eq!(
+            serde_json::from_value::<VertexIndex>(json!(4)).unwrap(),
+            VertexIndex(4)
+        );
+        assert_eq!(
+            EdgeIndex::all(3).collect::<Vec<_>>(),
+            [EdgeIndex(0), EdgeIndex(1), EdgeIndex(2)]
+        );
+    }
+}
@@ -0,0 +1,371 @@
+//! blending between two garments.
+//!
+//! [`Parameters::interpolate`] blends two parameter sets of a template, and
+//! [`Pattern::interpolate`] blends two patterns with the same topology, i.e.
+//! the same panels, vertices, edges and stitches, usually two instances of
+//! the same template. the blend is linear in everything that is stored: the
+//! parameter values, or the vertices, control points, arc radii and panel
+//! placements of the patterns.
+
+use nalgebra::{
+    Vector2,
+    Vector3,
+};
+use rust_decimal::Decimal;
+
+use crate::{
+    curve::Curve,
+    id::{
+        EdgeIndex,
+        PanelId,
+        ParameterName,
+    },
+    parameters::Parameters,
+    pattern::{
+        Edge,
+        Panel,
+        Pattern,
+    },
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum InterpolationError {
+    #[error("interpolation factor must be between 0 and 1, but is {t}")]
+    InvalidFactor { t: Decimal },
+
+    #[error("parameter {name} is only in one of the parameter sets")]
+    MissingParameter { name: ParameterName },
+
+    #[error("the patterns have different panels")]
+    PanelMismatch,
+
+    #[error("panels have a different number of vertices: {panel}")]
+    VertexMismatch { panel: PanelId },
+
+    #[error("panels have a different number of edges: {panel}")]
+    EdgeCountMismatch { panel: PanelId },
+
+    #[error("edges have different endpoints or kinds of curves: panel={panel}, {edge}")]
+    EdgeMismatch { panel: PanelId, edge: EdgeIndex },
+
+    #[error("the patterns have different stitches")]
+    StitchMismatch,
+}
+
+impl Parameters {
+    /// blends `self` (at `t = 0`) with `other` (at `t = 1`). both sets must
+    /// have the same parameters.
+    pub fn interpolate(
+        &self,
+        other: &Parameters,
+        t: Decimal,
+    ) -> Result<Parameters, InterpolationError> {
+        check_factor(t)?;
+
+        if let Some(name) = other
+            .parameters
+            .keys()
+            .find(|name| !self.parameters.contains_key(*name))
+        {
+            return Err(InterpolationError::MissingParameter { name: name.clone() });
+        }
+
+        let mut parameters = Parameters::default();
+        for (name, a) in &self.parameters {
+            let b = other
+                .parameters
+                .get(name)
+                .ok_or_else(|| InterpolationError::MissingParameter { name: name.clone() })?;
+            parameters.parameters.insert(name.clone(), lerp(*a, *b, t));
+        }
+
+        Ok(parameters)
+    }
+}
+
+impl Pattern {
+    /// blends `self` (at `t = 0`) with `other` (at `t = 1`). this fails if
+    /// the patterns don't have the same topology.
+    ///
+    /// both patterns should store their control points in the same
+    /// curvature coordinates, which is the case for instances of the same
+    /// template.
+    pub fn interpolate(&self, other: &Pattern, t: Decimal) -> Result<Pattern, InterpolationError> {
+        check_factor(t)?;
+
+        if self.panel_order != other.panel_order
+            || self.panels.len() != other.panels.len()
+            || self.panels.keys().ne(other.panels.keys())
+        {
+            return Err(InterpolationError::PanelMismatch);
+        }
+
+        let same_stitches = self.stitches.len() == other.stitches.len()
+            && self.stitches.iter().zip(&other.stitches).all(|(a, b)| {
+                a.0.len() == b.0.len()
+                    && a.0.iter().zip(&b.0).all(|(a, b)| a.handle() == b.handle())
+            });
+        if !same_stitches {
+            return Err(InterpolationError::StitchMismatch);
+        }
+
+        let panels = self
+            .panels
+            .iter()
+            .zip(other.panels.values())
+            .map(|((name, a), b)| Ok((name.clone(), interpolate_panel(name, a, b, t)?)))
+            .collect::<Result<_, InterpolationError>>()?;
+
+        Ok(Pattern {
+            panels,
+            panel_order: self.panel_order.clone(),
+            stitches: self.stitches.clone(),
+        })
+    }
+}
+
+fn check_factor(t: Decimal) -> Result<(), InterpolationError> {
+    if t < Decimal::ZERO || t > Decimal::ONE {
+        return Err(InterpolationError::InvalidFactor { t });
+    }
+    Ok(())
+}
+
+fn interpolate_panel(
+    name: &PanelId,
+    a: &Panel,
+    b: &Panel,
+    t: Decimal,
+) -> Result<Panel, InterpolationError> {
+    if a.vertices.len() != b.vertices.len() {
+        return Err(InterpolationError::VertexMismatch {
+            panel: name.clone(),
+        });
+    }
+    if a.edges.len() != b.edges.len() {
+        return Err(InterpolationError::EdgeCountMismatch {
+            panel: name.clone(),
+        });
+    }
+
+    let edges = a
+        .indexed_edges()
+        .zip(&b.edges)
+        .map(|((index, a), b)| {
+            interpolate_edge(a, b, t).ok_or_else(|| {
+                InterpolationError::EdgeMismatch {
+                    panel: name.clone(),
+                    edge: index,
+                }
+            })
+        })
+        .collect::<Result<_, _>>()?;
+
+    Ok(Panel {
+        translation: lerp_vector3(&a.translation, &b.translation, t),
+        rotation: lerp_vector3(&a.rotation, &b.rotation, t),
+        edges,
+        vertices: a
+            .vertices
+            .iter()
+            .zip(&b.vertices)
+            .map(|(a, b)| Vector2::new(lerp(a.x, b.x, t), lerp(a.y, b.y, t)))
+            .collect(),
+    })
+}
+
+/// blends two edges, or returns `None` if they have different endpoints or
+/// kinds of curves.
+fn interpolate_edge(a: &Edge, b: &Edge, t: Decimal) -> Option<Edge> {
+    if a.endpoints != b.endpoints {
+        return None;
+    }
+
+    let point = |a: &[Decimal; 2], b: &[Decimal; 2]| [lerp(a[0], b[0], t), lerp(a[1], b[1], t)];
+
+    let curve = match (&a.curve, &b.curve) {
+        (Curve::Line, Curve::Line) => Curve::Line,
+        (Curve::Quadratic(a), Curve::Quadratic(b)) => Curve::Quadratic(point(a, b)),
+        (Curve::Cubic(a), Curve::Cubic(b)) => {
+            Curve::Cubic([point(&a[0], &b[0]), point(&a[1], &b[1])])
+        }
+        (
+            Curve::Arc {
+                radius: a,
+                large_arc,
+                sweep,
+            },
+            Curve::Arc {
+                radius: b,
+                large_arc: b_large_arc,
+                sweep: b_sweep,
+            },
+        ) if large_arc == b_large_arc && sweep == b_sweep => {
+            Curve::Arc {
+                radius: lerp(*a, *b, t),
+                large_arc: *large_arc,
+                sweep: *sweep,
+            }
+        }
+        _ => return None,
+    };
+
+    Some(Edge {
+        endpoints: a.endpoints,
+        curve,
+    })
+}
+
+fn lerp(a: Decimal, b: Decimal, t: Decimal) -> Decimal {
+    (a + (b - a) * t).normalize()
+}
+
+fn lerp_vector3(a: &Vector3<Decimal>, b: &Vector3<Decimal>, t: Decimal) -> Vector3<Decimal> {
+    Vector3::new(lerp(a.x, b.x, t), lerp(a.y, b.y, t), lerp(a.z, b.z, t))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use serde_json::json;
+
+    use super::*;
+
+    fn decimal(value: &str) -> Decimal {
+        Decimal::from_str(value).unwrap()
+    }
+
+    fn parameters(values: &[(&str, &str)]) -> Parameters {
+        Parameters {
+            parameters: values
+                .iter()
+                .map(|(name, value)| (ParameterName::from(*name), decimal(value)))
+                .collect(),
+        }
+    }
+
+    fn pattern(size: i64, depth: f64, radius: i64, translation: [i64; 3], angle: i64) -> Pattern {
+        serde_json::from_value(json!({
+            "panels": {
+                "front": {
+                    "translation": translation,
+                    "rotation": [0, angle, 0],
+                    "vertices": [[0, 0], [size, 0], [size, size], [0, size]],
+                    "edges": [
+                        {"endpoints": [0, 1], "curvature": [0.5, depth]},
+                        {"endpoints": [1, 2], "curvature": {"type": "cubic", "controls": [[0.25, depth], [0.75, depth]]}},
+                        {
+                            "endpoints": [2, 3],
+                            "curvature": {"type": "arc", "radius": radius, "large_arc": false, "sweep": true},
+                        },
+                        {"endpoints": [3, 0]},
+                    ],
+                },
+            },
+            "panel_order": ["front"],
+            "stitches": [],
+        }))
+        .unwrap()
+    }
+
+    fn a() -> Pattern {
+        pattern(20, 0.2, 20, [0, 0, 0], 0)
+    }
+
+    fn b() -> Pattern {
+        pattern(40, 0.4, 40, [10, 0, -20], 90)
+    }
+
+    fn assert_same(actual: &Pattern, expected: &Pattern) {
+        assert_eq!(
+            serde_json::to_value(actual).unwrap(),
+            serde_json::to_value(expected).unwrap()
+        );
+    }
+
+    #[test]
+    fn interpolates_parameters() {
+        let a = parameters(&[("length", "0.8"), ("width", "1")]);
+        let b = parameters(&[("length", "1.2"), ("width", "2")]);
+
+        for (t, length, width) in [("0", "0.8", "1"), ("0.5", "1", "1.5"), ("1", "1.2", "2")] {
+            let blend = a.interpolate(&b, decimal(t)).unwrap();
+            assert_eq!(
+                blend.parameters,
+                parameters(&[("length", length), ("width", width)]).parameters,
+                "t = {}",
+                t
+            );
+        }
+    }
+
+    #[test]
+    fn parameters_must_match() {
+        let a = parameters(&[("length", "1")]);
+        let b = parameters(&[("length", "1"), ("width", "1")]);
+
+        for (a, b) in [(&a, &b), (&b, &a)] {
+            assert!(matches!(
+                a.interpolate(b, Decimal::ONE),
+                Err(InterpolationError::MissingParameter { name }) if name.as_str() == "width"
+            ));
+        }
+    }
+
+    #[test]
+    fn interpolates_patterns() {
+        assert_same(&a().interpolate(&b(), Decimal::ZERO).unwrap(), &a());
+        assert_same(&a().interpolate(&b(), Decimal::ONE).unwrap(), &b());
+        assert_same(
+            &a().interpolate(&b(), decimal("0.5")).unwrap(),
+            &pattern(30, 0.3, 30, [5, 0, -10], 45),
+        );
+    }
+
+    #[test]
+    fn invalid_factor() {
+        for t in ["-0.1", "1.5"] {
+            assert!(matches!(
+                a().interpolate(&b(), decimal(t)),
+                Err(InterpolationError::InvalidFactor { .. })
+            ));
+            assert!(matches!(
+                Parameters::default().interpolate(&Parameters::default(), decimal(t)),
+                Err(InterpolationError::InvalidFactor { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn topology_must_match() {
+        let mut other = b();
+        other.panels.get_mut(&PanelId::from("front")).unwrap().edges[0].curve = Curve::Line;
+        assert!(matches!(
+            a().interpolate(&other, decimal("0.5")),
+            Err(InterpolationError::EdgeMismatch {
+                edge: EdgeIndex(0),
+                ..
+            })
+        ));
+
+        let mut other = b();
+        other
+            .panels
+            .get_mut(&PanelId::from("front"))
+            .unwrap()
+            .vertices
+            .pop();
+        assert!(matches!(
+            a().interpolate(&other, decimal("0.5")),
+            Err(InterpolationError::VertexMismatch { .. })
+        ));
+
+        let mut other = b();
+        other.panel_order.clear();
+        assert!(matches!(
+            a().interpolate(&other, decimal("0.5")),
+            Err(InterpolationError::PanelMismatch)
+        ));
+    }
+}
@@ -0,0 +1,258 @@
+//! placement of panels on a sheet for 2D output.
+//!
+//! panels are defined in their own coordinates, so exporting them as they are
+//! puts them on top of each other. [`Pattern::layout`] places them side by
+//! side with shelf packing: panels are sorted by height and put next to each
+//! other in rows (shelves), starting a new shelf below when the sheet is full.
+
+use indexmap::IndexMap;
+use nalgebra::Vector2;
+use rust_decimal::Decimal;
+
+use crate::{
+    aabb::Rect,
+    id::PanelId,
+    pattern::{
+        Pattern,
+        RenderError,
+    },
+};
+
+#[derive(Clone, Debug)]
+pub struct LayoutOptions {
+    /// space between panels, and between shelves.
+    pub spacing: Decimal,
+
+    /// width of the sheet. shelves are never wider than this, unless a single
+    /// panel is.
+    pub sheet_width: Decimal,
+}
+
+/// where the panels are placed.
+#[derive(Clone, Debug, Default)]
+pub struct Layout {
+    /// offset by which the vertices of each panel are moved.
+    pub offsets: IndexMap<PanelId, Vector2<Decimal>>,
+
+    /// size of the area that is covered by the panels. the area starts at the
+    /// origin and extends to the right and down, i.e. panels are placed with
+    /// negative y coordinates.
+    pub size: Vector2<Decimal>,
+}
+
+impl Default for LayoutOptions {
+    /// 2cm spacing on a 150cm wide sheet, which is a common fabric width.
+    fn default() -> Self {
+        Self {
+            spacing: Decimal::TWO,
+            sheet_width: Decimal::from(150),
+        }
+    }
+}
+
+impl Pattern {
+    /// places the panels on a sheet. the pattern isn't changed, use
+    /// [`Pattern::apply_layout`] for that.
+    pub fn layout(&self, options: &LayoutOptions) -> Result<Layout, RenderError> {
+        let mut panels = self
+            .panels()?
+            .map(|(name, panel)| Ok((name, Rect::from(panel.as_aabb(name)?))))
+            .collect::<Result<Vec<_>, RenderError>>()?;
+
+        // tallest panels first. the sort is stable, so panels with the same
+        // height stay in panel order.
+        panels.sort_by_key(|(_, bounds)| std::cmp::Reverse(bounds.height()));
+
+        let mut layout = Layout::default();
+        let mut x = Decimal::ZERO;
+        let mut shelf_top = Decimal::ZERO;
+        let mut shelf_height = Decimal::ZERO;
+
+        for (name, bounds) in panels {
+            if x > Decimal::ZERO && x + bounds.width() > options.sheet_width {
+                shelf_top += shelf_height + options.spacing;
+                shelf_height = Decimal::ZERO;
+                x = Decimal::ZERO;
+            }
+
+            // the panel's top-left corner goes to `(x, -shelf_top)`.
+            let offset = Vector2::new(
+                x - bounds.top_left().x,
+                -shelf_top - bounds.bottom_right().y,
+            );
+            layout.offsets.insert(name.clone(), offset);
+
+            x += bounds.width();
+            shelf_height = shelf_height.max(bounds.height());
+            layout.size.x = layout.size.x.max(x);
+            layout.size.y = shelf_top + shelf_height;
+
+            x += options.spacing;
+        }
+
+        Ok(layout)
+    }
+
+    /// moves the vertices of the panels to where `layout` placed them. the
+    /// panels stay at the same place in 3D.
+    pub fn apply_layout(&mut self, layout: &Layout) -> Result<(), RenderError> {
+        for (name, offset) in &layout.offsets {
+            self.panel_mut(name)?.move_vertices(offset)?;
+        }
+
+        Ok(())
+    }
+
+    /// a copy of this pattern with the panels laid out.
+    pub fn laid_out(&self, options: &LayoutOptions) -> Result<Pattern, RenderError> {
+        let layout = self.layout(options)?;
+        let mut pattern = self.clone();
+        pattern.apply_layout(&layout)?;
+        Ok(pattern)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::id::{
+        EdgeIndex,
+        VertexIndex,
+    };
+
+    fn rectangle(x: i64, y: i64, width: i64, height: i64) -> serde_json::Value {
+        json!({
+            "translation": [0, 0, 0],
+            "rotation": [0, 0, 0],
+            "vertices": [[x, y], [x + width, y], [x + width, y + height], [x, y + height]],
+            "edges": [
+                {"endpoints": [0, 1]},
+                {"endpoints": [1, 2]},
+                {"endpoints": [2, 3]},
+                {"endpoints": [3, 0]},
+            ],
+        })
+    }
+
+    /// panels on top of each other, in their own coordinates.
+    fn pattern() -> Pattern {
+        serde_json::from_value(json!({
+            "panels": {
+                "a": rectangle(0, 0, 40, 20),
+                "b": rectangle(-10, 5, 50, 60),
+                "c": rectangle(3, -2, 30, 30),
+                "d": rectangle(0, 0, 70, 10),
+            },
+            "panel_order": ["a", "b", "c", "d"],
+            "stitches": [],
+        }))
+        .unwrap()
+    }
+
+    fn bounds(pattern: &Pattern) -> Vec<(PanelId, Rect<Decimal>)> {
+        pattern
+            .panels()
+            .unwrap()
+            .map(|(name, panel)| (name.clone(), Rect::from(panel.as_aabb(name).unwrap())))
+            .collect()
+    }
+
+    fn overlap(a: &Rect<Decimal>, b: &Rect<Decimal>, spacing: Decimal) -> bool {
+        a.top_left().x < b.bottom_right().x + spacing
+            && b.top_left().x < a.bottom_right().x + spacing
+            && a.top_left().y < b.bottom_right().y + spacing
+            && b.top_left().y < a.bottom_right().y + spacing
+    }
+
+    #[test]
+    fn shelves_do_not_overlap() {
+        let options = LayoutOptions {
+            spacing: Decimal::TWO,
+            sheet_width: Decimal::from(100),
+        };
+        let pattern = pattern();
+        let layout = pattern.layout(&options).unwrap();
+        let bounds = bounds(&pattern.laid_out(&options).unwrap());
+
+        for (i, (a_name, a)) in bounds.iter().enumerate() {
+            assert!(
+                a.bottom_right().y <= Decimal::ZERO,
+                "{} is above the sheet",
+                a_name
+            );
+            assert!(
+                a.top_left().x >= Decimal::ZERO,
+                "{} is left of the sheet",
+                a_name
+            );
+            assert!(
+                a.bottom_right().x <= options.sheet_width,
+                "{} is right of the sheet",
+                a_name
+            );
+
+            for (b_name, b) in &bounds[i + 1..] {
+                assert!(
+                    !overlap(a, b, options.spacing),
+                    "{} and {} are closer than the spacing",
+                    a_name,
+                    b_name
+                );
+            }
+        }
+
+        // the tallest panel starts the first shelf and the next one fits next
+        // to it. the other two don't fit next to each other, so they each get
+        // their own shelf. `Rect` has the smallest coordinates in `top_left`,
+        // so the top edge of a panel is `bottom_right().y`.
+        let corner = |name: &str| {
+            let (_, rect) = bounds
+                .iter()
+                .find(|(panel, _)| panel.as_str() == name)
+                .unwrap();
+            Vector2::new(rect.top_left().x, rect.bottom_right().y)
+        };
+        assert_eq!(corner("b"), Vector2::new(Decimal::ZERO, Decimal::ZERO));
+        assert_eq!(corner("c"), Vector2::new(Decimal::from(52), Decimal::ZERO));
+        assert_eq!(corner("a"), Vector2::new(Decimal::ZERO, Decimal::from(-62)));
+        assert_eq!(corner("d"), Vector2::new(Decimal::ZERO, Decimal::from(-84)));
+        assert_eq!(
+            layout.size,
+            Vector2::new(Decimal::from(82), Decimal::from(94))
+        );
+    }
+
+    #[test]
+    fn layout_keeps_panels_in_place() {
+        let pattern = pattern();
+        let laid_out = pattern.laid_out(&LayoutOptions::default()).unwrap();
+
+        let name = PanelId::from("c");
+        let before = pattern.panels[&name]
+            .geometry(&name, &mut crate::geometry::Converter::lossy())
+            .unwrap();
+        let after = laid_out.panels[&name]
+            .geometry(&name, &mut crate::geometry::Converter::lossy())
+            .unwrap();
+
+        for (a, b) in before.vertices.iter().zip(&after.vertices) {
+            assert!((before.to_world(a) - after.to_world(b)).norm() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn invalid_edges_fail() {
+        let mut pattern = pattern();
+        pattern.panels.get_mut(&PanelId::from("b")).unwrap().edges[1].endpoints[1] = VertexIndex(9);
+
+        assert!(matches!(
+            pattern.layout(&LayoutOptions::default()),
+            Err(RenderError::MissingEndpoint {
+                edge: EdgeIndex(1),
+                ..
+            })
+        ));
+    }
+}
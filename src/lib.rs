@@ -0,0 +1,65 @@
+//! rust 🦀 tools to work with sewing patterns.
+//!
+//! the main types are [`Template`], which is read from a dataset with
+//! [`reader`], and [`Pattern`], which is what a template is instantiated into
+//! with [`Template::with_parameters`]. patterns can be rendered with
+//! [`render`] and converted from and to other formats with [`convert`].
+//!
+//! patterns store their coordinates as decimals, so that templates
+//! round-trip exactly. for geometry they are resolved into `f64`s with
+//! [`geometry`]. panels can be placed next to each other with [`layout`], or
+//! nested on a roll of fabric for cutting with [`nesting`].
+//!
+//! # features
+//!
+//!  - `zip`: [`reader`] for templates in zipped datasets.
+//!  - `svg`: [`render`] patterns to SVG.
+//!  - `seamly2d`: importer for Seamly2D files in [`convert::seamly2d`].
+//!  - `cli`: the `skirt-rs` binary. this enables all other features.
+
+pub mod aabb;
+pub mod constraints;
+pub mod consumption;
+pub mod convert;
+pub mod curve;
+pub mod error;
+pub mod fitting;
+pub mod geometry;
+pub mod grading;
+pub mod id;
+pub mod interpolation;
+pub mod layout;
+pub mod nesting;
+mod normalize;
+pub mod parameters;
+pub mod pattern;
+#[cfg(feature = "zip")]
+pub mod reader;
+#[cfg(feature = "svg")]
+pub mod render;
+pub mod report;
+pub mod stitch;
+pub mod sweep;
+#[cfg(test)]
+mod testing;
+
+pub use crate::{
+    curve::Curve,
+    error::Error,
+    id::{
+        EdgeHandle,
+        EdgeIndex,
+        PanelId,
+        ParameterName,
+        VertexIndex,
+    },
+    parameters::Parameters,
+    pattern::{
+        Edge,
+        Panel,
+        Pattern,
+        RenderError,
+        Template,
+    },
+    stitch::StitchGraph,
+};
@@ -0,0 +1,737 @@
+//! nesting of panels on a roll of fabric for cutting, i.e. making a marker.
+//!
+//! the roll has a fixed width and the marker should use as little of its
+//! length as possible. panels are turned so that their grain line runs along
+//! the roll, and after that may only be turned by 180°. paired pieces can be
+//! cut as mirrored copies.
+//!
+//! the cutting lines (the outlines with seam allowance) are rasterized, and
+//! the pieces are placed greedily, largest first, at the position that is
+//! furthest to the start of the roll and then closest to the selvage. the
+//! raster is conservative, so pieces are always at least `spacing` apart.
+//!
+//! marker coordinates have the x axis along the roll and the y axis across
+//! it, with the origin at the start of the roll on one selvage.
+
+use std::{
+    collections::BTreeMap,
+    f64::consts::FRAC_1_SQRT_2,
+};
+
+use nalgebra::{
+    Matrix2,
+    Vector2,
+};
+
+use crate::{
+    geometry::{
+        signed_area,
+        Converter,
+    },
+    id::PanelId,
+    pattern::{
+        Pattern,
+        RenderError,
+    },
+};
+
+/// points closer than this are merged when outlines are prepared.
+const EPSILON: f64 = 1e-9;
+
+#[derive(Debug, thiserror::Error)]
+pub enum NestingError {
+    #[error("invalid value for {name}: {value}")]
+    InvalidOption { name: &'static str, value: f64 },
+
+    #[error("panel {panel} is {width} wide, which doesn't fit on the fabric")]
+    TooWide { panel: PanelId, width: f64 },
+
+    #[error(transparent)]
+    Render(#[from] RenderError),
+}
+
+/// how a [`Pattern`] is nested. lengths are in pattern units.
+#[derive(Clone, Debug)]
+pub struct NestingOptions {
+    /// width of the fabric roll.
+    pub fabric_width: f64,
+
+    /// smallest distance between the cutting lines of two pieces.
+    pub spacing: f64,
+
+    /// added around the outline of every panel.
+    pub seam_allowance: f64,
+
+    /// how many times panels are cut. panels that aren't in here are cut
+    /// once.
+    pub cut: BTreeMap<PanelId, u32>,
+
+    /// cut every second copy of a panel mirrored, e.g. for left and right
+    /// pieces.
+    pub mirror_pairs: bool,
+
+    /// allow pieces to be turned by 180°.
+    pub rotate: bool,
+
+    /// size of the raster cells. smaller cells nest tighter, but are slower.
+    pub resolution: f64,
+}
+
+/// placements of all pieces on the fabric.
+#[derive(Clone, Debug)]
+pub struct Marker {
+    pub fabric_width: f64,
+
+    /// length of fabric that is used, up to the end of the last cutting line.
+    pub length: f64,
+
+    pub placements: Vec<Placement>,
+}
+
+/// where a copy of a panel is placed.
+#[derive(Clone, Debug)]
+pub struct Placement {
+    pub panel: PanelId,
+
+    /// which copy of the panel this is, starting at 0.
+    pub copy: u32,
+
+    pub mirrored: bool,
+
+    /// whether the piece is turned by 180°.
+    pub rotated: bool,
+
+    /// maps panel coordinates to marker coordinates, with
+    /// `linear * point + offset`.
+    pub linear: Matrix2<f64>,
+    pub offset: Vector2<f64>,
+
+    /// the outline of the panel, in marker coordinates.
+    pub seam_line: Vec<Vector2<f64>>,
+
+    /// the outline with seam allowance, in marker coordinates.
+    pub cut_line: Vec<Vector2<f64>>,
+}
+
+/// a panel in one orientation, rasterized.
+#[derive(Clone, Debug)]
+struct Shape {
+    mirrored: bool,
+    rotated: bool,
+    linear: Matrix2<f64>,
+
+    /// position of the raster's origin, in oriented panel coordinates.
+    origin: Vector2<f64>,
+
+    mask: Mask,
+}
+
+/// occupied cells of a raster, as spans of columns for every row.
+#[derive(Clone, Debug)]
+struct Mask {
+    width: usize,
+    rows: Vec<Vec<(usize, usize)>>,
+}
+
+/// occupied cells of the fabric. every row is a bitset of columns along the
+/// roll.
+#[derive(Clone, Debug)]
+struct Fabric {
+    rows: Vec<Vec<u64>>,
+
+    /// number of columns that contain occupied cells.
+    length: usize,
+}
+
+impl Default for NestingOptions {
+    /// 150cm wide fabric, 1cm seam allowance, and 0.5cm between pieces.
+    fn default() -> Self {
+        Self {
+            fabric_width: 150.0,
+            spacing: 0.5,
+            seam_allowance: 1.0,
+            cut: BTreeMap::new(),
+            mirror_pairs: false,
+            rotate: true,
+            resolution: 0.5,
+        }
+    }
+}
+
+impl NestingOptions {
+    fn validate(&self) -> Result<(), NestingError> {
+        let positive = [
+            ("fabric width", self.fabric_width),
+            ("resolution", self.resolution),
+        ];
+        let non_negative = [
+            ("spacing", self.spacing),
+            ("seam allowance", self.seam_allowance),
+        ];
+
+        let invalid = positive
+            .into_iter()
+            .find(|(_, value)| !(value.is_finite() && *value > 0.0))
+            .or_else(|| {
+                non_negative
+                    .into_iter()
+                    .find(|(_, value)| !(value.is_finite() && *value >= 0.0))
+            });
+
+        match invalid {
+            Some((name, value)) => Err(NestingError::InvalidOption { name, value }),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Marker {
+    /// area of the cutting lines relative to the area of fabric used.
+    pub fn utilization(&self) -> f64 {
+        let used = self
+            .placements
+            .iter()
+            .map(|placement| signed_area(&placement.cut_line).abs())
+            .sum::<f64>();
+        used / (self.fabric_width * self.length)
+    }
+}
+
+impl Placement {
+    /// converts a point in panel coordinates to marker coordinates.
+    pub fn to_marker(&self, point: &Vector2<f64>) -> Vector2<f64> {
+        self.linear * point + self.offset
+    }
+
+    /// minimum and maximum corner of the cutting line, in marker coordinates.
+    pub fn bounding_box(&self) -> (Vector2<f64>, Vector2<f64>) {
+        bounds(self.cut_line.iter().copied())
+    }
+}
+
+impl Pattern {
+    /// nests all panels on a roll of fabric.
+    pub fn nest(&self, options: &NestingOptions) -> Result<Marker, NestingError> {
+        options.validate()?;
+
+        let geometry = self.geometry(&mut Converter::lossy())?;
+        let pad = options.spacing / 2.0;
+
+        let mut pieces = vec![];
+        for (name, panel) in &geometry.panels {
+            let mut seam_line = panel.outline(options.resolution).ok_or_else(|| {
+                RenderError::NotALoop {
+                    panel: name.clone(),
+                }
+            })?;
+            seam_line.dedup_by(|a, b| (*a - *b).norm() < EPSILON);
+            while seam_line.len() > 1
+                && (seam_line[0] - seam_line[seam_line.len() - 1]).norm() < EPSILON
+            {
+                seam_line.pop();
+            }
+
+            let cut_line = offset_polygon(&seam_line, options.seam_allowance);
+
+            // turn the panel so that the grain runs along the roll.
+            let grain = panel.grain_direction();
+            let aligned = Matrix2::new(grain.x, grain.y, -grain.y, grain.x);
+
+            let copies = options.cut.get(name).copied().unwrap_or(1);
+            for copy in 0..copies {
+                let mirrored = options.mirror_pairs && copy % 2 == 1;
+                let turns: &[bool] = if options.rotate {
+                    &[false, true]
+                }
+                else {
+                    &[false]
+                };
+
+                let shapes = turns
+                    .iter()
+                    .map(|rotated| {
+                        Shape::new(
+                            &cut_line,
+                            orientation(&aligned, mirrored, *rotated),
+                            mirrored,
+                            *rotated,
+                            pad,
+                            options.resolution,
+                        )
+                    })
+                    .collect::<Vec<_>>();
+
+                pieces.push((name, copy, seam_line.clone(), cut_line.clone(), shapes));
+            }
+        }
+
+        // largest pieces first. the sort is stable, so copies stay together.
+        pieces.sort_by(|a, b| signed_area(&b.3).abs().total_cmp(&signed_area(&a.3).abs()));
+
+        let rows = (options.fabric_width / options.resolution).floor() as usize;
+        let mut fabric = Fabric::new(rows);
+        let mut marker = Marker {
+            fabric_width: options.fabric_width,
+            length: 0.0,
+            placements: vec![],
+        };
+
+        for (name, copy, seam_line, cut_line, shapes) in pieces {
+            let (shape, (x, y)) = shapes
+                .iter()
+                .filter_map(|shape| Some((shape, fabric.find(&shape.mask)?)))
+                .min_by_key(|(_, position)| *position)
+                .ok_or_else(|| {
+                    let extent = cut_line.iter().map(|point| shapes[0].linear * point);
+                    let (min, max) = bounds(extent);
+                    NestingError::TooWide {
+                        panel: name.clone(),
+                        width: max.y - min.y,
+                    }
+                })?;
+            fabric.occupy(&shape.mask, x, y);
+
+            let offset = Vector2::new(x as f64, y as f64) * options.resolution - shape.origin;
+            let to_marker = |point: &Vector2<f64>| shape.linear * point + offset;
+
+            let placement = Placement {
+                panel: name.clone(),
+                copy,
+                mirrored: shape.mirrored,
+                rotated: shape.rotated,
+                linear: shape.linear,
+                offset,
+                seam_line: seam_line.iter().map(to_marker).collect(),
+                cut_line: cut_line.iter().map(to_marker).collect(),
+            };
+
+            let end = placement
+                .cut_line
+                .iter()
+                .map(|point| point.x)
+                .fold(0.0, f64::max);
+            marker.length = marker.length.max(end);
+            marker.placements.push(placement);
+        }
+
+        Ok(marker)
+    }
+}
+
+impl Shape {
+    fn new(
+        cut_line: &[Vector2<f64>],
+        linear: Matrix2<f64>,
+        mirrored: bool,
+        rotated: bool,
+        pad: f64,
+        resolution: f64,
+    ) -> Self {
+        let oriented = cut_line
+            .iter()
+            .map(|point| linear * point)
+            .collect::<Vec<_>>();
+        let (min, max) = bounds(oriented.iter().copied());
+
+        let origin = min - Vector2::repeat(pad);
+        let size = max - min + Vector2::repeat(2.0 * pad);
+        let local = oriented
+            .iter()
+            .map(|point| point - origin)
+            .collect::<Vec<_>>();
+
+        // a cell is occupied if any part of it is within `pad` of the cutting
+        // line, so that two pieces are at least `2 * pad` apart.
+        let mask = Mask::new(
+            &local,
+            pad + resolution * FRAC_1_SQRT_2,
+            resolution,
+            (size.x / resolution).ceil() as usize,
+            (size.y / resolution).ceil() as usize,
+        );
+
+        Self {
+            mirrored,
+            rotated,
+            linear,
+            origin,
+            mask,
+        }
+    }
+}
+
+impl Mask {
+    /// rasterizes the cells whose centers are inside `polygon` or within
+    /// `margin` of it.
+    fn new(
+        polygon: &[Vector2<f64>],
+        margin: f64,
+        resolution: f64,
+        width: usize,
+        height: usize,
+    ) -> Self {
+        let mut cells = vec![vec![false; width]; height];
+        let center = |index: usize| (index as f64 + 0.5) * resolution;
+        let cell_range = |from: f64, to: f64, count: usize| {
+            let first = (from / resolution - 0.5).ceil().max(0.0) as usize;
+            let last = ((to / resolution - 0.5).floor() + 1.0).clamp(0.0, count as f64) as usize;
+            first..last
+        };
+        let edges = || {
+            polygon
+                .iter()
+                .zip(polygon.iter().cycle().skip(1))
+                .take(polygon.len())
+        };
+
+        // inside, with the even-odd rule.
+        for (row, cells) in cells.iter_mut().enumerate() {
+            let y = center(row);
+            let mut crossings = edges()
+                .filter(|(a, b)| (a.y <= y) != (b.y <= y))
+                .map(|(a, b)| a.x + (y - a.y) * (b.x - a.x) / (b.y - a.y))
+                .collect::<Vec<_>>();
+            crossings.sort_by(f64::total_cmp);
+
+            for pair in crossings.chunks_exact(2) {
+                for column in cell_range(pair[0], pair[1], width) {
+                    cells[column] = true;
+                }
+            }
+        }
+
+        // close to the outline.
+        for (a, b) in edges() {
+            let min = a.inf(b).add_scalar(-margin);
+            let max = a.sup(b).add_scalar(margin);
+
+            for row in cell_range(min.y, max.y, height) {
+                for column in cell_range(min.x, max.x, width) {
+                    let point = Vector2::new(center(column), center(row));
+                    if distance_to_segment(&point, a, b) <= margin {
+                        cells[row][column] = true;
+                    }
+                }
+            }
+        }
+
+        let rows = cells
+            .iter()
+            .map(|cells| {
+                let mut spans = vec![];
+                let mut start = None;
+                for (column, occupied) in cells.iter().chain([&false]).enumerate() {
+                    match (start, occupied) {
+                        (None, true) => start = Some(column),
+                        (Some(first), false) => {
+                            spans.push((first, column));
+                            start = None;
+                        }
+                        _ => {}
+                    }
+                }
+                spans
+            })
+            .collect();
+
+        Self { width, rows }
+    }
+
+    fn height(&self) -> usize {
+        self.rows.len()
+    }
+}
+
+impl Fabric {
+    fn new(rows: usize) -> Self {
+        Self {
+            rows: vec![vec![]; rows],
+            length: 0,
+        }
+    }
+
+    /// the first position where `mask` fits, by column and then by row.
+    /// returns `None` if the mask is wider than the fabric.
+    fn find(&self, mask: &Mask) -> Option<(usize, usize)> {
+        let last_row = self.rows.len().checked_sub(mask.height())?;
+
+        // everything after `length` is free, so the loop finds a position
+        // there at the latest.
+        (0..=self.length).find_map(|x| {
+            (0..=last_row)
+                .find(|y| self.fits(mask, x, *y))
+                .map(|y| (x, y))
+        })
+    }
+
+    fn fits(&self, mask: &Mask, x: usize, y: usize) -> bool {
+        mask.rows.iter().enumerate().all(|(row, spans)| {
+            spans
+                .iter()
+                .all(|(start, end)| is_free(&self.rows[y + row], x + start, x + end))
+        })
+    }
+
+    fn occupy(&mut self, mask: &Mask, x: usize, y: usize) {
+        for (row, spans) in mask.rows.iter().enumerate() {
+            for (start, end) in spans {
+                set_range(&mut self.rows[y + row], x + start, x + end);
+            }
+        }
+        self.length = self.length.max(x + mask.width);
+    }
+}
+
+/// whether no bit in `start..end` is set.
+fn is_free(row: &[u64], start: usize, end: usize) -> bool {
+    word_masks(start, end).all(|(word, mask)| row.get(word).is_none_or(|bits| bits & mask == 0))
+}
+
+fn set_range(row: &mut Vec<u64>, start: usize, end: usize) {
+    for (word, mask) in word_masks(start, end) {
+        if row.len() <= word {
+            row.resize(word + 1, 0);
+        }
+        row[word] |= mask;
+    }
+}
+
+/// splits the bit range `start..end` into words and the bits in them.
+fn word_masks(start: usize, end: usize) -> impl Iterator<Item = (usize, u64)> {
+    let mut bit = start;
+    std::iter::from_fn(move || {
+        if bit >= end {
+            return None;
+        }
+
+        let word = bit / 64;
+        let offset = bit % 64;
+        let count = (64 - offset).min(end - bit);
+        let mask = if count == 64 {
+            u64::MAX
+        }
+        else {
+            ((1 << count) - 1) << offset
+        };
+
+        bit += count;
+        Some((word, mask))
+    })
+}
+
+/// maps panel coordinates to the orientation of a piece. the panel is first
+/// turned so that its grain runs along the roll, then mirrored across the
+/// grain line, then turned by 180°.
+fn orientation(aligned: &Matrix2<f64>, mirrored: bool, rotated: bool) -> Matrix2<f64> {
+    let mut linear = *aligned;
+    if mirrored {
+        linear = Matrix2::new(1.0, 0.0, 0.0, -1.0) * linear;
+    }
+    if rotated {
+        linear = -linear;
+    }
+    linear
+}
+
+/// offsets a closed polygon outwards by `distance`. corners where the offset
+/// would stick out further than twice the distance are beveled.
+fn offset_polygon(polygon: &[Vector2<f64>], distance: f64) -> Vec<Vector2<f64>> {
+    if distance == 0.0 || polygon.len() < 3 {
+        return polygon.to_vec();
+    }
+
+    // outwards is right of the direction of counter-clockwise polygons.
+    let sign = signed_area(polygon).signum();
+    let outwards = |direction: Vector2<f64>| Vector2::new(direction.y, -direction.x) * sign;
+
+    let count = polygon.len();
+    let mut offset = Vec::with_capacity(count);
+
+    for (index, point) in polygon.iter().enumerate() {
+        let previous = polygon[(index + count - 1) % count];
+        let next = polygon[(index + 1) % count];
+
+        let (Some(incoming), Some(outgoing)) = (
+            (point - previous).try_normalize(EPSILON),
+            (next - point).try_normalize(EPSILON),
+        )
+        else {
+            continue;
+        };
+
+        let (a, b) = (outwards(incoming), outwards(outgoing));
+        let cosine = a.dot(&b);
+
+        if cosine < -0.5 {
+            offset.push(point + a * distance);
+            offset.push(point + b * distance);
+        }
+        else {
+            offset.push(point + (a + b) * (distance / (1.0 + cosine)));
+        }
+    }
+
+    offset
+}
+
+fn bounds(points: impl IntoIterator<Item = Vector2<f64>>) -> (Vector2<f64>, Vector2<f64>) {
+    points.into_iter().fold(
+        (
+            Vector2::repeat(f64::INFINITY),
+            Vector2::repeat(f64::NEG_INFINITY),
+        ),
+        |(min, max), point| (min.inf(&point), max.sup(&point)),
+    )
+}
+
+fn distance_to_segment(point: &Vector2<f64>, a: &Vector2<f64>, b: &Vector2<f64>) -> f64 {
+    let direction = b - a;
+    let length_squared = direction.norm_squared();
+    let t = if length_squared > 0.0 {
+        ((point - a).dot(&direction) / length_squared).clamp(0.0, 1.0)
+    }
+    else {
+        0.0
+    };
+    (point - (a + direction * t)).norm()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::testing::{
+        self,
+        rectangle,
+    };
+
+    fn pattern(panels: &[(&str, i64, i64)]) -> Pattern {
+        let panels = panels
+            .iter()
+            .map(|(name, width, height)| (*name, rectangle(*width, *height)))
+            .collect();
+        testing::pattern(panels, json!([]))
+    }
+
+    fn separated(a: &Placement, b: &Placement, spacing: f64) -> bool {
+        let (a_min, a_max) = a.bounding_box();
+        let (b_min, b_max) = b.bounding_box();
+        let tolerance = 1e-9;
+
+        a_max.x + spacing <= b_min.x + tolerance
+            || b_max.x + spacing <= a_min.x + tolerance
+            || a_max.y + spacing <= b_min.y + tolerance
+            || b_max.y + spacing <= a_min.y + tolerance
+    }
+
+    #[test]
+    fn pieces_do_not_overlap() {
+        let pattern = pattern(&[("front", 20, 50), ("back", 25, 55), ("waistband", 8, 40)]);
+        let options = NestingOptions {
+            fabric_width: 60.0,
+            cut: [(PanelId::from("front"), 2), (PanelId::from("waistband"), 3)]
+                .into_iter()
+                .collect(),
+            mirror_pairs: true,
+            ..Default::default()
+        };
+        let marker = pattern.nest(&options).unwrap();
+
+        assert_eq!(marker.placements.len(), 6);
+        for (i, a) in marker.placements.iter().enumerate() {
+            let (min, max) = a.bounding_box();
+            assert!(min.x >= 0.0 && min.y >= 0.0, "{} starts outside", a.panel);
+            assert!(
+                max.y <= options.fabric_width,
+                "{} is off the fabric",
+                a.panel
+            );
+            assert!(max.x <= marker.length + 1e-9);
+            assert_eq!(a.mirrored, a.copy % 2 == 1);
+
+            for b in &marker.placements[i + 1..] {
+                assert!(
+                    separated(a, b, options.spacing),
+                    "{} {} and {} {} overlap",
+                    a.panel,
+                    a.copy,
+                    b.panel,
+                    b.copy
+                );
+            }
+        }
+
+        let utilization = marker.utilization();
+        assert!(utilization > 0.0 && utilization <= 1.0);
+    }
+
+    #[test]
+    fn grain_runs_along_the_roll() {
+        let marker = pattern(&[("panel", 10, 100)])
+            .nest(&NestingOptions {
+                fabric_width: 30.0,
+                ..Default::default()
+            })
+            .unwrap();
+
+        let (min, max) = marker.placements[0].bounding_box();
+        assert!((max.x - min.x - 102.0).abs() < 1e-6);
+        assert!((max.y - min.y - 12.0).abs() < 1e-6);
+        assert!((marker.length - max.x).abs() < 1e-9);
+
+        // the seam line is inside the cut line, by the seam allowance.
+        let (seam_min, _) = bounds(marker.placements[0].seam_line.iter().copied());
+        assert!((seam_min - min - Vector2::new(1.0, 1.0)).norm() < 1e-6);
+    }
+
+    #[test]
+    fn too_wide() {
+        let result = pattern(&[("panel", 80, 10)]).nest(&NestingOptions {
+            fabric_width: 50.0,
+            ..Default::default()
+        });
+
+        match result {
+            Err(NestingError::TooWide { panel, width }) => {
+                assert_eq!(panel.as_str(), "panel");
+                assert!((width - 82.0).abs() < 1e-6);
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn invalid_options() {
+        let pattern = pattern(&[("panel", 10, 10)]);
+
+        for (options, expected) in [
+            (
+                NestingOptions {
+                    resolution: 0.0,
+                    ..Default::default()
+                },
+                "resolution",
+            ),
+            (
+                NestingOptions {
+                    spacing: -1.0,
+                    ..Default::default()
+                },
+                "spacing",
+            ),
+            (
+                NestingOptions {
+                    fabric_width: f64::NAN,
+                    ..Default::default()
+                },
+                "fabric width",
+            ),
+        ] {
+            assert!(matches!(
+                pattern.nest(&options),
+                Err(NestingError::InvalidOption { name, .. }) if name == expected
+            ));
+        }
+    }
+}
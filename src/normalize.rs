@@ -0,0 +1,497 @@
+//! normalization of instantiated patterns, as described by
+//! [`Properties::normalize_panel_translation`] and
+//! [`Properties::normalized_edge_loops`]. normalized patterns are comparable
+//! across samples of the same template.
+//!
+//! [`Properties::normalize_panel_translation`]: crate::pattern::Properties::normalize_panel_translation
+//! [`Properties::normalized_edge_loops`]: crate::pattern::Properties::normalized_edge_loops
+
+use nalgebra::{
+    Rotation3,
+    Vector2,
+    Vector3,
+};
+use rust_decimal::Decimal;
+
+use crate::{
+    geometry::{
+        ConversionError,
+        Converter,
+    },
+    id::{
+        EdgeIndex,
+        PanelId,
+        VertexIndex,
+    },
+    pattern::{
+        Panel,
+        Pattern,
+        RenderError,
+    },
+};
+
+/// number of decimal places kept for translations that had to be rotated.
+const DECIMAL_PLACES: u32 = 6;
+
+/// where an edge of a normalized panel came from.
+#[derive(Copy, Clone, Debug)]
+struct MovedEdge {
+    /// index of the edge before normalization.
+    index: usize,
+
+    /// whether the edge's direction was reversed.
+    reversed: bool,
+}
+
+impl Pattern {
+    /// shifts every panel's vertices so that the bottom-left corner of their
+    /// bounding box is at the panel's origin. the panel's translation is
+    /// adjusted, so that the panel stays at the same place in 3D.
+    ///
+    /// this fails if a panel's rotation can't be applied to the offset.
+    pub fn normalize_panel_translations(&mut self) -> Result<(), RenderError> {
+        for panel in self.panels.values_mut() {
+            panel.normalize_translation()?;
+        }
+
+        Ok(())
+    }
+
+    /// reorders every panel's edges into a closed, counter-clockwise loop that
+    /// starts at the vertex closest to the bottom-left corner of the panel's
+    /// bounding box. the vertices are reordered to follow the loop, so that
+    /// edge `i` starts at vertex `i`. stitches are updated to point to the
+    /// reordered edges.
+    ///
+    /// edges that the loop follows from their end to their start are reversed:
+    /// their endpoints are swapped and their curves are reversed, so they keep
+    /// their shape. a stitch still connects the same points, but the reversed
+    /// side of it now runs the other way, which flips the stitch's
+    /// orientation.
+    ///
+    /// this fails if a panel's edges don't form a single closed loop. the
+    /// pattern isn't changed then.
+    pub fn normalize_edge_loops(&mut self) -> Result<(), RenderError> {
+        let mut panels = self.panels.clone();
+        let mut stitches = self.stitches.clone();
+
+        for (name, panel) in &mut panels {
+            let moved = panel.normalize_edge_loop(name)?;
+
+            for stitch in &mut stitches {
+                for side in stitch.0.iter_mut().filter(|side| &side.panel == name) {
+                    if let Some(index) = moved.iter().position(|moved| moved.index == side.edge.0) {
+                        side.edge = EdgeIndex(index);
+                    }
+                }
+            }
+        }
+
+        self.panels = panels;
+        self.stitches = stitches;
+
+        Ok(())
+    }
+}
+
+impl Panel {
+    fn normalize_translation(&mut self) -> Result<(), ConversionError> {
+        match bottom_left(&self.vertices) {
+            Some(offset) => self.move_vertices(&-offset),
+            None => Ok(()),
+        }
+    }
+
+    /// moves all vertices by `offset`. the panel's translation is adjusted, so
+    /// that the panel stays at the same place in 3D.
+    ///
+    /// this fails if the panel's rotation can't be applied to the offset.
+    pub fn move_vertices(&mut self, offset: &Vector2<Decimal>) -> Result<(), ConversionError> {
+        let shift = Vector3::new(offset.x, offset.y, Decimal::ZERO);
+
+        let shift = if self.rotation.iter().all(Decimal::is_zero) {
+            shift
+        }
+        else {
+            let mut converter = Converter::lossy();
+            let angles = converter
+                .to_f64_vector(&self.rotation)?
+                .map(f64::to_radians);
+            let rotation = Rotation3::from_euler_angles(angles.x, angles.y, angles.z);
+            let rotated = rotation * converter.to_f64_vector(&shift)?;
+            converter.to_decimal_vector(&rotated, DECIMAL_PLACES)?
+        };
+
+        for vertex in &mut self.vertices {
+            *vertex += offset;
+        }
+        self.translation -= shift;
+
+        Ok(())
+    }
+
+    fn normalize_edge_loop(&mut self, name: &PanelId) -> Result<Vec<MovedEdge>, RenderError> {
+        if self.edges.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let not_a_loop = || {
+            RenderError::NotALoop {
+                panel: name.clone(),
+            }
+        };
+
+        for edge in &self.edges {
+            for index in edge.endpoints {
+                self.get_vertex(index)?;
+            }
+        }
+
+        // start at the vertex closest to the bottom-left corner.
+        let corner = bottom_left(&self.vertices).unwrap_or_default();
+        let origin = self
+            .edges
+            .iter()
+            .flat_map(|edge| edge.endpoints)
+            .min_by_key(|index| {
+                let distance = self.vertices[index.0] - corner;
+                (distance.x * distance.x + distance.y * distance.y, *index)
+            })
+            .ok_or_else(not_a_loop)?;
+
+        // walk along the edges until we're back at the origin.
+        let mut used = vec![false; self.edges.len()];
+        let mut moved = Vec::with_capacity(self.edges.len());
+        let mut current = origin;
+
+        while moved.len() < self.edges.len() {
+            let (index, reversed) = self
+                .edges
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| !used[*index])
+                .find_map(|(index, edge)| {
+                    if edge.endpoints[0] == current {
+                        Some((index, false))
+                    }
+                    else if edge.endpoints[1] == current {
+                        Some((index, true))
+                    }
+                    else {
+                        None
+                    }
+                })
+                .ok_or_else(not_a_loop)?;
+
+            used[index] = true;
+            moved.push(MovedEdge { index, reversed });
+
+            current = self.edges[index].endpoints[if reversed { 0 } else { 1 }];
+            if current == origin && moved.len() < self.edges.len() {
+                return Err(not_a_loop());
+            }
+        }
+
+        if current != origin {
+            return Err(not_a_loop());
+        }
+
+        let loop_vertices = moved
+            .iter()
+            .map(|moved| self.edges[moved.index].endpoints[if moved.reversed { 1 } else { 0 }])
+            .collect::<Vec<_>>();
+
+        // make the loop counter-clockwise. the area ignores curves, which is
+        // enough to tell the orientation.
+        if signed_area(loop_vertices.iter().map(|index| &self.vertices[index.0])) < Decimal::ZERO {
+            moved.reverse();
+            for moved in &mut moved {
+                moved.reversed = !moved.reversed;
+            }
+        }
+
+        let old_edges = std::mem::take(&mut self.edges);
+        let old_vertices = std::mem::take(&mut self.vertices);
+
+        // vertex `i` is the start of edge `i`. vertices that aren't part of the
+        // loop are kept at the end.
+        let mut vertex_map = vec![None; old_vertices.len()];
+        for (new_index, moved) in moved.iter().enumerate() {
+            let edge = &old_edges[moved.index];
+            let start = edge.endpoints[if moved.reversed { 1 } else { 0 }];
+            vertex_map[start.0] = Some(VertexIndex(new_index));
+            self.vertices.push(old_vertices[start.0]);
+        }
+        for (old_index, vertex) in old_vertices.iter().enumerate() {
+            if vertex_map[old_index].is_none() {
+                vertex_map[old_index] = Some(VertexIndex(self.vertices.len()));
+                self.vertices.push(*vertex);
+            }
+        }
+
+        for moved in &moved {
+            let mut edge = old_edges[moved.index].clone();
+            if moved.reversed {
+                edge = edge.reversed();
+            }
+            edge.endpoints = edge
+                .endpoints
+                .map(|index| vertex_map[index.0].expect("all vertices are mapped"));
+            self.edges.push(edge);
+        }
+
+        Ok(moved)
+    }
+}
+
+/// the bottom-left corner of the bounding box of `vertices`.
+fn bottom_left(vertices: &[Vector2<Decimal>]) -> Option<Vector2<Decimal>> {
+    vertices
+        .iter()
+        .copied()
+        .reduce(|min, vertex| Vector2::new(min.x.min(vertex.x), min.y.min(vertex.y)))
+}
+
+/// signed area of a polygon. positive if the polygon is counter-clockwise.
+fn signed_area<'a>(vertices: impl Iterator<Item = &'a Vector2<Decimal>> + Clone) -> Decimal {
+    let next = vertices.clone().cycle().skip(1);
+    let twice_area: Decimal = vertices.zip(next).map(|(a, b)| a.x * b.y - b.x * a.y).sum();
+    twice_area / Decimal::TWO
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::pattern::Stitch;
+
+    fn pattern(panel: serde_json::Value, stitches: serde_json::Value) -> Pattern {
+        serde_json::from_value(json!({
+            "panels": {"front": panel},
+            "panel_order": ["front"],
+            "stitches": stitches,
+        }))
+        .unwrap()
+    }
+
+    /// world positions of the panel's vertices.
+    fn world_vertices(pattern: &Pattern) -> Vec<Vector3<f64>> {
+        let name = PanelId::from("front");
+        let geometry = pattern.panels[&name]
+            .geometry(&name, &mut Converter::lossy())
+            .unwrap();
+        geometry
+            .vertices
+            .iter()
+            .map(|vertex| geometry.to_world(vertex))
+            .collect()
+    }
+
+    fn decimals(values: &[i64]) -> Vec<Decimal> {
+        values.iter().copied().map(Decimal::from).collect()
+    }
+
+    #[test]
+    fn translation_keeps_the_panel_in_place() {
+        for rotation in [[0, 0, 0], [0, 90, 0], [30, -45, 10]] {
+            let mut pattern = pattern(
+                json!({
+                    "translation": [1, 2, 3],
+                    "rotation": rotation,
+                    "vertices": [[5, 7], [25, 7], [25, 17], [5, 17]],
+                    "edges": [
+                        {"endpoints": [0, 1]},
+                        {"endpoints": [1, 2]},
+                        {"endpoints": [2, 3]},
+                        {"endpoints": [3, 0]},
+                    ],
+                }),
+                json!([]),
+            );
+            let before = world_vertices(&pattern);
+
+            pattern.normalize_panel_translations().unwrap();
+
+            let panel = &pattern.panels[&PanelId::from("front")];
+            assert_eq!(panel.vertices[0], Vector2::zeros());
+            assert_eq!(panel.vertices[2], Vector2::new(20.into(), 10.into()));
+
+            for (after, before) in world_vertices(&pattern).iter().zip(&before) {
+                assert!(
+                    (after - before).norm() < 1e-5,
+                    "rotation {:?}: {} moved to {}",
+                    rotation,
+                    before,
+                    after
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn edge_loop_is_counter_clockwise_from_the_bottom_left() {
+        // a clockwise loop, starting at the top-right, with edges out of
+        // order.
+        let mut pattern = pattern(
+            json!({
+                "translation": [0, 0, 0],
+                "rotation": [0, 0, 0],
+                "vertices": [[10, 10], [10, 0], [0, 0], [0, 10]],
+                "edges": [
+                    {"endpoints": [2, 3]},
+                    {"endpoints": [0, 1]},
+                    {"endpoints": [3, 0]},
+                    {"endpoints": [1, 2], "curvature": [0.25, 0.5]},
+                ],
+            }),
+            json!([[
+                {"panel": "front", "edge": 3},
+                {"panel": "front", "edge": 0},
+            ]]),
+        );
+
+        pattern.normalize_edge_loops().unwrap();
+
+        let panel = &pattern.panels[&PanelId::from("front")];
+        let vertices = panel
+            .vertices
+            .iter()
+            .flat_map(|vertex| [vertex.x, vertex.y])
+            .collect::<Vec<_>>();
+        assert_eq!(vertices, decimals(&[0, 0, 10, 0, 10, 10, 0, 10]));
+
+        for (index, edge) in panel.edges.iter().enumerate() {
+            assert_eq!(
+                edge.endpoints,
+                [VertexIndex(index), VertexIndex((index + 1) % 4)]
+            );
+        }
+
+        // the curved bottom edge was reversed.
+        assert_eq!(
+            panel.edges[0].curve,
+            crate::curve::Curve::Quadratic([Decimal::new(75, 2), Decimal::new(-5, 1)])
+        );
+
+        let Stitch(sides) = &pattern.stitches[0];
+        assert_eq!(sides[0].edge, EdgeIndex(0));
+        assert_eq!(sides[1].edge, EdgeIndex(3));
+    }
+
+    #[test]
+    fn edges_must_form_a_loop() {
+        let mut pattern = pattern(
+            json!({
+                "translation": [0, 0, 0],
+                "rotation": [0, 0, 0],
+                "vertices": [[0, 0], [10, 0], [10, 10], [0, 10]],
+                "edges": [
+                    {"endpoints": [0, 1]},
+                    {"endpoints": [1, 2]},
+                    {"endpoints": [2, 3]},
+                ],
+            }),
+            json!([]),
+        );
+
+        assert!(matches!(
+            pattern.normalize_edge_loops(),
+            Err(RenderError::NotALoop { .. })
+        ));
+    }
+
+    /// a 10 x 10 square at the origin, and a clockwise one next to it, whose
+    /// left edge (edge 0) is stitched to the right edge of the first.
+    fn two_panels(second_edges: serde_json::Value) -> Pattern {
+        serde_json::from_value(json!({
+            "panels": {
+                "left": {
+                    "translation": [0, 0, 0],
+                    "rotation": [0, 0, 0],
+                    "vertices": [[0, 0], [10, 0], [10, 10], [0, 10]],
+                    "edges": [
+                        {"endpoints": [0, 1]},
+                        {"endpoints": [1, 2]},
+                        {"endpoints": [2, 3]},
+                        {"endpoints": [3, 0]},
+                    ],
+                },
+                "right": {
+                    "translation": [10, 0, 0],
+                    "rotation": [0, 0, 0],
+                    "vertices": [[0, 0], [0, 10], [10, 10], [10, 0]],
+                    "edges": second_edges,
+                },
+            },
+            "panel_order": ["left", "right"],
+            "stitches": [[
+                {"panel": "left", "edge": 1},
+                {"panel": "right", "edge": 0},
+            ]],
+        }))
+        .unwrap()
+    }
+
+    /// world positions of the ends of the edges in `pattern`'s first stitch.
+    fn stitched_points(pattern: &Pattern) -> Vec<[Vector3<f64>; 2]> {
+        let geometry = pattern.geometry(&mut Converter::lossy()).unwrap();
+        pattern.stitches[0]
+            .0
+            .iter()
+            .map(|side| {
+                let panel = &geometry.panels[&side.panel];
+                let segment = &panel.edges[side.edge.0].segment;
+                [
+                    panel.to_world(&segment.start()),
+                    panel.to_world(&segment.end()),
+                ]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn reversed_edges_stay_stitched_to_the_same_points() {
+        let mut pattern = two_panels(json!([
+            {"endpoints": [0, 1]},
+            {"endpoints": [1, 2]},
+            {"endpoints": [2, 3]},
+            {"endpoints": [3, 0]},
+        ]));
+        let before = stitched_points(&pattern);
+
+        pattern.normalize_edge_loops().unwrap();
+        let after = stitched_points(&pattern);
+
+        // the stitched edge of the clockwise panel is now its last edge, and
+        // it's reversed.
+        assert_eq!(pattern.stitches[0].0[1].edge, EdgeIndex(3));
+
+        let close = |a: &Vector3<f64>, b: &Vector3<f64>| (a - b).norm() < 1e-9;
+        assert!(close(&after[0][0], &before[0][0]) && close(&after[0][1], &before[0][1]));
+        assert!(close(&after[1][0], &before[1][1]) && close(&after[1][1], &before[1][0]));
+    }
+
+    #[test]
+    fn failed_normalization_keeps_the_pattern() {
+        let mut pattern = two_panels(json!([
+            {"endpoints": [0, 1]},
+            {"endpoints": [1, 2]},
+            {"endpoints": [2, 3]},
+        ]));
+        // the first panel would be reordered.
+        pattern
+            .panels
+            .get_mut(&PanelId::from("left"))
+            .unwrap()
+            .edges
+            .swap(0, 2);
+        let before = serde_json::to_value(&pattern).unwrap();
+
+        assert!(matches!(
+            pattern.normalize_edge_loops(),
+            Err(RenderError::NotALoop { panel }) if panel.as_str() == "right"
+        ));
+        assert_eq!(serde_json::to_value(&pattern).unwrap(), before);
+    }
+}
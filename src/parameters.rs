@@ -1,4 +1,17 @@
-use std::collections::BTreeMap;
+//! parameter files.
+//!
+//! [`Parameters`] can be read and written as TOML, JSON or YAML. they can
+//! also be read from the `specification.json` of a dataset sample, which has
+//! the same structure as a template, with the sampled value of every
+//! parameter as its `value`.
+
+use std::{
+    collections::BTreeMap,
+    path::{
+        Path,
+        PathBuf,
+    },
+};
 
 use rust_decimal::Decimal;
 use serde::{
@@ -6,11 +19,226 @@ use serde::{
     Serialize,
 };
 
+use crate::id::ParameterName;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ParametersError {
+    #[error("io error")]
+    Io(#[from] std::io::Error),
+
+    #[error("can't tell the format of parameter file: {}", .path.display())]
+    UnknownFormat { path: PathBuf },
+
+    #[error(
+        "invalid parameters format, expected `toml`, `json`, `yaml` or `specification`: {format}"
+    )]
+    InvalidFormat { format: String },
+
+    #[error("parameters can't be written as a dataset specification")]
+    WriteSpecification,
+
+    #[error("toml error")]
+    Toml(#[from] toml::de::Error),
+
+    #[error("toml error")]
+    TomlWrite(#[from] toml::ser::Error),
+
+    #[error("json error")]
+    Json(#[from] serde_json::Error),
+
+    #[error("yaml error")]
+    Yaml(#[from] serde_yaml::Error),
+}
+
 /// file format for a parameters file. so this only maps influence names to
 /// values. this will be applied to `parameters` map in
 /// [[`crate::pattern::Template`]], which will also check the constraints.
 #[derive(Clone, Debug, Serialize, Deserialize, Default)]
 pub struct Parameters {
     #[serde(flatten)]
-    pub parameters: BTreeMap<String, Decimal>,
+    pub parameters: BTreeMap<ParameterName, Decimal>,
+}
+
+/// formats parameters can be stored in.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ParametersFormat {
+    Toml,
+    Json,
+    Yaml,
+
+    /// the `specification.json` of a dataset sample. this can only be read.
+    Specification,
+}
+
+/// the parts of a dataset specification that have the parameters.
+#[derive(Debug, Deserialize)]
+struct Specification {
+    parameters: BTreeMap<ParameterName, SampledParameter>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SampledParameter {
+    value: Decimal,
+}
+
+impl ParametersFormat {
+    /// guesses the format from the file name. files ending in
+    /// `specification.json` are dataset specifications.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let file_name = path.file_name()?.to_str()?;
+        if file_name.ends_with("specification.json") {
+            return Some(ParametersFormat::Specification);
+        }
+
+        match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "toml" => Some(ParametersFormat::Toml),
+            "json" => Some(ParametersFormat::Json),
+            "yaml" | "yml" => Some(ParametersFormat::Yaml),
+            _ => None,
+        }
+    }
+}
+
+impl std::str::FromStr for ParametersFormat {
+    type Err = ParametersError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "toml" => Ok(ParametersFormat::Toml),
+            "json" => Ok(ParametersFormat::Json),
+            "yaml" => Ok(ParametersFormat::Yaml),
+            "specification" => Ok(ParametersFormat::Specification),
+            _ => {
+                Err(ParametersError::InvalidFormat {
+                    format: s.to_owned(),
+                })
+            }
+        }
+    }
+}
+
+impl Parameters {
+    /// parses parameters in the given format.
+    pub fn from_str_as(s: &str, format: ParametersFormat) -> Result<Self, ParametersError> {
+        Ok(match format {
+            ParametersFormat::Toml => toml::from_str(s)?,
+            ParametersFormat::Json => serde_json::from_str(s)?,
+            ParametersFormat::Yaml => serde_yaml::from_str(s)?,
+            ParametersFormat::Specification => {
+                let specification: Specification = serde_json::from_str(s)?;
+                Parameters {
+                    parameters: specification
+                        .parameters
+                        .into_iter()
+                        .map(|(name, parameter)| (name, parameter.value))
+                        .collect(),
+                }
+            }
+        })
+    }
+
+    /// writes the parameters in the given format.
+    pub fn to_string_as(&self, format: ParametersFormat) -> Result<String, ParametersError> {
+        Ok(match format {
+            ParametersFormat::Toml => toml::to_string(self)?,
+            ParametersFormat::Json => serde_json::to_string_pretty(self)? + "\n",
+            ParametersFormat::Yaml => serde_yaml::to_string(self)?,
+            ParametersFormat::Specification => return Err(ParametersError::WriteSpecification),
+        })
+    }
+
+    /// reads a parameters file. if `format` is `None`, it's guessed from the
+    /// file name.
+    pub fn read(path: &Path, format: Option<ParametersFormat>) -> Result<Self, ParametersError> {
+        let format = format_for(path, format)?;
+        let s = std::fs::read_to_string(path)?;
+        Self::from_str_as(&s, format)
+    }
+
+    /// writes a parameters file. if `format` is `None`, it's guessed from the
+    /// file name.
+    pub fn write(
+        &self,
+        path: &Path,
+        format: Option<ParametersFormat>,
+    ) -> Result<(), ParametersError> {
+        let format = format_for(path, format)?;
+        std::fs::write(path, self.to_string_as(format)?)?;
+        Ok(())
+    }
+}
+
+fn format_for(
+    path: &Path,
+    format: Option<ParametersFormat>,
+) -> Result<ParametersFormat, ParametersError> {
+    format
+        .or_else(|| ParametersFormat::from_path(path))
+        .ok_or_else(|| {
+            ParametersError::UnknownFormat {
+                path: path.to_owned(),
+            }
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parameters() -> Parameters {
+        let mut parameters = Parameters::default();
+        parameters
+            .parameters
+            .insert("length".into(), Decimal::new(8, 1));
+        parameters
+            .parameters
+            .insert("waist".into(), Decimal::new(125, 2));
+        parameters
+    }
+
+    fn round_trip(format: ParametersFormat, expected: &[&str]) {
+        let parameters = parameters();
+        let s = parameters.to_string_as(format).unwrap();
+        for line in expected {
+            assert!(s.contains(line), "{:?} doesn't contain {:?}", s, line);
+        }
+
+        let read = Parameters::from_str_as(&s, format).unwrap();
+        assert_eq!(read.parameters, parameters.parameters);
+    }
+
+    #[test]
+    fn toml_round_trip() {
+        round_trip(ParametersFormat::Toml, &["length = 0.8", "waist = 1.25"]);
+    }
+
+    #[test]
+    fn json_round_trip() {
+        round_trip(
+            ParametersFormat::Json,
+            &["\"length\": 0.8", "\"waist\": 1.25"],
+        );
+    }
+
+    #[test]
+    fn yaml_round_trip() {
+        round_trip(ParametersFormat::Yaml, &["length: 0.8", "waist: 1.25"]);
+    }
+
+    #[test]
+    fn reads_quoted_decimals() {
+        let read = Parameters::from_str_as("length = \"0.8\"", ParametersFormat::Toml).unwrap();
+        assert_eq!(
+            read.parameters[&ParameterName::from("length")],
+            Decimal::new(8, 1)
+        );
+    }
+
+    #[test]
+    fn specification_is_read_only() {
+        assert!(matches!(
+            parameters().to_string_as(ParametersFormat::Specification),
+            Err(ParametersError::WriteSpecification)
+        ));
+    }
 }
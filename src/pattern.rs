@@ -1,46 +1,53 @@
-use std::{
-    collections::{BTreeMap, HashMap},
-    slice::Iter,
+use std::collections::{
+    BTreeMap,
+    HashMap,
 };
 
 use nalgebra::{
-    Scalar,
+    Point2,
     Vector2,
     Vector3,
 };
-use rust_decimal::{
-    prelude::{
-        ToPrimitive,
-        Zero,
-    },
-    Decimal,
-};
+use rust_decimal::Decimal;
 use serde::{
     Deserialize,
     Serialize,
 };
-use svg::node::element::{
-    path::Data,
-    Path,
-};
 
 use crate::{
     aabb::{
-        AsAABB,
+        Rect,
         AABB,
     },
-    error::Error,
-    parameters::Parameters,
-    render::{
-        Render,
-        Target,
+    curve::{
+        Curve,
+        Segment,
     },
+    geometry::{
+        ConversionError,
+        Converter,
+    },
+    id::{
+        EdgeHandle,
+        EdgeIndex,
+        PanelId,
+        ParameterName,
+        VertexIndex,
+    },
+    parameters::Parameters,
 };
 
-#[derive(Debug, Serialize, Deserialize)]
+/// how the control points of curved edges are stored.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum CurvatureCoords {
+    /// control points are relative to the edge (see
+    /// [`Edge::relative_control_point`]). this is what all geometry in this
+    /// crate expects.
     Relative,
+
+    /// control points are absolute panel coordinates.
+    Absolute,
 }
 
 #[derive(Copy, Clone, Debug, Serialize, Deserialize)]
@@ -61,7 +68,7 @@ impl Direction {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum ParameterType {
     Length,
@@ -88,7 +95,33 @@ pub struct Properties {
 #[serde(deny_unknown_fields)]
 pub struct Influence {
     pub edge_list: Vec<EdgeRef>,
-    pub panel: String,
+    pub panel: PanelId,
+}
+
+impl Influence {
+    /// the influenced edges, with the direction they are influenced in.
+    pub fn edges(&self) -> impl Iterator<Item = (EdgeHandle, Option<Direction>)> + '_ {
+        self.edge_list.iter().map(|edge_ref| {
+            (
+                EdgeHandle::new(self.panel.clone(), edge_ref.id),
+                edge_ref.direction,
+            )
+        })
+    }
+}
+
+/// how the value of a parameter changes the pattern.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Semantics {
+    /// the value scales the length or curvature of the influenced edges. a
+    /// value of 1 leaves them as they are. this is what the dataset uses.
+    Multiplicative,
+
+    /// the value is added to the length of the influenced edges, in pattern
+    /// units, or to the relative curvature of their control points. a value
+    /// of 0 leaves them as they are.
+    Additive,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -98,19 +131,97 @@ pub struct Parameter {
     pub range: [Decimal; 2],
     pub r#type: ParameterType,
 
-    /// the default value
+    /// the default value. this is the value the template's geometry is at.
     pub value: Decimal,
+
+    /// how the value is applied. this isn't part of the dataset's template
+    /// format. if omitted, it's inferred, see [`Parameter::semantics`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub semantics: Option<Semantics>,
 }
 
+impl Semantics {
+    /// the value that leaves the pattern as it is.
+    pub fn neutral(&self) -> Decimal {
+        match self {
+            Semantics::Multiplicative => Decimal::ONE,
+            Semantics::Additive => Decimal::ZERO,
+        }
+    }
+
+    /// the value that undoes `value`, or `None` if a multiplicative value is
+    /// 0.
+    pub fn inverse(&self, value: Decimal) -> Option<Decimal> {
+        match self {
+            Semantics::Multiplicative => Decimal::ONE.checked_div(value),
+            Semantics::Additive => Some(-value),
+        }
+    }
+}
 
-#[derive(Debug, Serialize, Deserialize)]
+impl Parameter {
+    /// how the value is applied. parameters that don't declare it are
+    /// additive if their default value is 0, since scaling by 0 can't be
+    /// undone, and multiplicative otherwise.
+    pub fn semantics(&self) -> Semantics {
+        self.semantics.unwrap_or(if self.value.is_zero() {
+            Semantics::Additive
+        }
+        else {
+            Semantics::Multiplicative
+        })
+    }
+
+    /// applies `value` to the influenced edges. if `reversed` is set, the
+    /// influences and edges are visited in reverse order, which undoes
+    /// applying them in order.
+    fn apply(
+        &self,
+        pattern: &mut Pattern,
+        semantics: Semantics,
+        value: Decimal,
+        reversed: bool,
+    ) -> Result<(), RenderError> {
+        let mut edges = self
+            .influence
+            .iter()
+            .flat_map(|influence| influence.edges())
+            .collect::<Vec<_>>();
+        if reversed {
+            edges.reverse();
+        }
+
+        for (handle, direction) in edges {
+            let panel = pattern.panel_mut(&handle.panel)?;
+            let direction = direction.unwrap_or(Direction::Both);
+
+            match (self.r#type, semantics) {
+                (ParameterType::Length, Semantics::Multiplicative) => {
+                    panel.extend_edge(&handle.panel, handle.edge, direction, value)?
+                }
+                (ParameterType::Length, Semantics::Additive) => {
+                    panel.lengthen_edge(&handle.panel, handle.edge, direction, value)?
+                }
+                (ParameterType::Curve, Semantics::Multiplicative) => {
+                    panel.curve_edge(&handle.panel, handle.edge, value)?
+                }
+                (ParameterType::Curve, Semantics::Additive) => {
+                    panel.bend_edge(&handle.panel, handle.edge, value)?
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum ConstraintType {
     LengthEquality,
     CurveEquality,
 }
 
-
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct Constraint {
@@ -124,8 +235,8 @@ pub struct Template {
     pub pattern: Pattern,
     pub properties: Properties,
 
-    pub parameters: BTreeMap<String, Parameter>,
-    pub parameter_order: Vec<String>,
+    pub parameters: BTreeMap<ParameterName, Parameter>,
+    pub parameter_order: Vec<ParameterName>,
 
     pub constraints: HashMap<String, Constraint>,
     pub constraint_order: Vec<String>,
@@ -134,108 +245,201 @@ pub struct Template {
 #[derive(Debug, thiserror::Error)]
 pub enum RenderError {
     #[error("no such parameter: {name}")]
-    NoSuchParameter { name: String },
+    NoSuchParameter { name: ParameterName },
+    #[error("unknown parameter: {name}{}", did_you_mean(.suggestion))]
+    UnknownParameter {
+        name: ParameterName,
+        suggestion: Option<ParameterName>,
+    },
+    #[error("multiplicative parameter has a default value of 0: {name}")]
+    ZeroDefault { name: ParameterName },
     #[error("value {value} is out of range {} .. {}", .range[0], .range[1])]
     OutOfRange { value: Decimal, range: [Decimal; 2] },
     #[error("no such panel: {panel}")]
-    NoSuchPanel { panel: String },
+    NoSuchPanel { panel: PanelId },
     #[error("no such edge: panel={panel}, {edge}")]
-    NoSuchEdge { panel: String, edge: usize },
+    NoSuchEdge { panel: PanelId, edge: EdgeIndex },
     #[error("no such vertex: {index}")]
-    NoSuchVertex { index: usize },
+    NoSuchVertex { index: VertexIndex },
+    #[error("edge has the same start and end: panel={panel}, {edge}")]
+    DegenerateEdge { panel: PanelId, edge: EdgeIndex },
+    #[error("edges don't form a single closed loop: {panel}")]
+    NotALoop { panel: PanelId },
+    #[error("edge refers to a missing vertex: panel={panel}, {edge}, vertex={vertex}")]
+    MissingEndpoint {
+        panel: PanelId,
+        edge: EdgeIndex,
+        vertex: VertexIndex,
+    },
+    #[error("edge has non-finite coordinates: panel={panel}, {edge}")]
+    NotFinite { panel: PanelId, edge: EdgeIndex },
+    #[error("conversion error")]
+    Conversion(#[from] ConversionError),
+    #[error("view box is not finite: {value}")]
+    NotFiniteViewBox { value: f64 },
+}
+
+/// decimal places of edge lengths that additive length parameters are
+/// applied to.
+const LENGTH_DECIMAL_PLACES: u32 = 9;
+
+/// decimal places of the corners of panel bounding boxes.
+const BOUNDS_DECIMAL_PLACES: u32 = 9;
+
+/// smallest jaro-winkler similarity of a parameter name to be suggested for
+/// an unknown one.
+const SIMILAR_NAME: f64 = 0.8;
+
+fn did_you_mean(suggestion: &Option<ParameterName>) -> String {
+    suggestion
+        .as_ref()
+        .map(|suggestion| format!(", did you mean {}?", suggestion))
+        .unwrap_or_default()
 }
 
 impl Template {
-    pub fn parameters<'a>(&'a self) -> OrderedIter<'a, Parameter> {
-        OrderedIter {
-            items: &self.parameters,
-            order: self.parameter_order.iter(),
+    /// iterates over the parameters in `parameter_order`. fails if the order
+    /// names a parameter that doesn't exist.
+    pub fn parameters(&self) -> Result<OrderedIter<'_, ParameterName, Parameter>, RenderError> {
+        OrderedIter::new(&self.parameters, &self.parameter_order, |name| {
+            RenderError::NoSuchParameter { name: name.clone() }
+        })
+    }
+
+    /// parameters with the default value of every parameter.
+    pub fn default_parameters(&self) -> Parameters {
+        Parameters {
+            parameters: self
+                .parameters
+                .iter()
+                .map(|(name, parameter)| (name.clone(), parameter.value))
+                .collect(),
         }
     }
 
-    pub fn with_parameters(&self, parameters: &Parameters) -> Result<Pattern, RenderError> {
-        let mut pattern = self.pattern.clone();
+    /// fills in the default value of every parameter that's missing from
+    /// `parameters`. if `strict` is set, missing parameters are an error
+    /// instead. names that aren't parameters of the template are always an
+    /// error.
+    pub fn complete_parameters(
+        &self,
+        parameters: &Parameters,
+        strict: bool,
+    ) -> Result<Parameters, RenderError> {
+        if let Some(name) = parameters
+            .parameters
+            .keys()
+            .find(|name| !self.parameters.contains_key(*name))
+        {
+            return Err(RenderError::UnknownParameter {
+                name: name.clone(),
+                suggestion: self.similar_parameter(name),
+            });
+        }
 
+        let mut complete = parameters.clone();
         for (name, parameter) in &self.parameters {
-            let parameter_value = parameters.parameters.get(name).ok_or_else(|| {
-                RenderError::NoSuchParameter {
-                    name: name.to_owned(),
+            if !complete.parameters.contains_key(name) {
+                if strict {
+                    return Err(RenderError::NoSuchParameter { name: name.clone() });
                 }
-            })?;
-
-            // see comments below
-            if *parameter_value < parameter.range[0] || *parameter_value > parameter.range[1] {
-                return Err(RenderError::OutOfRange {
-                    value: *parameter_value,
-                    range: parameter.range,
-                });
+                complete.parameters.insert(name.clone(), parameter.value);
             }
+        }
 
-            //let new_value = update.apply(parameter.value);
-            // i don't really know how we now this is additive. usually it's multiplicative
-            // and you can kind of tell from the default value.
-            assert_eq!(parameter.value.to_u64(), Some(1));
-
-            // todo: check local constraints
-            // the paper says that this is only the range we sample from, but that there is
-            // also a parameter type.
-            /*if new_value < parameter.range[0] || new_value > parameter.range[1] {
-                return Err(RenderError::OutOfRange {
-                    value: new_value,
-                    range: parameter.range,
-                });
-            }*/
+        Ok(complete)
+    }
+
+    /// the parameter whose name is closest to `name`, if any is close enough
+    /// to be a likely typo.
+    fn similar_parameter(&self, name: &ParameterName) -> Option<ParameterName> {
+        self.parameters
+            .keys()
+            .map(|candidate| {
+                (
+                    strsim::jaro_winkler(name.as_str(), candidate.as_str()),
+                    candidate,
+                )
+            })
+            .filter(|(similarity, _)| *similarity >= SIMILAR_NAME)
+            .max_by(|(a, _), (b, _)| a.total_cmp(b))
+            .map(|(_, candidate)| candidate.clone())
+    }
+
+    /// instantiates the template. parameters that are missing from
+    /// `parameters` have their default value, see
+    /// [`Template::complete_parameters`].
+    pub fn with_parameters(&self, parameters: &Parameters) -> Result<Pattern, RenderError> {
+        let parameters = self.complete_parameters(parameters, false)?;
+        let mut pattern = self.pattern.clone();
+
+        // curvature parameters scale relative control points.
+        let absolute = self.properties.curvature_coords == CurvatureCoords::Absolute;
+        if absolute {
+            pattern.convert_curvature(CurvatureCoords::Absolute, CurvatureCoords::Relative)?;
+        }
+
+        // parameters can move the same vertices, so the order matters.
+        let order = self.parameters()?.collect::<Vec<_>>();
 
-            // todo: apply to influences
-            for influence in &parameter.influence {
-                let panel = pattern.panels.get_mut(&influence.panel).ok_or_else(|| {
-                    RenderError::NoSuchPanel {
-                        panel: influence.panel.to_owned(),
+        let values = order
+            .iter()
+            .map(|(name, parameter)| {
+                let value = parameters.parameters.get(*name).ok_or_else(|| {
+                    RenderError::NoSuchParameter {
+                        name: (*name).clone(),
                     }
                 })?;
 
-                for edge_ref in &influence.edge_list {
-                    let edge = panel.edges.get(edge_ref.id).ok_or_else(|| {
-                        RenderError::NoSuchEdge {
-                            panel: influence.panel.to_owned(),
-                            edge: edge_ref.id,
-                        }
-                    })?;
-
-                    let _start = *panel.get_vertex(edge.endpoints[0])?;
-                    let _end = *panel.get_vertex(edge.endpoints[1])?;
-
-                    // change value
-                    match parameter.r#type {
-                        ParameterType::Length => {
-                            // move vertices(?)
-                            // todo: we might have to layout them ourselves after this and we might
-                            // want to do this anyway. but we just need
-                            // to preserve the orientation (grain line).
-                            if edge_ref
-                                .direction
-                                .map(|direction| direction.is_start())
-                                .unwrap_or_default()
-                            {
-                                //edge.endpoints[0]
-                                // todo:
-                                //  is_start: start = start - k * (end-start) ??
-                                //  is_end:   end   = end + k * (end-start) ??
-                            }
-                            if edge_ref
-                                .direction
-                                .map(|direction| direction.is_end())
-                                .unwrap_or_default()
-                            {
-                                // todo: move vertices
-                            }
-                        }
-                        ParameterType::Curve => todo!(),
-                    }
+                // the range is what the dataset samples from. the paper
+                // doesn't say whether values outside of it are valid, so we
+                // don't allow them.
+                if *value < parameter.range[0] || *value > parameter.range[1] {
+                    return Err(RenderError::OutOfRange {
+                        value: *value,
+                        range: parameter.range,
+                    });
                 }
+
+                Ok(*value)
+            })
+            .collect::<Result<Vec<_>, RenderError>>()?;
+
+        // todo: check local constraints
+
+        // the template's geometry is at the default values. like the dataset
+        // generator, we first undo them in reverse order, which brings the
+        // template to the neutral values, and then apply the new values.
+        for (name, parameter) in order.iter().rev() {
+            let semantics = parameter.semantics();
+            if parameter.value != semantics.neutral() {
+                let inverse = semantics.inverse(parameter.value).ok_or_else(|| {
+                    RenderError::ZeroDefault {
+                        name: (*name).clone(),
+                    }
+                })?;
+                parameter.apply(&mut pattern, semantics, inverse, true)?;
+            }
+        }
+
+        for ((_, parameter), value) in order.iter().zip(values) {
+            let semantics = parameter.semantics();
+            if value != semantics.neutral() {
+                parameter.apply(&mut pattern, semantics, value, false)?;
             }
         }
 
+        if absolute {
+            pattern.convert_curvature(CurvatureCoords::Relative, CurvatureCoords::Absolute)?;
+        }
+
+        if self.properties.normalize_panel_translation {
+            pattern.normalize_panel_translations()?;
+        }
+        if self.properties.normalized_edge_loops {
+            pattern.normalize_edge_loops()?;
+        }
+
         Ok(pattern)
     }
 }
@@ -243,28 +447,122 @@ impl Template {
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct StitchStrough {
-    pub edge: usize,
-    pub panel: String,
+    pub edge: EdgeIndex,
+    pub panel: PanelId,
+}
+
+impl StitchStrough {
+    pub fn handle(&self) -> EdgeHandle {
+        EdgeHandle::new(self.panel.clone(), self.edge)
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(transparent)]
-pub struct Stitch(Vec<StitchStrough>);
+pub struct Stitch(pub Vec<StitchStrough>);
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct Pattern {
-    pub panels: BTreeMap<String, Panel>,
-    pub panel_order: Vec<String>,
+    pub panels: BTreeMap<PanelId, Panel>,
+    pub panel_order: Vec<PanelId>,
     pub stitches: Vec<Stitch>,
 }
 
 impl Pattern {
-    pub fn panels<'a>(&'a self) -> OrderedIter<'a, Panel> {
-        OrderedIter {
-            items: &self.panels,
-            order: self.panel_order.iter(),
+    /// iterates over the panels in `panel_order`. fails if the order names a
+    /// panel that doesn't exist.
+    pub fn panels(&self) -> Result<OrderedIter<'_, PanelId, Panel>, RenderError> {
+        OrderedIter::new(&self.panels, &self.panel_order, |panel| {
+            RenderError::NoSuchPanel {
+                panel: panel.clone(),
+            }
+        })
+    }
+
+    pub fn panel(&self, panel: &PanelId) -> Result<&Panel, RenderError> {
+        self.panels.get(panel).ok_or_else(|| {
+            RenderError::NoSuchPanel {
+                panel: panel.clone(),
+            }
+        })
+    }
+
+    pub fn panel_mut(&mut self, panel: &PanelId) -> Result<&mut Panel, RenderError> {
+        self.panels.get_mut(panel).ok_or_else(|| {
+            RenderError::NoSuchPanel {
+                panel: panel.clone(),
+            }
+        })
+    }
+
+    /// looks up the edge referenced by `handle`, together with the panel it
+    /// belongs to.
+    pub fn edge(&self, handle: &EdgeHandle) -> Result<(&Panel, &Edge), RenderError> {
+        let panel = self.panel(&handle.panel)?;
+        let edge = panel.get_edge(&handle.panel, handle.edge)?;
+        Ok((panel, edge))
+    }
+
+    /// resolves the edge referenced by `handle` into absolute coordinates.
+    /// see [`Panel::resolve_edge`].
+    pub fn edge_segment(&self, handle: &EdgeHandle) -> Result<Segment, RenderError> {
+        self.panel(&handle.panel)?
+            .resolve_edge(&handle.panel, handle.edge)
+    }
+
+    /// rewrites the control points of every edge from `from` coordinates to
+    /// `to` coordinates. arcs don't have control points and are left as they
+    /// are.
+    ///
+    /// converting to relative coordinates fails for curved edges whose start
+    /// and end are the same vertex.
+    pub fn convert_curvature(
+        &mut self,
+        from: CurvatureCoords,
+        to: CurvatureCoords,
+    ) -> Result<(), RenderError> {
+        if from == to {
+            return Ok(());
+        }
+
+        for (name, panel) in &mut self.panels {
+            let Panel {
+                edges, vertices, ..
+            } = panel;
+
+            for (index, edge) in EdgeIndex::all(edges.len()).zip(edges.iter_mut()) {
+                let vertex = |index: VertexIndex| {
+                    vertices
+                        .get(index.0)
+                        .ok_or(RenderError::NoSuchVertex { index })
+                };
+                let start = vertex(edge.endpoints[0])?;
+                let end = vertex(edge.endpoints[1])?;
+
+                for control in edge.curve.control_points_mut() {
+                    *control = match to {
+                        CurvatureCoords::Absolute => {
+                            let absolute = Edge::absolute_control_point(start, end, control);
+                            [absolute.x, absolute.y]
+                        }
+                        CurvatureCoords::Relative => {
+                            let absolute = Vector2::new(control[0], control[1]);
+                            Edge::relative_control_point(start, end, &absolute).ok_or_else(
+                                || {
+                                    RenderError::DegenerateEdge {
+                                        panel: name.clone(),
+                                        edge: index,
+                                    }
+                                },
+                            )?
+                        }
+                    };
+                }
+            }
         }
+
+        Ok(())
     }
 }
 
@@ -278,64 +576,217 @@ pub struct Panel {
 }
 
 impl Panel {
-    pub fn get_vertex(&self, index: usize) -> Result<&Vector2<Decimal>, RenderError> {
+    pub fn get_vertex(&self, index: VertexIndex) -> Result<&Vector2<Decimal>, RenderError> {
         self.vertices
-            .get(index)
-            .ok_or_else(|| RenderError::NoSuchVertex { index })
+            .get(index.0)
+            .ok_or(RenderError::NoSuchVertex { index })
     }
 
-    pub fn get_vertex_mut(&mut self, index: usize) -> Result<&mut Vector2<Decimal>, RenderError> {
+    pub fn get_vertex_mut(
+        &mut self,
+        index: VertexIndex,
+    ) -> Result<&mut Vector2<Decimal>, RenderError> {
         self.vertices
-            .get_mut(index)
-            .ok_or_else(|| RenderError::NoSuchVertex { index })
+            .get_mut(index.0)
+            .ok_or(RenderError::NoSuchVertex { index })
+    }
+
+    /// looks up an edge. `panel` is the name of this panel and only used for
+    /// the error.
+    pub fn get_edge(&self, panel: &PanelId, edge: EdgeIndex) -> Result<&Edge, RenderError> {
+        self.edges.get(edge.0).ok_or_else(|| {
+            RenderError::NoSuchEdge {
+                panel: panel.clone(),
+                edge,
+            }
+        })
+    }
+
+    /// iterates over the edges with their indices.
+    pub fn indexed_edges(&self) -> impl Iterator<Item = (EdgeIndex, &Edge)> {
+        EdgeIndex::all(self.edges.len()).zip(&self.edges)
     }
-}
 
-impl Render for Panel {
-    type Context = ();
+    /// resolves an edge of this panel into absolute coordinates.
+    pub fn edge_segment(&self, edge: &Edge) -> Result<Segment, RenderError> {
+        let start = self.get_vertex(edge.endpoints[0])?;
+        let end = self.get_vertex(edge.endpoints[1])?;
+        Ok(edge.curve.segment(start, end)?)
+    }
+
+    /// resolves the edge with index `edge` into absolute coordinates. unlike
+    /// [`Panel::edge_segment`] this reports errors with the panel's name and
+    /// the edge index, and it fails if the segment isn't finite.
+    pub fn resolve_edge(&self, panel: &PanelId, edge: EdgeIndex) -> Result<Segment, RenderError> {
+        self.resolve_edge_with(panel, edge, &mut Converter::lossy())
+    }
+
+    /// like [`Panel::resolve_edge`], but converts the coordinates with
+    /// `converter`.
+    pub fn resolve_edge_with(
+        &self,
+        panel: &PanelId,
+        edge: EdgeIndex,
+        converter: &mut Converter,
+    ) -> Result<Segment, RenderError> {
+        let resolved = self.get_edge(panel, edge)?;
+
+        for vertex in resolved.endpoints {
+            if vertex.0 >= self.vertices.len() {
+                return Err(RenderError::MissingEndpoint {
+                    panel: panel.clone(),
+                    edge,
+                    vertex,
+                });
+            }
+        }
 
-    fn render(&self, target: &mut Target, _context: &Self::Context) -> Result<(), Error> {
-        for edge in &self.edges {
-            let mut vertices = edge.endpoints.iter().map(|endpoint| {
-                self.vertices
-                    .get(*endpoint)
-                    .unwrap_or_else(|| panic!("invalid vertex index: {}", endpoint))
+        let start = &self.vertices[resolved.endpoints[0].0];
+        let end = &self.vertices[resolved.endpoints[1].0];
+        let segment = resolved.curve.segment_with(start, end, converter)?;
+        if !segment.is_finite() {
+            return Err(RenderError::NotFinite {
+                panel: panel.clone(),
+                edge,
             });
+        }
+
+        Ok(segment)
+    }
+
+    /// scales an edge by `factor` along itself. `direction` is the end of the
+    /// edge that moves, while the other end stays in place. if both ends
+    /// move, the midpoint stays in place. the vertices are shared with
+    /// adjacent edges, which change with them.
+    pub fn extend_edge(
+        &mut self,
+        panel: &PanelId,
+        edge: EdgeIndex,
+        direction: Direction,
+        factor: Decimal,
+    ) -> Result<(), RenderError> {
+        let [start_index, end_index] = self.get_edge(panel, edge)?.endpoints;
+        let start = *self.get_vertex(start_index)?;
+        let end = *self.get_vertex(end_index)?;
+
+        let fixed = match direction {
+            Direction::Start => end,
+            Direction::End => start,
+            Direction::Both => (start + end) / Decimal::TWO,
+        };
+
+        if direction.is_start() {
+            *self.get_vertex_mut(start_index)? = fixed + (start - fixed) * factor;
+        }
+        if direction.is_end() {
+            *self.get_vertex_mut(end_index)? = fixed + (end - fixed) * factor;
+        }
+
+        Ok(())
+    }
 
-            let first = vertices
-                .next()
-                .unwrap_or_else(|| panic!("no vertices in edge"));
-            let second = vertices
-                .next()
-                .unwrap_or_else(|| panic!("no vertices in edge"));
-
-            let mut data =
-                Data::new().move_to((first.x.to_f64().unwrap(), first.y.to_f64().unwrap()));
-
-            if let Some(curvature) = &edge.curvature {
-                // quadratic bezier curve
-                data = data.smooth_quadratic_curve_to((
-                    second.x.to_f64().unwrap(),
-                    second.y.to_f64().unwrap(),
-                    curvature[0].to_f64().unwrap(),
-                    curvature[1].to_f64().unwrap(),
-                ));
+    /// changes the length of an edge by `amount`, moving its ends along the
+    /// edge. `direction` is the end of the edge that moves, like in
+    /// [`Panel::extend_edge`]. if both ends move, each moves by half.
+    pub fn lengthen_edge(
+        &mut self,
+        panel: &PanelId,
+        edge: EdgeIndex,
+        direction: Direction,
+        amount: Decimal,
+    ) -> Result<(), RenderError> {
+        let degenerate = || {
+            RenderError::DegenerateEdge {
+                panel: panel.clone(),
+                edge,
             }
-            else {
-                // straight line
-                data = data.line_to((second.x.to_f64().unwrap(), second.y.to_f64().unwrap()));
+        };
+
+        // relative control points follow the ends, so the length of curves
+        // scales with them.
+        let length = self.resolve_edge(panel, edge)?.length();
+        if length == 0.0 {
+            return Err(degenerate());
+        }
+
+        let mut converter = Converter::lossy();
+        let length = converter.to_decimal(length, LENGTH_DECIMAL_PLACES)?;
+        let factor = (length + amount)
+            .checked_div(length)
+            .ok_or_else(degenerate)?;
+
+        self.extend_edge(panel, edge, direction, factor)
+    }
+
+    /// adds `amount` to how far the control points of an edge are from the
+    /// edge, relative to its length. this expects relative curvature
+    /// coordinates. lines and arcs don't have control points and stay as they
+    /// are.
+    pub fn bend_edge(
+        &mut self,
+        panel: &PanelId,
+        edge: EdgeIndex,
+        amount: Decimal,
+    ) -> Result<(), RenderError> {
+        let resolved = self.edges.get_mut(edge.0).ok_or_else(|| {
+            RenderError::NoSuchEdge {
+                panel: panel.clone(),
+                edge,
             }
+        })?;
+
+        for control in resolved.curve.control_points_mut() {
+            control[1] += amount;
+        }
+
+        Ok(())
+    }
 
-            let path = Path::new()
-                .set("fill", "none")
-                .set("stroke", "black")
-                .set("stroke-width", 3u64)
-                .set("d", data);
+    /// scales how far the control points of an edge are from the edge by
+    /// `factor`. this expects relative curvature coordinates. lines and arcs
+    /// don't have control points and stay as they are.
+    pub fn curve_edge(
+        &mut self,
+        panel: &PanelId,
+        edge: EdgeIndex,
+        factor: Decimal,
+    ) -> Result<(), RenderError> {
+        let resolved = self.edges.get_mut(edge.0).ok_or_else(|| {
+            RenderError::NoSuchEdge {
+                panel: panel.clone(),
+                edge,
+            }
+        })?;
 
-            target.add(path);
+        for control in resolved.curve.control_points_mut() {
+            control[1] *= factor;
         }
 
-        todo!();
+        Ok(())
+    }
+
+    /// length of an edge of this panel.
+    pub fn edge_length(&self, edge: &Edge) -> Result<f64, RenderError> {
+        Ok(self.edge_segment(edge)?.length())
+    }
+
+    /// bounding box of all edges of the panel. `panel` is the name of this
+    /// panel and only used for errors. a panel without edges has an empty
+    /// bounding box at the origin.
+    pub fn as_aabb(&self, panel: &PanelId) -> Result<AABB<Decimal>, RenderError> {
+        let mut converter = Converter::lossy();
+        let (min, max) = self
+            .geometry(panel, &mut converter)?
+            .bounding_box()
+            .unwrap_or((Vector2::zeros(), Vector2::zeros()));
+
+        let min = converter.to_decimal_point(&min, BOUNDS_DECIMAL_PLACES)?;
+        let max = converter.to_decimal_point(&max, BOUNDS_DECIMAL_PLACES)?;
+
+        Ok(AABB::from(Rect::new(
+            Point2::new(min.x, min.y),
+            Point2::new(max.x, max.y),
+        )))
     }
 }
 
@@ -343,58 +794,123 @@ impl Render for Panel {
 #[serde(deny_unknown_fields)]
 pub struct Edge {
     /// endpoints indices. this references into the panel's vertex array.
-    pub endpoints: [usize; 2],
+    pub endpoints: [VertexIndex; 2],
 
-    /// bezier curve control points. each `f32` is one control point? it only
-    /// supports quadratic right now.
+    /// the shape of the edge. in the template format this is the control
+    /// point of a quadratic bezier curve:
     ///
     /// > 2D coordinates of the quadratic Bezier curve control point (named
-    /// curvature coordinates) > if the edge is not a straight line.
-    #[serde(default)]
-    pub curvature: Option<[Decimal; 2]>,
+    /// > curvature coordinates) if the edge is not a straight line.
+    ///
+    /// other curves are written as objects tagged with their `type`.
+    #[serde(rename = "curvature", default, skip_serializing_if = "Curve::is_line")]
+    pub curve: Curve,
 }
 
-impl<T> AsAABB<T> for Edge
-where
-    T: Clone + Scalar + Zero + PartialOrd,
-{
-    fn as_aabb(&self) -> AABB<T> {
-        todo!()
+impl Edge {
+    /// the same edge, traced from its end to its start.
+    pub fn reversed(&self) -> Edge {
+        Edge {
+            endpoints: [self.endpoints[1], self.endpoints[0]],
+            curve: self.curve.reversed(),
+        }
+    }
+
+    /// converts an absolute control point into relative curvature coordinates.
+    ///
+    /// the first coordinate is the position of the control point projected
+    /// onto the edge, as a fraction of the edge. the second coordinate is the
+    /// distance from the edge, as a fraction of the edge length, where positive
+    /// values are to the left of the edge (looking from `start` to `end`).
+    ///
+    /// returns `None` if `start` and `end` are the same point.
+    pub fn relative_control_point(
+        start: &Vector2<Decimal>,
+        end: &Vector2<Decimal>,
+        control: &Vector2<Decimal>,
+    ) -> Option<[Decimal; 2]> {
+        let edge = end - start;
+        let control = control - start;
+
+        let length_squared = edge.x * edge.x + edge.y * edge.y;
+        if length_squared.is_zero() {
+            return None;
+        }
+
+        let along = (control.x * edge.x + control.y * edge.y) / length_squared;
+        let across = (edge.x * control.y - edge.y * control.x) / length_squared;
+
+        Some([along, across])
+    }
+
+    /// converts relative curvature coordinates into an absolute control point.
+    /// this is the inverse of [`Edge::relative_control_point`].
+    pub fn absolute_control_point(
+        start: &Vector2<Decimal>,
+        end: &Vector2<Decimal>,
+        relative: &[Decimal; 2],
+    ) -> Vector2<Decimal> {
+        let edge = end - start;
+        let perpendicular = Vector2::new(-edge.y, edge.x);
+
+        start + edge * relative[0] + perpendicular * relative[1]
     }
 }
 
-pub struct OrderedIter<'a, T> {
-    items: &'a BTreeMap<String, T>,
-    order: Iter<'a, String>,
+/// iterator over named items in a given order. the order is resolved when
+/// the iterator is created, so iterating can't fail.
+pub struct OrderedIter<'a, K, T> {
+    items: std::vec::IntoIter<(&'a K, &'a T)>,
 }
 
-impl<'a, T> Iterator for OrderedIter<'a, T> {
-    type Item = (&'a str, &'a T);
+impl<'a, K: Ord, T> OrderedIter<'a, K, T> {
+    fn new(
+        items: &'a BTreeMap<K, T>,
+        order: &'a [K],
+        missing: impl Fn(&K) -> RenderError,
+    ) -> Result<Self, RenderError> {
+        let items = order
+            .iter()
+            .map(|name| {
+                items
+                    .get(name)
+                    .map(|item| (name, item))
+                    .ok_or_else(|| missing(name))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
 
-    fn next(&mut self) -> Option<Self::Item> {
-        let name = self.order.next()?;
+        Ok(Self {
+            items: items.into_iter(),
+        })
+    }
+}
 
-        let item = self
-            .items
-            .get(name)
-            .unwrap_or_else(|| panic!("item is in order, but doesn't exist: {}", name));
+impl<'a, K, T> Iterator for OrderedIter<'a, K, T> {
+    type Item = (&'a K, &'a T);
 
-        Some((name.as_str(), item))
+    fn next(&mut self) -> Option<Self::Item> {
+        self.items.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.items.size_hint()
     }
 }
 
+impl<'a, K, T> ExactSizeIterator for OrderedIter<'a, K, T> {}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(from = "RawEdgeRef", into = "RawEdgeRef")]
 pub struct EdgeRef {
-    pub id: usize,
+    pub id: EdgeIndex,
     pub direction: Option<Direction>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(untagged)]
 enum RawEdgeRef {
-    WithDirection { direction: Direction, id: usize },
-    Id(usize),
+    WithDirection { direction: Direction, id: EdgeIndex },
+    Id(EdgeIndex),
 }
 
 impl From<RawEdgeRef> for EdgeRef {
@@ -429,3 +945,277 @@ impl From<EdgeRef> for RawEdgeRef {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::testing;
+
+    /// a 2 x 1 panel whose bottom edge is a half circle below it.
+    fn panel() -> Panel {
+        serde_json::from_value(json!({
+            "translation": [0, 0, 0],
+            "rotation": [0, 0, 0],
+            "vertices": [[0, 0], [2, 0], [2, 1], [0, 1]],
+            "edges": [
+                {
+                    "endpoints": [0, 1],
+                    "curvature": {"type": "arc", "radius": 1, "large_arc": false, "sweep": true},
+                },
+                {"endpoints": [1, 2]},
+                {"endpoints": [2, 3]},
+                {"endpoints": [3, 0]},
+            ],
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn bounding_box_includes_curves() {
+        let rect = Rect::from(panel().as_aabb(&PanelId::from("front")).unwrap());
+        assert_eq!(rect.top_left(), &Point2::new(Decimal::ZERO, -Decimal::ONE));
+        assert_eq!(
+            rect.bottom_right(),
+            &Point2::new(Decimal::TWO, Decimal::ONE)
+        );
+    }
+
+    #[test]
+    fn bounding_box_fails_for_invalid_edges() {
+        let mut panel = panel();
+        panel.edges[2].endpoints[1] = VertexIndex(7);
+
+        assert!(matches!(
+            panel.as_aabb(&PanelId::from("front")),
+            Err(RenderError::MissingEndpoint {
+                edge: EdgeIndex(2),
+                vertex: VertexIndex(7),
+                ..
+            })
+        ));
+    }
+
+    /// a pattern with a single panel, whose edges have absolute control
+    /// points.
+    fn absolute_pattern() -> Pattern {
+        serde_json::from_value(json!({
+            "panels": {
+                "front": {
+                    "translation": [0, 0, 0],
+                    "rotation": [0, 0, 0],
+                    "vertices": [[0, 0], [3, 1], [1, 4]],
+                    "edges": [
+                        {"endpoints": [0, 1], "curvature": [2, -1]},
+                        {
+                            "endpoints": [1, 2],
+                            "curvature": {"type": "cubic", "controls": [[3.5, 2], [2.25, 3.75]]},
+                        },
+                        {"endpoints": [2, 0]},
+                    ],
+                },
+            },
+            "panel_order": ["front"],
+            "stitches": [],
+        }))
+        .unwrap()
+    }
+
+    fn control_points(pattern: &Pattern) -> Vec<[Decimal; 2]> {
+        pattern.panels[&PanelId::from("front")]
+            .edges
+            .iter()
+            .flat_map(|edge| edge.curve.control_points().to_vec())
+            .collect()
+    }
+
+    #[test]
+    fn curvature_round_trip() {
+        let original = absolute_pattern();
+        let mut pattern = absolute_pattern();
+
+        pattern
+            .convert_curvature(CurvatureCoords::Absolute, CurvatureCoords::Relative)
+            .unwrap();
+        // the quadratic control point is half way along the edge, and half its
+        // length to the right.
+        assert_eq!(
+            control_points(&pattern)[0],
+            [Decimal::new(5, 1), Decimal::new(-5, 1)]
+        );
+
+        pattern
+            .convert_curvature(CurvatureCoords::Relative, CurvatureCoords::Absolute)
+            .unwrap();
+
+        for (actual, expected) in control_points(&pattern)
+            .iter()
+            .zip(&control_points(&original))
+        {
+            for (actual, expected) in actual.iter().zip(expected) {
+                assert!(
+                    (actual - expected).abs() < Decimal::new(1, 9),
+                    "expected {}, got {}",
+                    expected,
+                    actual
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn curvature_of_zero_length_edge() {
+        let mut pattern = absolute_pattern();
+        let panel = pattern.panels.get_mut(&PanelId::from("front")).unwrap();
+        panel.vertices[1] = panel.vertices[0];
+
+        assert!(matches!(
+            pattern.convert_curvature(CurvatureCoords::Absolute, CurvatureCoords::Relative),
+            Err(RenderError::DegenerateEdge {
+                edge: EdgeIndex(0),
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn panels_follow_panel_order() {
+        let mut pattern = testing::pattern(
+            vec![
+                ("b", testing::rectangle(1, 1)),
+                ("a", testing::rectangle(2, 2)),
+            ],
+            json!([]),
+        );
+
+        let names = pattern
+            .panels()
+            .unwrap()
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["b", "a"]);
+
+        pattern.panel_order.push(PanelId::from("c"));
+        assert!(matches!(
+            pattern.panels(),
+            Err(RenderError::NoSuchPanel { panel }) if panel.as_str() == "c"
+        ));
+    }
+
+    /// a template with the parameters `length`, `waist` and `waist_curve`.
+    fn skirt() -> Template {
+        let parameter = |edge: usize, r#type: &str, value: f64| {
+            json!({
+                "influence": [{"panel": "front", "edge_list": [edge]}],
+                "range": [0.5, 2],
+                "type": r#type,
+                "value": value,
+            })
+        };
+
+        testing::template(
+            testing::pattern_json(vec![("front", testing::rectangle(40, 50))], json!([])),
+            vec![
+                ("length", parameter(1, "length", 1.0)),
+                ("waist", parameter(2, "length", 1.0)),
+                ("waist_curve", parameter(2, "curve", 1.5)),
+            ],
+        )
+    }
+
+    fn parameters(values: &[(&str, &str)]) -> Parameters {
+        Parameters {
+            parameters: values
+                .iter()
+                .map(|(name, value)| (ParameterName((*name).to_owned()), value.parse().unwrap()))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn omitted_parameters_have_their_default() {
+        let template = skirt();
+        let complete = template
+            .complete_parameters(&parameters(&[("waist", "1.2")]), false)
+            .unwrap();
+
+        assert_eq!(
+            complete.parameters,
+            parameters(&[("length", "1"), ("waist", "1.2"), ("waist_curve", "1.5")]).parameters
+        );
+        assert_eq!(
+            template
+                .complete_parameters(&Parameters::default(), false)
+                .unwrap()
+                .parameters,
+            template.default_parameters().parameters
+        );
+    }
+
+    #[test]
+    fn strict_parameters_must_be_complete() {
+        let template = skirt();
+        let all = parameters(&[("length", "1.1"), ("waist", "1.2"), ("waist_curve", "2")]);
+        assert_eq!(
+            template.complete_parameters(&all, true).unwrap().parameters,
+            all.parameters
+        );
+
+        let error = template
+            .complete_parameters(&parameters(&[("length", "1.1"), ("waist", "1.2")]), true)
+            .unwrap_err();
+        assert!(
+            matches!(&error, RenderError::NoSuchParameter { name } if name.as_str() == "waist_curve"),
+            "{:?}",
+            error
+        );
+    }
+
+    #[test]
+    fn unknown_parameters_suggest_similar_names() {
+        let template = skirt();
+        let unknown = |name: &str| {
+            match template.complete_parameters(&parameters(&[(name, "1")]), false) {
+                Err(error @ RenderError::UnknownParameter { .. }) => error,
+                other => panic!("expected {} to be unknown, got {:?}", name, other),
+            }
+        };
+        let suggestion = |name: &str| {
+            match unknown(name) {
+                RenderError::UnknownParameter { suggestion, .. } => {
+                    suggestion.map(|suggestion| suggestion.0)
+                }
+                _ => unreachable!(),
+            }
+        };
+
+        assert_eq!(suggestion("lenght").as_deref(), Some("length"));
+        // the closest name wins, although `waist` is similar, too.
+        assert_eq!(suggestion("waist_curv").as_deref(), Some("waist_curve"));
+
+        // `width` is 0.76 similar to `waist`, and `lang` 0.775 to `length`,
+        // both below the threshold.
+        assert_eq!(suggestion("width"), None);
+        assert_eq!(suggestion("lang"), None);
+        assert_eq!(suggestion("hips"), None);
+
+        assert_eq!(
+            unknown("lenght").to_string(),
+            "unknown parameter: lenght, did you mean length?"
+        );
+        assert_eq!(unknown("hips").to_string(), "unknown parameter: hips");
+
+        // unknown names are an error in strict mode, too.
+        let all = parameters(&[
+            ("length", "1"),
+            ("waist", "1"),
+            ("waist_curve", "1"),
+            ("hips", "1"),
+        ]);
+        assert!(matches!(
+            template.complete_parameters(&all, true),
+            Err(RenderError::UnknownParameter { .. })
+        ));
+    }
+}
@@ -12,7 +12,10 @@ use zip::ZipArchive;
 
 use crate::{
     error::Error,
-    pattern::Template,
+    pattern::{
+        CurvatureCoords,
+        Template,
+    },
 };
 
 pub struct Reader {
@@ -29,12 +32,12 @@ impl Reader {
         Ok(Self { zip })
     }
 
+    /// returns the path of the template specification in the archive.
     fn template_specification(&mut self) -> Result<String, Error> {
         for path in self.zip.file_names() {
-            let path = Path::new(path);
-            if let Some(file_name) = path.file_name().and_then(|s| s.to_str()) {
+            if let Some(file_name) = Path::new(path).file_name().and_then(|s| s.to_str()) {
                 if file_name.ends_with("template_specification.json") {
-                    return Ok(file_name.to_owned());
+                    return Ok(path.to_owned());
                 }
             }
         }
@@ -42,32 +45,183 @@ impl Reader {
         Err(Error::TemplatesNotFound)
     }
 
+    /// name of the template, i.e. the file name of the template
+    /// specification without the `_template_specification.json` suffix.
+    pub fn template_name(&mut self) -> Result<String, Error> {
+        let path = self.template_specification()?;
+        let file_name = Path::new(&path)
+            .file_name()
+            .and_then(|s| s.to_str())
+            .unwrap_or_default();
+
+        Ok(file_name
+            .trim_end_matches("template_specification.json")
+            .trim_end_matches('_')
+            .to_owned())
+    }
+
+    /// reads the template. if its control points are in absolute
+    /// coordinates, they are converted to relative coordinates.
     pub fn template(&mut self) -> Result<Template, Error> {
         let path = self.template_specification()?;
         let file = self.zip.by_name(&path)?;
-        Ok(json_deserialize(file)?)
+        let mut template: Template = json_deserialize(file, &path)?;
+
+        template.pattern.convert_curvature(
+            template.properties.curvature_coords,
+            CurvatureCoords::Relative,
+        )?;
+        template.properties.curvature_coords = CurvatureCoords::Relative;
+
+        Ok(template)
     }
 }
 
-fn json_deserialize<T: for<'de> Deserialize<'de>, R: Read>(mut reader: R) -> Result<T, Error> {
+/// number of lines before and after the error that are included in a
+/// [`JsonError`]'s excerpt.
+const EXCERPT_LINES: usize = 5;
+
+/// a json file in the dataset couldn't be parsed.
+#[derive(Debug, thiserror::Error)]
+#[error("invalid json in {file}#{pointer} (line {line}, column {column})")]
+pub struct JsonError {
+    /// path of the file in the archive.
+    pub file: String,
+
+    /// json pointer to the value that failed to parse, e.g.
+    /// `/pattern/panels/front/edges/3/curvature`. this is empty if the error is
+    /// in the document itself.
+    pub pointer: String,
+
+    /// line of the error, starting at 1.
+    pub line: usize,
+
+    /// column of the error, starting at 1.
+    pub column: usize,
+
+    /// lines around the error, with their line numbers. the column of the
+    /// error is marked below its line.
+    pub excerpt: String,
+
+    #[source]
+    pub source: serde_json::Error,
+}
+
+fn json_deserialize<T: for<'de> Deserialize<'de>, R: Read>(
+    mut reader: R,
+    file: &str,
+) -> Result<T, Error> {
     let mut buf = String::new();
     reader.read_to_string(&mut buf)?;
 
-    Ok(serde_json::from_str(&buf).inspect_err(|e| {
-        log::debug!("{}", e);
+    let mut deserializer = serde_json::Deserializer::from_str(&buf);
+
+    serde_path_to_error::deserialize(&mut deserializer).map_err(|error| {
+        let pointer = json_pointer(error.path());
+        let source = error.into_inner();
+        let line = source.line();
+        let column = source.column();
 
-        for (i, line) in buf.lines().enumerate() {
-            if i + 1 == e.line() {
-                log::debug!("{:03} > {}", i + 1, line);
+        let mut excerpt = String::new();
+        for (i, text) in buf.lines().enumerate() {
+            let number = i + 1;
+            if number.abs_diff(line) <= EXCERPT_LINES {
+                excerpt.push_str(&format!("{:>4} | {}\n", number, text));
             }
-            else if (i as i32 - e.line() as i32).abs() <= 10 {
-                log::debug!("{:03}   {}", i + 1, line);
+            if number == line {
+                excerpt.push_str(&format!("     | {:>width$}\n", "^", width = column.max(1)));
             }
         }
-    })?)
+
+        JsonError {
+            file: file.to_owned(),
+            pointer,
+            line,
+            column,
+            excerpt,
+            source,
+        }
+        .into()
+    })
+}
+
+/// formats a path as json pointer (RFC 6901).
+fn json_pointer(path: &serde_path_to_error::Path) -> String {
+    let mut pointer = String::new();
+
+    for segment in path.iter() {
+        pointer.push('/');
+        match segment {
+            serde_path_to_error::Segment::Seq { index } => pointer.push_str(&index.to_string()),
+            serde_path_to_error::Segment::Map { key } => pointer.push_str(&escape_token(key)),
+            serde_path_to_error::Segment::Enum { variant } => {
+                pointer.push_str(&escape_token(variant))
+            }
+            serde_path_to_error::Segment::Unknown => pointer.push('?'),
+        }
+    }
+
+    pointer
+}
+
+/// escapes `~` and `/` in a reference token of a json pointer.
+fn escape_token(token: &str) -> String {
+    token.replace('~', "~0").replace('/', "~1")
 }
 
 pub fn from_file(path: impl AsRef<Path>) -> Result<Template, Error> {
     let mut reader = Reader::new(path)?;
-    Ok(reader.template()?)
+    reader.template()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+
+    #[allow(dead_code)]
+    #[derive(Debug, Deserialize)]
+    enum Shape {
+        #[serde(rename = "half/circle")]
+        HalfCircle { radius: u32 },
+    }
+
+    fn json_error<T: for<'de> Deserialize<'de> + std::fmt::Debug>(json: &str) -> JsonError {
+        match json_deserialize::<T, _>(json.as_bytes(), "sample.json") {
+            Err(Error::JsonFile(error)) => error,
+            result => panic!("expected a json error, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn pointer_and_position() {
+        let error =
+            json_error::<BTreeMap<String, Vec<u32>>>("{\n  \"a\": [1, 2],\n  \"b\": [3, -4]\n}");
+
+        assert_eq!(error.file, "sample.json");
+        assert_eq!(error.pointer, "/b/1");
+        assert_eq!(error.line, 3);
+        assert_eq!(error.column, 13);
+        assert!(error.excerpt.contains("   3 |   \"b\": [3, -4]\n"));
+        assert!(error
+            .to_string()
+            .starts_with("invalid json in sample.json#/b/1 (line 3, column 13)"));
+    }
+
+    #[test]
+    fn pointer_escapes_tokens() {
+        let error = json_error::<BTreeMap<String, Vec<u32>>>(r#"{"a/b~c": [true]}"#);
+        assert_eq!(error.pointer, "/a~1b~0c/0");
+
+        let error = json_error::<Vec<Shape>>(r#"[{"half/circle": {"radius": -1}}]"#);
+        assert_eq!(error.pointer, "/0/half~1circle/radius");
+    }
+
+    #[test]
+    fn syntax_error_in_document() {
+        let error = json_error::<Vec<u32>>("[1, 2");
+        assert_eq!(error.pointer, "");
+        assert_eq!(error.line, 1);
+    }
 }
@@ -1,11 +1,31 @@
-use std::sync::Arc;
+//! rendering of patterns to SVG.
+//!
+//! patterns have the y axis pointing up, so all coordinates are flipped when
+//! they are written to the document. lengths are in pattern units, i.e.
+//! centimeters for templates.
 
-use parking_lot::RwLock;
-use rust_decimal::{
-    prelude::ToPrimitive,
-    Decimal,
+use std::{
+    collections::BTreeMap,
+    sync::Arc,
+};
+
+use nalgebra::{
+    Point2,
+    Vector2,
 };
+use parking_lot::RwLock;
 use svg::{
+    node::{
+        element::{
+            path::Data,
+            Animate,
+            Circle,
+            Group,
+            Path,
+            Text,
+        },
+        Text as TextNode,
+    },
     Document,
     Node,
 };
@@ -16,9 +36,69 @@ use crate::{
         Rect,
         AABB,
     },
+    curve::Segment,
     error::Error,
+    geometry::{
+        Converter,
+        PanelGeometry,
+        PatternGeometry,
+    },
+    grading::{
+        Anchor,
+        Grading,
+    },
+    id::{
+        EdgeHandle,
+        PanelId,
+    },
+    layout::LayoutOptions,
+    nesting::Marker,
+    parameters::Parameters,
+    pattern::{
+        Panel,
+        Pattern,
+        RenderError,
+    },
+    sweep::SweepGrid,
 };
 
+/// width of panel outlines.
+const OUTLINE_WIDTH: f64 = 0.1;
+
+/// width of stitched edges, when they are highlighted.
+const STITCH_WIDTH: f64 = 0.3;
+
+/// length of notch marks, centered on the edge.
+const NOTCH_LENGTH: f64 = 1.0;
+
+/// length of the arrows that show the direction of edges.
+const ARROW_SIZE: f64 = 1.0;
+
+/// font size of panel and stitch labels.
+const LABEL_SIZE: f64 = 1.5;
+
+/// font size of edge indices.
+const EDGE_LABEL_SIZE: f64 = 1.0;
+
+/// font size of the title block's parameter values.
+const TITLE_SIZE: f64 = 2.0;
+
+/// distance of stitch labels and edge indices from their edge.
+const LABEL_OFFSET: f64 = 1.5;
+
+/// colors of stitches. stitches are numbered and use the colors in order.
+const STITCH_COLORS: &[&str] = &[
+    "#e6194b", "#3cb44b", "#4363d8", "#f58231", "#911eb4", "#42d4f4", "#f032e6", "#9a6324",
+    "#800000", "#000075",
+];
+
+/// dash patterns of graded sizes. sizes use the colors of
+/// [`STITCH_COLORS`] and these patterns in order.
+const SIZE_DASHES: &[&str] = &["none", "1 0.5", "0.3 0.3", "2 0.5 0.3 0.5"];
+
+/// length of the lines in the legend of graded sizes.
+const LEGEND_LENGTH: f64 = 5.0;
+
 #[derive(Debug, Default)]
 struct Inner {
     /// note: this needs to be an option, so we can replace it easily. but the
@@ -26,27 +106,19 @@ struct Inner {
     /// created empty, but the document will be lazily created when needed
     document: Option<Document>,
 
-    /// this tracks how large the view box is.
-    view_box: AABB<Decimal>,
+    /// this tracks how large the view box is, in document coordinates.
+    view_box: AABB<f64>,
 }
 
 /// thread-safe clonable wrapper around the document.
 ///
 /// note: we also need to wrap it into an option to be able to replace the
 /// value.
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct Target {
     inner: Arc<RwLock<Inner>>,
 }
 
-impl Default for Target {
-    fn default() -> Self {
-        Self {
-            inner: Default::default(),
-        }
-    }
-}
-
 impl Target {
     pub fn add<T: Node>(&mut self, node: T) {
         let mut inner = self.inner.write();
@@ -58,27 +130,46 @@ impl Target {
         inner.document = Some(document);
     }
 
-    pub fn resize_for(&mut self, aabb: impl AsAABB<Decimal>) {
+    pub fn resize_for(&mut self, aabb: impl AsAABB<f64>) {
         let mut inner = self.inner.write();
         inner.view_box.insert(aabb);
     }
 
-    fn build(self) -> Document {
+    /// the area that was drawn so far, in document coordinates.
+    pub fn view_box(&self) -> Rect<f64> {
+        Rect::from(self.inner.read().view_box)
+    }
+
+    /// grows the view box to contain a panel.
+    fn resize_for_panel(&mut self, geometry: &PanelGeometry) {
+        if let Some((min, max)) = geometry.bounding_box() {
+            let (left, top) = to_document(&Vector2::new(min.x, max.y));
+            let (right, bottom) = to_document(&Vector2::new(max.x, min.y));
+            self.resize_for(Rect::new(
+                Point2::new(left, top),
+                Point2::new(right, bottom),
+            ));
+        }
+    }
+
+    /// finishes the document. fails if the view box isn't finite.
+    pub fn build(self) -> Result<Document, RenderError> {
         let mut inner = self.inner.write();
 
         let document = inner.document.take().unwrap_or_else(Document::new);
 
         let view_box = Rect::from(inner.view_box);
+        let values = [
+            view_box.top_left().x,
+            view_box.top_left().y,
+            view_box.width(),
+            view_box.height(),
+        ];
+        if let Some(value) = values.iter().find(|value| !value.is_finite()) {
+            return Err(RenderError::NotFiniteViewBox { value: *value });
+        }
 
-        document.set(
-            "viewBox",
-            (
-                view_box.top_left().x.to_f64().unwrap(),
-                view_box.top_left().y.to_f64().unwrap(),
-                view_box.bottom_right().x.to_f64().unwrap(),
-                view_box.bottom_right().y.to_f64().unwrap(),
-            ),
-        )
+        Ok(document.set("viewBox", (values[0], values[1], values[2], values[3])))
     }
 }
 
@@ -88,3 +179,1187 @@ pub trait Render {
 
     fn render(&self, target: &mut Target, context: &Self::Context) -> Result<(), Error>;
 }
+
+/// what is drawn on top of the panel outlines.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Overlays {
+    /// draw stitched edges in the color of their stitch and label them with
+    /// the stitch number.
+    pub stitches: bool,
+
+    /// draw notches at the ends and the midpoint of stitched edges.
+    pub notches: bool,
+
+    /// draw arrows on stitched edges that show the direction they are sewn
+    /// in, see [`sewing_directions`].
+    pub arrows: bool,
+
+    /// label edges with their index. this is useful for debugging templates.
+    pub edge_indices: bool,
+}
+
+/// how a [`Pattern`] is rendered.
+#[derive(Clone, Debug, Default)]
+pub struct PatternContext {
+    pub overlays: Overlays,
+
+    /// how many times panels are cut. panels that aren't in here are cut
+    /// once.
+    pub cut: BTreeMap<PanelId, u32>,
+
+    /// drawn below the panels.
+    pub title: Option<TitleBlock>,
+
+    /// place the panels next to each other. if `None`, the panels are drawn
+    /// in their own coordinates.
+    pub layout: Option<LayoutOptions>,
+}
+
+/// how a [`Panel`] is rendered.
+#[derive(Clone, Debug)]
+pub struct PanelContext {
+    /// the name of the panel, used for the label and in errors.
+    pub name: PanelId,
+
+    /// how many times the panel is cut.
+    pub cut: u32,
+
+    /// label edges with their index.
+    pub edge_indices: bool,
+}
+
+/// how a [`Grading`] is rendered.
+#[derive(Copy, Clone, Debug)]
+pub struct GradingContext {
+    /// the point at which the sizes of every panel are aligned.
+    pub anchor: Anchor,
+
+    /// space between panels.
+    pub spacing: f64,
+}
+
+/// how a [`SweepGrid`] is rendered.
+#[derive(Clone, Debug, Default)]
+pub struct SweepContext {
+    /// how every pattern is rendered. the title is ignored, every pattern is
+    /// labelled with its parameter values instead.
+    pub pattern: PatternContext,
+
+    /// space between patterns.
+    pub spacing: f64,
+
+    /// show the patterns one after another in an animation, with this many
+    /// seconds per pattern, instead of next to each other.
+    pub frame_duration: Option<f64>,
+}
+
+/// the name of the template and the parameters the pattern was made with.
+#[derive(Clone, Debug)]
+pub struct TitleBlock {
+    pub name: String,
+    pub parameters: Parameters,
+}
+
+impl PanelContext {
+    pub fn new(name: PanelId) -> Self {
+        Self {
+            name,
+            cut: 1,
+            edge_indices: false,
+        }
+    }
+}
+
+impl Render for Pattern {
+    type Context = PatternContext;
+
+    fn render(&self, target: &mut Target, context: &Self::Context) -> Result<(), Error> {
+        if let Some(options) = &context.layout {
+            let context = PatternContext {
+                layout: None,
+                ..context.clone()
+            };
+            return self.laid_out(options)?.render(target, &context);
+        }
+
+        for (name, panel) in self.panels()? {
+            let panel_context = PanelContext {
+                name: name.clone(),
+                cut: context.cut.get(name).copied().unwrap_or(1),
+                edge_indices: context.overlays.edge_indices,
+            };
+            panel.render(target, &panel_context)?;
+        }
+
+        self.render_overlays(target, &context.overlays)?;
+
+        if let Some(title) = &context.title {
+            title.render(target, &())?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Pattern {
+    fn render_overlays(&self, target: &mut Target, context: &Overlays) -> Result<(), Error> {
+        if !(context.stitches || context.notches || context.arrows) {
+            return Ok(());
+        }
+
+        let geometry = self.geometry(&mut Converter::lossy())?;
+
+        let graph = self.stitch_graph()?;
+
+        for (index, stitch) in graph.stitches().iter().enumerate() {
+            let color = STITCH_COLORS[index % STITCH_COLORS.len()];
+            let directions = sewing_directions(&geometry, stitch)?;
+
+            for (side, reversed) in stitch.iter().zip(directions) {
+                let segment = stitched_segment(&geometry, side)?;
+
+                if context.stitches {
+                    render_stitch(target, &segment, index, color);
+                }
+                if context.notches {
+                    for t in [0.0, 0.5, 1.0] {
+                        render_notch(target, &segment, t, color);
+                    }
+                }
+                if context.arrows {
+                    render_arrow(target, &segment, reversed);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Render for Panel {
+    type Context = PanelContext;
+
+    fn render(&self, target: &mut Target, context: &Self::Context) -> Result<(), Error> {
+        let geometry = self.geometry(&context.name, &mut Converter::lossy())?;
+
+        for (index, edge) in geometry.edges.iter().enumerate() {
+            let path = Path::new()
+                .set("fill", "none")
+                .set("stroke", "black")
+                .set("stroke-width", OUTLINE_WIDTH)
+                .set("d", segment_data(&edge.segment));
+
+            target.add(path);
+
+            if context.edge_indices {
+                // outside of the panel, so it doesn't collide with stitch
+                // labels.
+                let position =
+                    edge.segment.point_at(0.5) - normal_at(&edge.segment, 0.5) * LABEL_OFFSET;
+                target.add(text(&position, EDGE_LABEL_SIZE, "gray", index));
+
+                let (x, y) = to_document(&position);
+                let margin = Vector2::repeat(EDGE_LABEL_SIZE);
+                target.resize_for(Point2::new(x, y) - margin);
+                target.resize_for(Point2::new(x, y) + margin);
+            }
+        }
+
+        if let Some((min, max)) = geometry.bounding_box() {
+            let center = (min + max) / 2.0;
+            let grain = geometry.grain_direction();
+
+            // the grain line is as long as half the panel's extent along it.
+            let extent = (max - min).component_mul(&grain).abs().sum();
+            render_grain_line(target, &center, &grain, extent / 2.0);
+
+            // the label goes next to the grain line.
+            let beside = Vector2::new(grain.y, -grain.x) * LABEL_SIZE;
+            target.add(text(&(center + beside), LABEL_SIZE, "black", &context.name));
+            target.add(text(
+                &(center + beside * 2.0),
+                LABEL_SIZE,
+                "black",
+                format!("cut {}", context.cut),
+            ));
+        }
+
+        target.resize_for_panel(&geometry);
+
+        Ok(())
+    }
+}
+
+impl Render for TitleBlock {
+    type Context = ();
+
+    /// renders the title block below everything that was rendered so far.
+    fn render(&self, target: &mut Target, _context: &Self::Context) -> Result<(), Error> {
+        let view_box = target.view_box();
+        let left = view_box.top_left().x;
+        let mut y = view_box.bottom_right().y;
+
+        let lines = std::iter::once(self.name.clone()).chain(
+            self.parameters
+                .parameters
+                .iter()
+                .map(|(name, value)| format!("{} = {}", name, value)),
+        );
+
+        for (index, line) in lines.enumerate() {
+            y += TITLE_SIZE * 1.5;
+            let size = if index == 0 {
+                TITLE_SIZE * 1.5
+            }
+            else {
+                TITLE_SIZE
+            };
+
+            let node = Text::new()
+                .set("x", left)
+                .set("y", y)
+                .set("font-size", size)
+                .set("font-family", "sans-serif")
+                .add(TextNode::new(line));
+            target.add(node);
+        }
+
+        target.resize_for(Point2::new(left, y + TITLE_SIZE));
+
+        Ok(())
+    }
+}
+
+impl Render for Marker {
+    type Context = ();
+
+    /// renders the fabric, the cutting lines and the seam lines of all pieces,
+    /// and the length of fabric used.
+    fn render(&self, target: &mut Target, _context: &Self::Context) -> Result<(), Error> {
+        let fabric = [
+            Vector2::zeros(),
+            Vector2::new(self.length, 0.0),
+            Vector2::new(self.length, self.fabric_width),
+            Vector2::new(0.0, self.fabric_width),
+        ];
+        let path = Path::new()
+            .set("fill", "none")
+            .set("stroke", "gray")
+            .set("stroke-width", OUTLINE_WIDTH)
+            .set("stroke-dasharray", "2 1")
+            .set("d", polygon_data(&fabric));
+        target.add(path);
+
+        for placement in &self.placements {
+            let path = Path::new()
+                .set("fill", "none")
+                .set("stroke", "black")
+                .set("stroke-width", OUTLINE_WIDTH)
+                .set("d", polygon_data(&placement.cut_line));
+            target.add(path);
+
+            let path = Path::new()
+                .set("fill", "none")
+                .set("stroke", "gray")
+                .set("stroke-width", OUTLINE_WIDTH)
+                .set("stroke-dasharray", "0.5 0.5")
+                .set("d", polygon_data(&placement.seam_line));
+            target.add(path);
+
+            let (min, max) = placement.bounding_box();
+            let center = (min + max) / 2.0;
+
+            // pieces are nested with the grain along the roll.
+            render_grain_line(target, &center, &Vector2::x(), (max.x - min.x) / 2.0);
+
+            let mut label = format!("{} #{}", placement.panel, placement.copy + 1);
+            if placement.mirrored {
+                label.push_str(" (mirrored)");
+            }
+            let beside = Vector2::new(0.0, -LABEL_SIZE);
+            target.add(text(&(center + beside), LABEL_SIZE, "black", label));
+        }
+
+        let (left, top) = to_document(&fabric[3]);
+        let (right, bottom) = to_document(&fabric[1]);
+        target.resize_for(Rect::new(
+            Point2::new(left, top),
+            Point2::new(right, bottom),
+        ));
+
+        let position = Vector2::new(self.length / 2.0, -TITLE_SIZE * 1.5);
+        target.add(text(
+            &position,
+            TITLE_SIZE,
+            "black",
+            format!(
+                "{:.1} x {:.1}, {:.0}% used",
+                self.length,
+                self.fabric_width,
+                self.utilization() * 100.0
+            ),
+        ));
+        let (x, y) = to_document(&position);
+        target.resize_for(Point2::new(x, y + TITLE_SIZE));
+
+        Ok(())
+    }
+}
+
+impl Render for Grading {
+    type Context = GradingContext;
+
+    /// renders all sizes of every panel on top of each other, aligned at the
+    /// anchor, with the panels next to each other and a legend below.
+    fn render(&self, target: &mut Target, context: &Self::Context) -> Result<(), Error> {
+        let Some(first) = self.sizes.first()
+        else {
+            return Ok(());
+        };
+
+        let geometries = self
+            .sizes
+            .iter()
+            .map(|size| size.pattern.geometry(&mut Converter::lossy()))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut x = 0.0;
+        for (name, _) in first.pattern.panels()? {
+            // the panel of every size, moved so that its anchor is at the
+            // origin.
+            let mut sizes = vec![];
+            for (index, geometry) in geometries.iter().enumerate() {
+                let Some(panel) = geometry.panels.get(name)
+                else {
+                    continue;
+                };
+                let Some(anchor) = context.anchor.point(panel)
+                else {
+                    if let Anchor::Vertex(index) = context.anchor {
+                        return Err(RenderError::NoSuchVertex { index }.into());
+                    }
+                    continue;
+                };
+                if let Some((min, max)) = panel.bounding_box() {
+                    sizes.push((index, panel, -anchor, min - anchor, max - anchor));
+                }
+            }
+
+            let Some((min, max)) = sizes.iter().map(|(_, _, _, min, max)| (*min, *max)).reduce(
+                |(min, max), (other_min, other_max)| (min.inf(&other_min), max.sup(&other_max)),
+            )
+            else {
+                continue;
+            };
+
+            // the group of sizes goes to the right of the previous one.
+            let shift = Vector2::new(x - min.x, -min.y);
+
+            for (index, panel, offset, _, _) in &sizes {
+                let color = STITCH_COLORS[index % STITCH_COLORS.len()];
+                let dashes = SIZE_DASHES[index % SIZE_DASHES.len()];
+
+                for edge in &panel.edges {
+                    let segment = edge.segment.translated(&(offset + shift));
+                    let path = Path::new()
+                        .set("fill", "none")
+                        .set("stroke", color)
+                        .set("stroke-width", OUTLINE_WIDTH)
+                        .set("stroke-dasharray", dashes)
+                        .set("d", segment_data(&segment));
+                    target.add(path);
+                }
+            }
+
+            let (anchor_x, anchor_y) = to_document(&shift);
+            let circle = Circle::new()
+                .set("cx", anchor_x)
+                .set("cy", anchor_y)
+                .set("r", NOTCH_LENGTH / 2.0)
+                .set("fill", "black");
+            target.add(circle);
+
+            let top = max + shift;
+            let label = Vector2::new(x + (max.x - min.x) / 2.0, top.y + LABEL_SIZE);
+            target.add(text(&label, LABEL_SIZE, "black", name));
+
+            let (left, top) = to_document(&Vector2::new(x, label.y + LABEL_SIZE));
+            let (right, bottom) = to_document(&Vector2::new(x + max.x - min.x, 0.0));
+            target.resize_for(Rect::new(
+                Point2::new(left, top),
+                Point2::new(right, bottom),
+            ));
+
+            x += max.x - min.x + context.spacing;
+        }
+
+        // legend
+        for (index, size) in self.sizes.iter().enumerate() {
+            let y = -(index as f64 + 2.0) * LABEL_SIZE * 1.5;
+            let data = Data::new()
+                .move_to(to_document(&Vector2::new(0.0, y)))
+                .line_to(to_document(&Vector2::new(LEGEND_LENGTH, y)));
+            let path = Path::new()
+                .set("fill", "none")
+                .set("stroke", STITCH_COLORS[index % STITCH_COLORS.len()])
+                .set("stroke-width", OUTLINE_WIDTH * 2.0)
+                .set("stroke-dasharray", SIZE_DASHES[index % SIZE_DASHES.len()])
+                .set("d", data);
+            target.add(path);
+
+            let (x, y) = to_document(&Vector2::new(LEGEND_LENGTH + LABEL_SIZE, y));
+            let node = Text::new()
+                .set("x", x)
+                .set("y", y)
+                .set("font-size", LABEL_SIZE)
+                .set("font-family", "sans-serif")
+                .set("dominant-baseline", "middle")
+                .add(TextNode::new(size.name.clone()));
+            target.add(node);
+
+            target.resize_for(Point2::new(x + LABEL_SIZE * 4.0, y + LABEL_SIZE));
+        }
+
+        Ok(())
+    }
+}
+
+impl SweepGrid {
+    /// renders every pattern into its own document, labelled with the values
+    /// of the swept parameters. returns the view box of every document, too.
+    pub fn frames(&self, context: &PatternContext) -> Result<Vec<(Rect<f64>, Document)>, Error> {
+        let context = PatternContext {
+            title: None,
+            ..context.clone()
+        };
+
+        self.cells
+            .iter()
+            .map(|cell| {
+                let mut target = Target::default();
+                cell.pattern.render(&mut target, &context)?;
+
+                let view_box = target.view_box();
+                let label = self.label(cell);
+                let center = view_box.top_left().x + view_box.width() / 2.0;
+                let top = view_box.top_left().y - LABEL_SIZE;
+                target.add(text(
+                    &Vector2::new(center, -top),
+                    LABEL_SIZE,
+                    "black",
+                    &label,
+                ));
+
+                // roughly the width of the label.
+                let half_width = label.len() as f64 * LABEL_SIZE * 0.3;
+                target.resize_for(Point2::new(center - half_width, top - LABEL_SIZE));
+                target.resize_for(Point2::new(center + half_width, top));
+
+                let view_box = target.view_box();
+                Ok((view_box, target.build()?))
+            })
+            .collect()
+    }
+}
+
+impl Render for SweepGrid {
+    type Context = SweepContext;
+
+    /// renders a contact sheet with the patterns in a grid, or an animation
+    /// if `frame_duration` is set.
+    fn render(&self, target: &mut Target, context: &Self::Context) -> Result<(), Error> {
+        let frames = self.frames(&context.pattern)?;
+
+        if let Some(duration) = context.frame_duration {
+            // all frames are shown in the same place, so they get the same
+            // view box.
+            let mut view_box = AABB::<f64>::default();
+            for (frame, _) in &frames {
+                view_box.insert(*frame);
+            }
+            let view_box = Rect::from(view_box);
+            let count = frames.len() as f64;
+
+            for (index, (_, document)) in frames.into_iter().enumerate() {
+                let start = index as f64 / count;
+                let end = (index + 1) as f64 / count;
+                let (values, key_times) = if index == 0 {
+                    ("1;0".to_owned(), format!("0;{}", end))
+                }
+                else {
+                    ("0;1;0".to_owned(), format!("0;{};{}", start, end))
+                };
+
+                let animate = Animate::new()
+                    .set("attributeName", "opacity")
+                    .set("calcMode", "discrete")
+                    .set("values", values)
+                    .set("keyTimes", key_times)
+                    .set("dur", format!("{}s", duration * count))
+                    .set("repeatCount", "indefinite");
+
+                let group = Group::new()
+                    .set("opacity", if index == 0 { 1 } else { 0 })
+                    .add(nested(document, &view_box, view_box.top_left()))
+                    .add(animate);
+                target.add(group);
+            }
+
+            target.resize_for(view_box);
+        }
+        else {
+            let cell = frames
+                .iter()
+                .map(|(view_box, _)| view_box.size())
+                .fold(Vector2::zeros(), |cell: Vector2<f64>, size| cell.sup(&size));
+
+            for (index, (view_box, document)) in frames.into_iter().enumerate() {
+                let row = (index / self.columns) as f64;
+                let column = (index % self.columns) as f64;
+                let position = Point2::new(
+                    column * (cell.x + context.spacing),
+                    row * (cell.y + context.spacing),
+                );
+
+                target.add(nested(document, &view_box, &position));
+                target.resize_for(Rect::new(position, position + view_box.size()));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// places a document inside another one, with the top left corner of its
+/// view box at `position`.
+fn nested(document: Document, view_box: &Rect<f64>, position: &Point2<f64>) -> Document {
+    document
+        .set("x", position.x)
+        .set("y", position.y)
+        .set("width", view_box.width())
+        .set("height", view_box.height())
+        .set(
+            "viewBox",
+            (
+                view_box.top_left().x,
+                view_box.top_left().y,
+                view_box.width(),
+                view_box.height(),
+            ),
+        )
+}
+
+fn render_grain_line(
+    target: &mut Target,
+    center: &Vector2<f64>,
+    grain: &Vector2<f64>,
+    length: f64,
+) {
+    let start = center - grain * (length / 2.0);
+    let end = center + grain * (length / 2.0);
+    let side = Vector2::new(-grain.y, grain.x) * (ARROW_SIZE / 3.0);
+
+    let mut data = Data::new()
+        .move_to(to_document(&start))
+        .line_to(to_document(&end));
+    for (tip, direction) in [(end, *grain), (start, -grain)] {
+        let base = tip - direction * ARROW_SIZE;
+        data = data
+            .move_to(to_document(&(base + side)))
+            .line_to(to_document(&tip))
+            .line_to(to_document(&(base - side)));
+    }
+
+    let path = Path::new()
+        .set("fill", "none")
+        .set("stroke", "black")
+        .set("stroke-width", OUTLINE_WIDTH)
+        .set("d", data);
+    target.add(path);
+}
+
+/// centered text at `position`.
+fn text(position: &Vector2<f64>, size: f64, color: &str, content: impl ToString) -> Text {
+    let (x, y) = to_document(position);
+    Text::new()
+        .set("x", x)
+        .set("y", y)
+        .set("fill", color)
+        .set("font-size", size)
+        .set("font-family", "sans-serif")
+        .set("text-anchor", "middle")
+        .set("dominant-baseline", "middle")
+        .add(TextNode::new(content.to_string()))
+}
+
+/// converts a point in pattern coordinates into document coordinates.
+fn to_document(point: &Vector2<f64>) -> (f64, f64) {
+    (point.x, -point.y)
+}
+
+/// path data for a closed polygon.
+fn polygon_data(points: &[Vector2<f64>]) -> Data {
+    let mut data = Data::new();
+    for (index, point) in points.iter().enumerate() {
+        data = if index == 0 {
+            data.move_to(to_document(point))
+        }
+        else {
+            data.line_to(to_document(point))
+        };
+    }
+    data.close()
+}
+
+/// path data for a single segment.
+fn segment_data(segment: &Segment) -> Data {
+    let end = to_document(&segment.end());
+    let data = Data::new().move_to(to_document(&segment.start()));
+
+    match segment {
+        Segment::Line { .. } => data.line_to(end),
+        Segment::Quadratic { control, .. } => {
+            let control = to_document(control);
+            data.quadratic_curve_to((control.0, control.1, end.0, end.1))
+        }
+        Segment::Cubic { controls, .. } => {
+            let first = to_document(&controls[0]);
+            let second = to_document(&controls[1]);
+            data.cubic_curve_to((first.0, first.1, second.0, second.1, end.0, end.1))
+        }
+        Segment::Arc {
+            radius,
+            sweep_angle,
+            ..
+        } => {
+            let large_arc = sweep_angle.abs() > std::f64::consts::PI;
+            // flipping the y axis also flips the sweep direction.
+            let sweep = *sweep_angle < 0.0;
+            data.elliptical_arc_to((
+                *radius,
+                *radius,
+                0.0,
+                large_arc as u8,
+                sweep as u8,
+                end.0,
+                end.1,
+            ))
+        }
+    }
+}
+
+/// looks up the segment of a stitched edge.
+fn stitched_segment(geometry: &PatternGeometry, side: &EdgeHandle) -> Result<Segment, RenderError> {
+    geometry
+        .panels
+        .get(&side.panel)
+        .and_then(|panel| panel.edges.get(side.edge.0))
+        .map(|edge| edge.segment)
+        .ok_or_else(|| {
+            RenderError::NoSuchEdge {
+                panel: side.panel.clone(),
+                edge: side.edge,
+            }
+        })
+}
+
+/// the direction every edge of a stitch is sewn in, relative to the edge's
+/// own direction. `true` means that the edge is sewn from its end to its
+/// start.
+///
+/// the first edge is sewn along its direction. the other edges are sewn the
+/// way their ends meet the ends of the first edge when the panels are placed
+/// in 3D: an edge is reversed if its end is closer to the start of the first
+/// edge, and its start closer to the end of the first edge, than the other
+/// way around.
+pub fn sewing_directions(
+    geometry: &PatternGeometry,
+    stitch: &[EdgeHandle],
+) -> Result<Vec<bool>, RenderError> {
+    let ends = stitch
+        .iter()
+        .map(|side| {
+            let panel = geometry.panels.get(&side.panel).ok_or_else(|| {
+                RenderError::NoSuchPanel {
+                    panel: side.panel.clone(),
+                }
+            })?;
+            let segment = stitched_segment(geometry, side)?;
+            Ok([
+                panel.to_world(&segment.start()),
+                panel.to_world(&segment.end()),
+            ])
+        })
+        .collect::<Result<Vec<_>, RenderError>>()?;
+
+    let Some([first_start, first_end]) = ends.first()
+    else {
+        return Ok(vec![]);
+    };
+
+    Ok(ends
+        .iter()
+        .map(|[start, end]| {
+            let along = (start - first_start).norm() + (end - first_end).norm();
+            let against = (end - first_start).norm() + (start - first_end).norm();
+            against < along
+        })
+        .collect())
+}
+
+/// left of the segment's direction at `t`. this points into the panel for
+/// counter-clockwise edge loops.
+fn normal_at(segment: &Segment, t: f64) -> Vector2<f64> {
+    let direction = segment.direction_at(t);
+    Vector2::new(-direction.y, direction.x)
+}
+
+fn render_stitch(target: &mut Target, segment: &Segment, index: usize, color: &str) {
+    let path = Path::new()
+        .set("fill", "none")
+        .set("stroke", color)
+        .set("stroke-width", STITCH_WIDTH)
+        .set("stroke-opacity", 0.6)
+        .set("d", segment_data(segment));
+    target.add(path);
+
+    let position = segment.point_at(0.5) + normal_at(segment, 0.5) * LABEL_OFFSET;
+    target.add(text(&position, LABEL_SIZE, color, index + 1));
+}
+
+fn render_notch(target: &mut Target, segment: &Segment, t: f64, color: &str) {
+    let point = segment.point_at(t);
+    let normal = normal_at(segment, t) * (NOTCH_LENGTH / 2.0);
+
+    let data = Data::new()
+        .move_to(to_document(&(point - normal)))
+        .line_to(to_document(&(point + normal)));
+
+    let path = Path::new()
+        .set("fill", "none")
+        .set("stroke", color)
+        .set("stroke-width", OUTLINE_WIDTH)
+        .set("d", data);
+    target.add(path);
+}
+
+/// draws an arrow in the middle of the segment, pointing along it or, if
+/// `reversed`, against it.
+fn render_arrow(target: &mut Target, segment: &Segment, reversed: bool) {
+    let point = segment.point_at(0.5);
+    let sign = if reversed { -1.0 } else { 1.0 };
+    let direction = segment.direction_at(0.5) * (sign * ARROW_SIZE / 2.0);
+    let normal = normal_at(segment, 0.5) * (ARROW_SIZE / 3.0);
+
+    let data = Data::new()
+        .move_to(to_document(&(point + direction)))
+        .line_to(to_document(&(point - direction + normal)))
+        .line_to(to_document(&(point - direction - normal)))
+        .close();
+
+    let path = Path::new().set("fill", "black").set("d", data);
+    target.add(path);
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        grading::SizeChart,
+        id::{
+            ParameterName,
+            VertexIndex,
+        },
+        testing,
+    };
+
+    fn square(translation: [i64; 3]) -> serde_json::Value {
+        json!({
+            "translation": translation,
+            "rotation": [0, 0, 0],
+            "vertices": [[0, 0], [10, 0], [10, 10], [0, 10]],
+            "edges": [
+                {"endpoints": [0, 1]},
+                {"endpoints": [1, 2]},
+                {"endpoints": [2, 3]},
+                {"endpoints": [3, 0]},
+            ],
+        })
+    }
+
+    /// the back is behind the front and 10 to the right, so its left side is
+    /// behind the front's right side, but runs the other way. the side is
+    /// right behind the front, so its right side runs the same way as the
+    /// front's right side. both are sewn to the front's right side.
+    fn pattern() -> Pattern {
+        serde_json::from_value(json!({
+            "panels": {
+                "front": square([0, 0, 0]),
+                "back": square([10, 0, -5]),
+                "side": square([0, 0, -5]),
+            },
+            "panel_order": ["front", "back", "side"],
+            "stitches": [
+                [{"panel": "front", "edge": 1}, {"panel": "back", "edge": 3}],
+                [{"panel": "front", "edge": 1}, {"panel": "side", "edge": 1}],
+            ],
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn sewing_directions_follow_the_pair() {
+        let pattern = pattern();
+        let geometry = pattern.geometry(&mut Converter::lossy()).unwrap();
+        let graph = pattern.stitch_graph().unwrap();
+
+        assert_eq!(
+            sewing_directions(&geometry, &graph.stitches()[0]).unwrap(),
+            vec![false, true]
+        );
+        assert_eq!(
+            sewing_directions(&geometry, &graph.stitches()[1]).unwrap(),
+            vec![false, false]
+        );
+    }
+
+    #[test]
+    fn arrows_only_on_stitched_edges() {
+        let pattern = pattern();
+        let mut target = Target::default();
+        let overlays = Overlays {
+            arrows: true,
+            ..Default::default()
+        };
+        pattern.render_overlays(&mut target, &overlays).unwrap();
+
+        let document = target.inner.read().document.clone().unwrap().to_string();
+        assert_eq!(document.matches("fill=\"black\"").count(), 4);
+    }
+
+    fn render(pattern: &Pattern, context: &PatternContext) -> String {
+        let mut target = Target::default();
+        pattern.render(&mut target, context).unwrap();
+        target.build().unwrap().to_string()
+    }
+
+    fn count(svg: &str, pattern: &str) -> usize {
+        svg.matches(pattern).count()
+    }
+
+    /// how many text elements have `content`.
+    fn count_texts(svg: &str, content: &str) -> usize {
+        svg.split("</text>")
+            .filter_map(|part| part.rsplit_once('>'))
+            .filter(|(_, text)| text.trim() == content)
+            .count()
+    }
+
+    #[test]
+    fn panels_have_labels_and_grain_lines() {
+        let context = PatternContext {
+            cut: [(PanelId::from("back"), 2)].into_iter().collect(),
+            ..Default::default()
+        };
+        let svg = render(&pattern(), &context);
+
+        for name in ["front", "back", "side"] {
+            assert_eq!(count_texts(&svg, name), 1, "{}", name);
+        }
+        assert_eq!(count_texts(&svg, "cut 1"), 2);
+        assert_eq!(count_texts(&svg, "cut 2"), 1);
+
+        // four edges and a grain line for every panel, and nothing else.
+        assert_eq!(count(&svg, "<path"), 15);
+        assert_eq!(count(&svg, "stroke=\"black\""), 15);
+    }
+
+    #[test]
+    fn overlays_are_optional() {
+        let pattern = pattern();
+        let plain = render(&pattern, &PatternContext::default());
+
+        let with = |overlays: Overlays| {
+            render(
+                &pattern,
+                &PatternContext {
+                    overlays,
+                    ..Default::default()
+                },
+            )
+        };
+
+        // stitches highlight both sides of both stitches and label them.
+        let svg = with(Overlays {
+            stitches: true,
+            ..Default::default()
+        });
+        assert_eq!(count(&plain, "stroke-opacity"), 0);
+        assert_eq!(count(&svg, "stroke-opacity"), 4);
+        assert_eq!(count_texts(&svg, "1"), 2);
+        assert_eq!(count_texts(&svg, "2"), 2);
+
+        // three notches on every side.
+        let svg = with(Overlays {
+            notches: true,
+            ..Default::default()
+        });
+        assert_eq!(count(&svg, "<path") - count(&plain, "<path"), 12);
+
+        // edge indices are gray labels outside of every edge.
+        let svg = with(Overlays {
+            edge_indices: true,
+            ..Default::default()
+        });
+        assert_eq!(count(&plain, "fill=\"gray\""), 0);
+        assert_eq!(count(&svg, "fill=\"gray\""), 12);
+        assert_eq!(count_texts(&svg, "3"), 3);
+    }
+
+    #[test]
+    fn title_block() {
+        let pattern = pattern();
+        let mut parameters = Parameters::default();
+        parameters.parameters.insert(
+            crate::id::ParameterName::from("length"),
+            rust_decimal::Decimal::new(8, 1),
+        );
+
+        let plain = render(&pattern, &PatternContext::default());
+        let svg = render(
+            &pattern,
+            &PatternContext {
+                title: Some(TitleBlock {
+                    name: "skirt_2".to_owned(),
+                    parameters,
+                }),
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(count_texts(&plain, "skirt_2"), 0);
+        assert_eq!(count_texts(&svg, "skirt_2"), 1);
+        assert_eq!(count_texts(&svg, "length = 0.8"), 1);
+
+        // the title goes below the panels, and the view box grows to fit it.
+        let view_box = |svg: &str| {
+            let start = svg.find("viewBox=\"").unwrap() + 9;
+            let end = start + svg[start..].find('"').unwrap();
+            svg[start..end]
+                .split_whitespace()
+                .map(|value| value.parse::<f64>().unwrap())
+                .collect::<Vec<_>>()
+        };
+        assert!(view_box(&svg)[3] > view_box(&plain)[3]);
+    }
+
+    fn grading() -> Grading {
+        let chart: SizeChart = serde_json::from_value(json!({
+            "measurements": {"hem": {"edges": ["front:0"]}},
+            "sizes": [{"name": "S", "hem": 30}, {"name": "L", "hem": 60}],
+        }))
+        .unwrap();
+        testing::hem_template()
+            .grade(&chart, &Default::default())
+            .unwrap()
+    }
+
+    fn render_grading(anchor: Anchor) -> Result<String, Error> {
+        let mut target = Target::default();
+        let context = GradingContext {
+            anchor,
+            spacing: 5.0,
+        };
+        grading().render(&mut target, &context)?;
+        Ok(target.build().unwrap().to_string())
+    }
+
+    /// start and end of the outline edges drawn in `color`.
+    fn outline(svg: &str, color: &str) -> Vec<[(f64, f64); 2]> {
+        let point = |point: &str| {
+            let (x, y) = point.split_once(',').unwrap();
+            (x.parse::<f64>().unwrap(), y.parse::<f64>().unwrap())
+        };
+
+        svg.lines()
+            .filter(|line| {
+                line.contains(&format!("stroke=\"{}\"", color))
+                    && line.contains(&format!("stroke-width=\"{}\"", OUTLINE_WIDTH))
+            })
+            .map(|line| {
+                let data = line.split("d=\"").nth(1).unwrap();
+                let data = &data[..data.find('"').unwrap()];
+                let (start, end) = data.split_once(" L").unwrap();
+                [point(start.trim_start_matches('M')), point(end)]
+            })
+            .collect()
+    }
+
+    fn assert_same_point(a: (f64, f64), b: (f64, f64)) {
+        assert!(
+            (a.0 - b.0).abs() < 1e-6 && (a.1 - b.1).abs() < 1e-6,
+            "{:?} != {:?}",
+            a,
+            b
+        );
+    }
+
+    #[test]
+    fn every_size_has_an_outline() {
+        let svg = render_grading(Anchor::BottomLeft).unwrap();
+
+        for (index, size) in ["S", "L"].into_iter().enumerate() {
+            assert_eq!(outline(&svg, STITCH_COLORS[index]).len(), 4);
+            assert_eq!(
+                count(
+                    &svg,
+                    &format!("stroke-dasharray=\"{}\"", SIZE_DASHES[index])
+                ),
+                5
+            );
+            assert_eq!(count_texts(&svg, size), 1);
+        }
+        assert_eq!(count_texts(&svg, "front"), 1);
+        assert_eq!(count(&svg, "<circle"), 1);
+    }
+
+    #[test]
+    fn sizes_are_aligned_at_the_anchor() {
+        // the hem is the first edge, from vertex 0 to vertex 1. only the hem
+        // is scaled, so the other end of it is apart.
+        for (vertex, other) in [(0, 1), (1, 0)] {
+            let svg = render_grading(Anchor::Vertex(VertexIndex(vertex))).unwrap();
+            let small = outline(&svg, STITCH_COLORS[0]);
+            let large = outline(&svg, STITCH_COLORS[1]);
+            assert_same_point(small[0][vertex], large[0][vertex]);
+            assert!((small[0][other].0 - large[0][other].0).abs() > 29.0);
+        }
+
+        // the bottom left corners of the bounding boxes.
+        let svg = render_grading(Anchor::BottomLeft).unwrap();
+        let corner = |color| {
+            outline(&svg, color)
+                .into_iter()
+                .flatten()
+                .reduce(|(x, y), (other_x, other_y)| (x.min(other_x), y.max(other_y)))
+                .unwrap()
+        };
+        assert_same_point(corner(STITCH_COLORS[0]), corner(STITCH_COLORS[1]));
+    }
+
+    #[test]
+    fn anchor_vertex_must_exist() {
+        let error = render_grading(Anchor::Vertex(VertexIndex(4))).unwrap_err();
+        assert!(
+            matches!(
+                error,
+                Error::RenderError(RenderError::NoSuchVertex {
+                    index: VertexIndex(4)
+                })
+            ),
+            "{:?}",
+            error
+        );
+    }
+
+    /// the hem template swept in 3 steps of `width`.
+    fn sweep() -> SweepGrid {
+        let template = testing::hem_template();
+        template
+            .sweep(
+                &template.default_parameters(),
+                &[ParameterName("width".to_owned())],
+                3,
+            )
+            .unwrap()
+    }
+
+    fn render_sweep(frame_duration: Option<f64>) -> String {
+        let mut target = Target::default();
+        let context = SweepContext {
+            frame_duration,
+            spacing: 5.0,
+            ..Default::default()
+        };
+        sweep().render(&mut target, &context).unwrap();
+        target.build().unwrap().to_string()
+    }
+
+    #[test]
+    fn sweep_frames_are_labelled() {
+        let grid = sweep();
+        let context = PatternContext {
+            title: Some(TitleBlock {
+                name: "skirt".to_owned(),
+                parameters: Parameters::default(),
+            }),
+            ..Default::default()
+        };
+        let frames = grid.frames(&context).unwrap();
+        assert_eq!(frames.len(), 3);
+
+        for ((view_box, document), label) in
+            frames
+                .iter()
+                .zip(["width = 0.5", "width = 1.25", "width = 2"])
+        {
+            let svg = document.to_string();
+            assert_eq!(count_texts(&svg, label), 1);
+            assert_eq!(count_texts(&svg, "skirt"), 0);
+            assert_eq!(count(&svg, "<svg"), 1);
+
+            // the label is above the pattern.
+            assert!(view_box.top_left().y < -50.0, "{:?}", view_box);
+        }
+
+        // the hem grows to the left and right.
+        assert!(frames[0].0.width() < frames[2].0.width());
+    }
+
+    #[test]
+    fn sweep_contact_sheet() {
+        let svg = render_sweep(None);
+
+        // a nested document per pattern, next to each other.
+        assert_eq!(count(&svg, "<svg"), 4);
+        assert_eq!(count(&svg, "<animate"), 0);
+        let positions = svg
+            .lines()
+            .skip(1)
+            .filter(|line| line.starts_with("<svg"))
+            .map(|line| {
+                let x = line.split(" x=\"").nth(1).unwrap();
+                x[..x.find('"').unwrap()].parse::<f64>().unwrap()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(positions.len(), 3);
+        assert!(positions[0] < positions[1] && positions[1] < positions[2]);
+    }
+
+    #[test]
+    fn sweep_animation() {
+        let svg = render_sweep(Some(0.5));
+
+        assert_eq!(count(&svg, "<svg"), 4);
+        assert_eq!(count(&svg, "<animate"), 3);
+        assert_eq!(count(&svg, "dur=\"1.5s\""), 3);
+        assert_eq!(count(&svg, "<g opacity=\"1\">"), 1);
+        assert_eq!(count(&svg, "<g opacity=\"0\">"), 2);
+        assert_eq!(count(&svg, "values=\"1;0\""), 1);
+        assert_eq!(count(&svg, "values=\"0;1;0\""), 2);
+
+        // every frame is in the same place.
+        let view_boxes = svg
+            .lines()
+            .filter(|line| line.starts_with("<svg"))
+            .map(|line| {
+                let view_box = line.split("viewBox=\"").nth(1).unwrap();
+                &view_box[..view_box.find('"').unwrap()]
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(view_boxes.len(), 4);
+        assert!(view_boxes.iter().all(|view_box| *view_box == view_boxes[0]));
+    }
+}
@@ -0,0 +1,73 @@
+//! helpers shared by the reports on patterns, i.e. [`crate::consumption`],
+//! [`crate::fitting`] and [`crate::constraints`].
+
+use std::fmt::{
+    self,
+    Write,
+};
+
+use rust_decimal::{
+    prelude::ToPrimitive,
+    Decimal,
+};
+
+/// the `units_in_meter` of a report isn't a positive number.
+#[derive(Debug, thiserror::Error)]
+#[error("invalid units in meter: {units_in_meter}")]
+pub struct InvalidUnits {
+    pub units_in_meter: Decimal,
+}
+
+/// pattern units in a meter, as `f64`.
+pub(crate) fn units_in_meter(value: Decimal) -> Result<f64, InvalidUnits> {
+    value
+        .to_f64()
+        .filter(|units| units.is_finite() && *units > 0.0)
+        .ok_or(InvalidUnits {
+            units_in_meter: value,
+        })
+}
+
+/// appends a row to a CSV table.
+pub(crate) fn write_csv_row(csv: &mut String, row: fmt::Arguments<'_>) {
+    // writing to a string can't fail.
+    let _ = csv.write_fmt(row);
+    csv.push('\n');
+}
+
+/// quotes a CSV field if it needs to be.
+pub(crate) fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    }
+    else {
+        value.to_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn units_must_be_positive() {
+        assert_eq!(units_in_meter(Decimal::ONE_HUNDRED).unwrap(), 100.0);
+        assert!(units_in_meter(Decimal::ZERO).is_err());
+        assert!(units_in_meter(Decimal::NEGATIVE_ONE).is_err());
+    }
+
+    #[test]
+    fn quotes_csv_fields() {
+        assert_eq!(csv_field("front"), "front");
+        assert_eq!(csv_field("front, left"), "\"front, left\"");
+        assert_eq!(csv_field("5\" hem"), "\"5\"\" hem\"");
+        assert_eq!(csv_field("a\nb"), "\"a\nb\"");
+    }
+
+    #[test]
+    fn writes_rows() {
+        let mut csv = String::from("a,b\n");
+        write_csv_row(&mut csv, format_args!("{},{}", csv_field("x,y"), 2));
+        assert_eq!(csv, "a,b\n\"x,y\",2\n");
+    }
+}
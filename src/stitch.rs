@@ -0,0 +1,288 @@
+//! queries on how the panels of a [`Pattern`] are sewn together.
+
+use std::collections::{
+    BTreeMap,
+    BTreeSet,
+};
+
+use crate::{
+    id::{
+        EdgeHandle,
+        EdgeIndex,
+        PanelId,
+    },
+    pattern::{
+        Pattern,
+        RenderError,
+    },
+};
+
+/// index of a stitch in [`Pattern::stitches`].
+pub type StitchIndex = usize;
+
+/// the stitches of a pattern, indexed by the edges they connect.
+///
+/// a stitch can connect any number of edges. two edges are sewn to each other,
+/// if they are part of the same stitch.
+#[derive(Clone, Debug)]
+pub struct StitchGraph {
+    /// the edges of every stitch.
+    stitches: Vec<Vec<EdgeHandle>>,
+
+    /// the stitches every edge of the pattern is part of. this also contains
+    /// the edges that aren't stitched.
+    edges: BTreeMap<EdgeHandle, Vec<StitchIndex>>,
+
+    /// all panels in `panel_order`.
+    panels: Vec<PanelId>,
+}
+
+impl StitchGraph {
+    /// builds the graph. fails if a stitch refers to an edge that doesn't
+    /// exist.
+    pub fn new(pattern: &Pattern) -> Result<Self, RenderError> {
+        let mut edges = BTreeMap::new();
+        let mut panels = vec![];
+
+        for (name, panel) in pattern.panels()? {
+            panels.push(name.clone());
+            for index in EdgeIndex::all(panel.edges.len()) {
+                edges.insert(EdgeHandle::new(name.clone(), index), vec![]);
+            }
+        }
+
+        let mut stitches = Vec::with_capacity(pattern.stitches.len());
+
+        for (index, stitch) in pattern.stitches.iter().enumerate() {
+            let sides = stitch
+                .0
+                .iter()
+                .map(|side| side.handle())
+                .collect::<Vec<_>>();
+
+            for side in &sides {
+                pattern.edge(side)?;
+                edges.entry(side.clone()).or_default().push(index);
+            }
+
+            stitches.push(sides);
+        }
+
+        Ok(Self {
+            stitches,
+            edges,
+            panels,
+        })
+    }
+
+    pub fn stitches(&self) -> &[Vec<EdgeHandle>] {
+        &self.stitches
+    }
+
+    /// the stitches `edge` is part of.
+    pub fn stitches_of(&self, edge: &EdgeHandle) -> &[StitchIndex] {
+        self.edges.get(edge).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    pub fn is_stitched(&self, edge: &EdgeHandle) -> bool {
+        !self.stitches_of(edge).is_empty()
+    }
+
+    /// the edges `edge` is sewn to. edges that are sewn to `edge` by more than
+    /// one stitch are yielded once per stitch.
+    pub fn sewn_to<'a>(&'a self, edge: &'a EdgeHandle) -> impl Iterator<Item = &'a EdgeHandle> {
+        self.stitches_of(edge)
+            .iter()
+            .flat_map(move |index| &self.stitches[*index])
+            .filter(move |other| *other != edge)
+    }
+
+    /// edges that aren't stitched to anything, e.g. hems and openings.
+    pub fn free_edges(&self) -> impl Iterator<Item = &EdgeHandle> {
+        self.edges
+            .iter()
+            .filter(|(_, stitches)| stitches.is_empty())
+            .map(|(edge, _)| edge)
+    }
+
+    /// edges that are part of more than one stitch, with the number of
+    /// stitches.
+    pub fn multiply_stitched(&self) -> impl Iterator<Item = (&EdgeHandle, usize)> {
+        self.edges
+            .iter()
+            .filter(|(_, stitches)| stitches.len() > 1)
+            .map(|(edge, stitches)| (edge, stitches.len()))
+    }
+
+    /// panels that are sewn to `panel`, not including `panel` itself.
+    pub fn connected_panels(&self, panel: &PanelId) -> BTreeSet<&PanelId> {
+        self.edges
+            .keys()
+            .filter(|edge| &edge.panel == panel)
+            .flat_map(|edge| self.sewn_to(edge))
+            .map(|other| &other.panel)
+            .filter(|other| *other != panel)
+            .collect()
+    }
+
+    /// groups of panels that are sewn together. panels without stitches form
+    /// groups of their own. groups are ordered by their first panel in
+    /// `panel_order`.
+    pub fn components(&self) -> Vec<Vec<&PanelId>> {
+        let mut visited = BTreeSet::new();
+        let mut components = vec![];
+
+        for panel in &self.panels {
+            if !visited.insert(panel) {
+                continue;
+            }
+
+            let mut component = vec![panel];
+            let mut queue = vec![panel];
+
+            while let Some(current) = queue.pop() {
+                for other in self.connected_panels(current) {
+                    if visited.insert(other) {
+                        component.push(other);
+                        queue.push(other);
+                    }
+                }
+            }
+
+            components.push(component);
+        }
+
+        components
+    }
+}
+
+impl Pattern {
+    /// see [`StitchGraph::new`].
+    pub fn stitch_graph(&self) -> Result<StitchGraph, RenderError> {
+        StitchGraph::new(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn square() -> serde_json::Value {
+        json!({
+            "translation": [0, 0, 0],
+            "rotation": [0, 0, 0],
+            "vertices": [[0, 0], [10, 0], [10, 10], [0, 10]],
+            "edges": [
+                {"endpoints": [0, 1]},
+                {"endpoints": [1, 2]},
+                {"endpoints": [2, 3]},
+                {"endpoints": [3, 0]},
+            ],
+        })
+    }
+
+    fn side(panel: &str, edge: usize) -> serde_json::Value {
+        json!({"panel": panel, "edge": edge})
+    }
+
+    /// front and back are sewn at their sides and both to the belt. the
+    /// pocket isn't sewn to anything.
+    fn pattern(stitches: serde_json::Value) -> Pattern {
+        serde_json::from_value(json!({
+            "panels": {
+                "back": square(),
+                "belt": square(),
+                "front": square(),
+                "pocket": square(),
+            },
+            "panel_order": ["front", "back", "belt", "pocket"],
+            "stitches": stitches,
+        }))
+        .unwrap()
+    }
+
+    fn graph() -> StitchGraph {
+        pattern(json!([
+            [side("front", 1), side("back", 3)],
+            [side("front", 3), side("back", 1)],
+            [side("belt", 0), side("front", 2), side("back", 2)],
+            [side("front", 1), side("belt", 1)],
+        ]))
+        .stitch_graph()
+        .unwrap()
+    }
+
+    fn handle(panel: &str, edge: usize) -> EdgeHandle {
+        EdgeHandle::new(panel, edge)
+    }
+
+    #[test]
+    fn sewn_to() {
+        let graph = graph();
+
+        assert_eq!(graph.stitches_of(&handle("front", 1)), &[0, 3]);
+        assert_eq!(
+            graph.sewn_to(&handle("front", 1)).collect::<Vec<_>>(),
+            vec![&handle("back", 3), &handle("belt", 1)]
+        );
+        assert_eq!(
+            graph.sewn_to(&handle("belt", 0)).collect::<Vec<_>>(),
+            vec![&handle("front", 2), &handle("back", 2)]
+        );
+
+        assert!(graph.is_stitched(&handle("back", 1)));
+        assert!(!graph.is_stitched(&handle("front", 0)));
+        assert!(!graph.is_stitched(&handle("nothing", 0)));
+    }
+
+    #[test]
+    fn free_and_multiply_stitched_edges() {
+        let graph = graph();
+
+        let free = graph.free_edges().cloned().collect::<BTreeSet<_>>();
+        assert_eq!(free.len(), 16 - 8);
+        assert!(free.contains(&handle("front", 0)));
+        assert!(free.contains(&handle("belt", 2)));
+        assert!((0..4).all(|edge| free.contains(&handle("pocket", edge))));
+
+        assert_eq!(
+            graph.multiply_stitched().collect::<Vec<_>>(),
+            vec![(&handle("front", 1), 2)]
+        );
+    }
+
+    #[test]
+    fn connected_panels_and_components() {
+        let graph = graph();
+
+        let front = PanelId::from("front");
+        let back = PanelId::from("back");
+        let belt = PanelId::from("belt");
+        let pocket = PanelId::from("pocket");
+
+        assert_eq!(
+            graph.connected_panels(&front),
+            BTreeSet::from([&back, &belt])
+        );
+        assert!(graph.connected_panels(&pocket).is_empty());
+
+        assert_eq!(
+            graph.components(),
+            vec![vec![&front, &back, &belt], vec![&pocket]]
+        );
+    }
+
+    #[test]
+    fn stitch_to_missing_edge() {
+        let result = pattern(json!([[side("front", 1), side("back", 9)]])).stitch_graph();
+        assert!(matches!(
+            result,
+            Err(RenderError::NoSuchEdge {
+                edge: EdgeIndex(9),
+                ..
+            })
+        ));
+    }
+}
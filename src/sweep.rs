@@ -0,0 +1,330 @@
+//! sweeping parameters across their range, to see how they affect a
+//! template.
+//!
+//! [`Template::sweep`] varies one or two parameters in evenly spaced steps
+//! and instantiates a pattern for every combination. the patterns form a grid
+//! with the values of the first parameter in the columns and those of the
+//! second parameter in the rows.
+
+use rust_decimal::Decimal;
+
+use crate::{
+    id::ParameterName,
+    parameters::Parameters,
+    pattern::{
+        Pattern,
+        RenderError,
+        Template,
+    },
+};
+
+/// swept values are rounded to this many decimal places.
+const DECIMAL_PLACES: u32 = 6;
+
+#[derive(Debug, thiserror::Error)]
+pub enum SweepError {
+    #[error("one or two parameters can be swept, but got {count}")]
+    ParameterCount { count: usize },
+
+    #[error("parameter {name} is swept twice")]
+    DuplicateParameter { name: ParameterName },
+
+    #[error("a sweep needs at least 2 steps, but got {steps}")]
+    TooFewSteps { steps: usize },
+
+    #[error(transparent)]
+    Render(#[from] RenderError),
+}
+
+/// patterns of a sweep, row by row.
+#[derive(Clone, Debug)]
+pub struct SweepGrid {
+    /// the swept parameters. the first one changes along the columns, the
+    /// second one along the rows.
+    pub swept: Vec<ParameterName>,
+
+    pub columns: usize,
+    pub rows: usize,
+
+    pub cells: Vec<SweepCell>,
+}
+
+#[derive(Clone, Debug)]
+pub struct SweepCell {
+    /// values of the swept parameters, in the order of
+    /// [`SweepGrid::swept`].
+    pub values: Vec<Decimal>,
+
+    /// all parameters the pattern was made with.
+    pub parameters: Parameters,
+
+    pub pattern: Pattern,
+}
+
+impl Template {
+    /// instantiates the template with `swept` parameters varied across their
+    /// range in `steps` steps, including both ends of the range. all other
+    /// parameters are taken from `base`.
+    pub fn sweep(
+        &self,
+        base: &Parameters,
+        swept: &[ParameterName],
+        steps: usize,
+    ) -> Result<SweepGrid, SweepError> {
+        if swept.is_empty() || swept.len() > 2 {
+            return Err(SweepError::ParameterCount { count: swept.len() });
+        }
+        if swept.len() == 2 && swept[0] == swept[1] {
+            return Err(SweepError::DuplicateParameter {
+                name: swept[0].clone(),
+            });
+        }
+        if steps < 2 {
+            return Err(SweepError::TooFewSteps { steps });
+        }
+
+        let values = swept
+            .iter()
+            .map(|name| {
+                let parameter = self
+                    .parameters
+                    .get(name)
+                    .ok_or_else(|| RenderError::NoSuchParameter { name: name.clone() })?;
+                let [min, max] = parameter.range;
+                let last = Decimal::from(steps - 1);
+
+                Ok((0..steps)
+                    .map(|step| {
+                        (min + (max - min) * Decimal::from(step) / last)
+                            .round_dp(DECIMAL_PLACES)
+                            .normalize()
+                    })
+                    .collect::<Vec<_>>())
+            })
+            .collect::<Result<Vec<_>, RenderError>>()?;
+
+        let rows = if swept.len() == 2 { steps } else { 1 };
+        let mut cells = Vec::with_capacity(rows * steps);
+
+        for row in 0..rows {
+            for column in 0..steps {
+                let values = [column, row]
+                    .iter()
+                    .zip(&values)
+                    .map(|(step, values)| values[*step])
+                    .collect::<Vec<_>>();
+
+                let mut parameters = base.clone();
+                for (name, value) in swept.iter().zip(&values) {
+                    parameters.parameters.insert(name.clone(), *value);
+                }
+                let pattern = self.with_parameters(&parameters)?;
+
+                cells.push(SweepCell {
+                    values,
+                    parameters,
+                    pattern,
+                });
+            }
+        }
+
+        Ok(SweepGrid {
+            swept: swept.to_vec(),
+            columns: steps,
+            rows,
+            cells,
+        })
+    }
+}
+
+impl SweepGrid {
+    /// the label of a cell, e.g. `length = 0.5, waist = 1`.
+    pub fn label(&self, cell: &SweepCell) -> String {
+        self.swept
+            .iter()
+            .zip(&cell.values)
+            .map(|(name, value)| format!("{} = {}", name, value))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::testing::{
+        self,
+        pattern_json,
+        rectangle,
+    };
+
+    /// a 40x50 panel whose hem is scaled by `width` and whose right side is
+    /// scaled by `height`.
+    fn template() -> Template {
+        testing::template(
+            pattern_json(vec![("front", rectangle(40, 50))], json!([])),
+            vec![
+                (
+                    "width",
+                    json!({
+                        "influence": [{"panel": "front", "edge_list": [0]}],
+                        "range": [0.5, 2],
+                        "type": "length",
+                        "value": 1,
+                    }),
+                ),
+                (
+                    "height",
+                    json!({
+                        "influence": [{"panel": "front", "edge_list": [1]}],
+                        "range": [0.8, 1.2],
+                        "type": "length",
+                        "value": 1,
+                    }),
+                ),
+            ],
+        )
+    }
+
+    fn names(names: &[&str]) -> Vec<ParameterName> {
+        names
+            .iter()
+            .map(|name| ParameterName((*name).to_owned()))
+            .collect()
+    }
+
+    fn decimal(value: &str) -> Decimal {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn sweeps_across_the_range() {
+        let template = template();
+        let grid = template
+            .sweep(&template.default_parameters(), &names(&["width"]), 3)
+            .unwrap();
+
+        assert_eq!((grid.columns, grid.rows), (3, 1));
+        assert_eq!(grid.cells.len(), 3);
+
+        let values = grid
+            .cells
+            .iter()
+            .map(|cell| cell.values[0])
+            .collect::<Vec<_>>();
+        let range = template.parameters[&names(&["width"])[0]].range;
+        assert_eq!(values, [range[0], decimal("1.25"), range[1]]);
+
+        let hem = "front:0".parse().unwrap();
+        for (cell, length) in grid.cells.iter().zip([20.0, 50.0, 80.0]) {
+            let achieved = cell.pattern.edge_segment(&hem).unwrap().length();
+            assert!((achieved - length).abs() < 1e-9, "{}", achieved);
+
+            // the other parameter keeps its value.
+            assert_eq!(
+                cell.parameters.parameters[&names(&["height"])[0]],
+                Decimal::ONE
+            );
+        }
+
+        assert_eq!(grid.label(&grid.cells[0]), "width = 0.5");
+        assert_eq!(grid.label(&grid.cells[1]), "width = 1.25");
+    }
+
+    #[test]
+    fn two_parameters_make_a_grid() {
+        let template = template();
+        let grid = template
+            .sweep(
+                &template.default_parameters(),
+                &names(&["width", "height"]),
+                2,
+            )
+            .unwrap();
+
+        assert_eq!(grid.swept, names(&["width", "height"]));
+        assert_eq!((grid.columns, grid.rows), (2, 2));
+
+        // width along the columns, height along the rows.
+        let values = grid
+            .cells
+            .iter()
+            .map(|cell| cell.values.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            values,
+            [
+                [decimal("0.5"), decimal("0.8")],
+                [decimal("2"), decimal("0.8")],
+                [decimal("0.5"), decimal("1.2")],
+                [decimal("2"), decimal("1.2")],
+            ]
+        );
+        assert_eq!(grid.label(&grid.cells[2]), "width = 0.5, height = 1.2");
+    }
+
+    #[test]
+    fn too_few_steps() {
+        let template = template();
+        for steps in [0, 1] {
+            let error = template
+                .sweep(&template.default_parameters(), &names(&["width"]), steps)
+                .unwrap_err();
+            assert!(
+                matches!(error, SweepError::TooFewSteps { steps: error_steps } if error_steps == steps),
+                "{:?}",
+                error
+            );
+        }
+    }
+
+    #[test]
+    fn one_or_two_parameters() {
+        let template = template();
+        for swept in [names(&[]), names(&["width", "height", "width"])] {
+            let error = template
+                .sweep(&template.default_parameters(), &swept, 3)
+                .unwrap_err();
+            assert!(
+                matches!(error, SweepError::ParameterCount { count } if count == swept.len()),
+                "{:?}",
+                error
+            );
+        }
+    }
+
+    #[test]
+    fn unknown_parameter() {
+        let template = template();
+        let error = template
+            .sweep(&template.default_parameters(), &names(&["length"]), 3)
+            .unwrap_err();
+        assert!(
+            matches!(
+                &error,
+                SweepError::Render(RenderError::NoSuchParameter { name }) if name.0 == "length"
+            ),
+            "{:?}",
+            error
+        );
+    }
+
+    #[test]
+    fn parameter_swept_twice() {
+        let template = template();
+        let error = template
+            .sweep(
+                &template.default_parameters(),
+                &names(&["width", "width"]),
+                3,
+            )
+            .unwrap_err();
+        assert!(
+            matches!(&error, SweepError::DuplicateParameter { name } if name.0 == "width"),
+            "{:?}",
+            error
+        );
+    }
+}
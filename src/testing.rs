@@ -0,0 +1,91 @@
+//! fixtures shared by the unit tests.
+
+use serde_json::{
+    json,
+    Map,
+    Value,
+};
+
+use crate::pattern::{
+    Pattern,
+    Template,
+};
+
+/// a `width` x `height` rectangular panel, as json. its bottom left corner is
+/// at the origin, and its edges go counter-clockwise, starting with the
+/// bottom.
+pub fn rectangle(width: i64, height: i64) -> Value {
+    json!({
+        "translation": [0, 0, 0],
+        "rotation": [0, 0, 0],
+        "vertices": [[0, 0], [width, 0], [width, height], [0, height]],
+        "edges": [
+            {"endpoints": [0, 1]},
+            {"endpoints": [1, 2]},
+            {"endpoints": [2, 3]},
+            {"endpoints": [3, 0]},
+        ],
+    })
+}
+
+/// a pattern with `panels` in this order, as json.
+pub fn pattern_json(panels: Vec<(&str, Value)>, stitches: Value) -> Value {
+    let order = panels.iter().map(|(name, _)| *name).collect::<Vec<_>>();
+    let panels = panels
+        .into_iter()
+        .map(|(name, panel)| (name.to_owned(), panel))
+        .collect::<Map<_, _>>();
+
+    json!({
+        "panels": panels,
+        "panel_order": order,
+        "stitches": stitches,
+    })
+}
+
+/// a pattern with `panels` in this order.
+pub fn pattern(panels: Vec<(&str, Value)>, stitches: Value) -> Pattern {
+    serde_json::from_value(pattern_json(panels, stitches)).unwrap()
+}
+
+/// a template of `pattern` with `parameters` in this order, and without
+/// constraints.
+pub fn template(pattern: Value, parameters: Vec<(&str, Value)>) -> Template {
+    let order = parameters.iter().map(|(name, _)| *name).collect::<Vec<_>>();
+    let parameters = parameters
+        .into_iter()
+        .map(|(name, parameter)| (name.to_owned(), parameter))
+        .collect::<Map<_, _>>();
+
+    serde_json::from_value(json!({
+        "pattern": pattern,
+        "properties": {
+            "curvature_coords": "relative",
+            "normalize_panel_translation": false,
+            "units_in_meter": 100,
+            "normalized_edge_loops": true,
+        },
+        "parameters": parameters,
+        "parameter_order": order,
+        "constraints": {},
+        "constraint_order": [],
+    }))
+    .unwrap()
+}
+
+/// a template of a 40x50 rectangular front whose hem is scaled by `width`,
+/// within 0.5 .. 2.
+pub fn hem_template() -> Template {
+    template(
+        pattern_json(vec![("front", rectangle(40, 50))], json!([])),
+        vec![(
+            "width",
+            json!({
+                "influence": [{"panel": "front", "edge_list": [0]}],
+                "range": [0.5, 2],
+                "type": "length",
+                "value": 1,
+            }),
+        )],
+    )
+}
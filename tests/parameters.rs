@@ -0,0 +1,358 @@
+//! instantiating templates with parameters, checked against samples.
+//!
+//! `data/hand_written_template.json` and `data/hand_written_sample.json` are
+//! written by hand in the dataset's format, and aren't taken from the
+//! published dataset. the sample's parameter `value`s are the sampled values,
+//! and its pattern was worked out by hand from the template, see
+//! `sample_is_worked_out_by_hand`, so it doesn't depend on this crate.
+
+use rust_decimal::{
+    prelude::ToPrimitive,
+    Decimal,
+};
+use serde_json::{
+    json,
+    Value,
+};
+use skirt_rs::{
+    pattern::Semantics,
+    Curve,
+    EdgeHandle,
+    EdgeIndex,
+    PanelId,
+    ParameterName,
+    Parameters,
+    Pattern,
+    RenderError,
+    Template,
+};
+
+const TEMPLATE: &str = include_str!("data/hand_written_template.json");
+const SAMPLE: &str = include_str!("data/hand_written_sample.json");
+
+const TOLERANCE: f64 = 1e-6;
+
+fn template(json: &str) -> Template {
+    serde_json::from_str(json).unwrap()
+}
+
+/// the template, with the parameter `name` replaced by `parameter`.
+fn template_with_parameter(name: &str, parameter: Value) -> Template {
+    let mut json: Value = serde_json::from_str(TEMPLATE).unwrap();
+    json["parameters"][name] = parameter;
+    serde_json::from_value(json).unwrap()
+}
+
+fn parameters(values: &[(&str, Decimal)]) -> Parameters {
+    let mut parameters = Parameters::default();
+    for (name, value) in values {
+        parameters.parameters.insert((*name).into(), *value);
+    }
+    parameters
+}
+
+fn assert_close(actual: Decimal, expected: Decimal, what: &str) {
+    let difference = (actual - expected).to_f64().unwrap().abs();
+    assert!(
+        difference <= TOLERANCE,
+        "{}: expected {}, got {}",
+        what,
+        expected,
+        actual
+    );
+}
+
+fn assert_close_f64(actual: f64, expected: f64, what: &str) {
+    assert!(
+        (actual - expected).abs() <= TOLERANCE,
+        "{}: expected {}, got {}",
+        what,
+        expected,
+        actual
+    );
+}
+
+/// length of the front waist.
+fn waist_length(pattern: &Pattern) -> f64 {
+    pattern
+        .edge_segment(&EdgeHandle::new(PanelId::from("front"), EdgeIndex(2)))
+        .unwrap()
+        .length()
+}
+
+fn assert_patterns_eq(actual: &Pattern, expected: &Pattern) {
+    assert_eq!(actual.panel_order, expected.panel_order);
+
+    for (name, expected_panel) in &expected.panels {
+        let panel = &actual.panels[name];
+
+        for i in 0..3 {
+            assert_close(
+                panel.translation[i],
+                expected_panel.translation[i],
+                &format!("{} translation", name),
+            );
+            assert_close(
+                panel.rotation[i],
+                expected_panel.rotation[i],
+                &format!("{} rotation", name),
+            );
+        }
+
+        assert_eq!(panel.vertices.len(), expected_panel.vertices.len());
+        for (index, (vertex, expected_vertex)) in panel
+            .vertices
+            .iter()
+            .zip(&expected_panel.vertices)
+            .enumerate()
+        {
+            let what = format!("{} vertex {}", name, index);
+            assert_close(vertex.x, expected_vertex.x, &what);
+            assert_close(vertex.y, expected_vertex.y, &what);
+        }
+
+        assert_eq!(panel.edges.len(), expected_panel.edges.len());
+        for (index, (edge, expected_edge)) in
+            panel.edges.iter().zip(&expected_panel.edges).enumerate()
+        {
+            let what = format!("{} edge {}", name, index);
+            assert_eq!(edge.endpoints, expected_edge.endpoints, "{}", what);
+
+            let controls = edge.curve.control_points();
+            let expected_controls = expected_edge.curve.control_points();
+            assert_eq!(controls.len(), expected_controls.len(), "{}", what);
+            for (control, expected_control) in controls.iter().zip(expected_controls.iter()) {
+                assert_close(control[0], expected_control[0], &what);
+                assert_close(control[1], expected_control[1], &what);
+            }
+        }
+    }
+}
+
+#[test]
+fn default_parameters_keep_the_template() {
+    let template = template(TEMPLATE);
+    let pattern = template
+        .with_parameters(&template.default_parameters())
+        .unwrap();
+
+    assert_patterns_eq(&pattern, &template.pattern);
+}
+
+/// the sample's pattern, from the template with `length = 1.5`,
+/// `waist = 1.2` and `waist_curve = 2`:
+///
+///  - `length` scales the sides (edges 1 and 3) around their waist ends, so the
+///    hem vertices 0 and 1 move to `waist + 1.5 * (hem - waist)`.
+///  - `waist` scales the waist (edge 2) around its middle, which is at `x = 30`
+///    on both panels.
+///  - `waist_curve` doubles the waist's curvature.
+///  - the panels are normalized, so the vertices are moved to start at the
+///    origin and the translation is moved the other way.
+#[test]
+fn sample_is_worked_out_by_hand() {
+    let expected = [
+        (
+            "front",
+            // hem (0, 0) and (60, 0), waist (50, 60) and (10, 60). the hem
+            // moves to (-5, -30) and (65, -30), the waist is 48 instead of 40
+            // wide.
+            [-5.0, 50.0, 20.0],
+            [[0.0, 0.0], [70.0, 0.0], [59.0, 90.0], [11.0, 90.0]],
+            0.2,
+        ),
+        (
+            "back",
+            // hem (0, 0) and (56, 0), waist (48, 60) and (12, 60). the hem
+            // moves to (-6, -30) and (60, -30), the waist is 43.2 instead of
+            // 36 wide.
+            [-6.0, 50.0, -20.0],
+            [[0.0, 0.0], [66.0, 0.0], [57.6, 90.0], [14.4, 90.0]],
+            0.1,
+        ),
+    ];
+
+    let template = template(TEMPLATE);
+    let sample = self::template(SAMPLE);
+    let pattern = template
+        .with_parameters(&sample.default_parameters())
+        .unwrap();
+
+    for (pattern, what) in [(&sample.pattern, "sample"), (&pattern, "instance")] {
+        for (name, translation, vertices, curvature) in &expected {
+            let panel = &pattern.panels[&PanelId::from(*name)];
+            let what = format!("{} {}", what, name);
+
+            for (actual, expected) in panel.translation.iter().zip(translation) {
+                assert_close_f64(actual.to_f64().unwrap(), *expected, &what);
+            }
+            for (actual, expected) in panel.vertices.iter().zip(vertices) {
+                assert_close_f64(actual.x.to_f64().unwrap(), expected[0], &what);
+                assert_close_f64(actual.y.to_f64().unwrap(), expected[1], &what);
+            }
+
+            let across = panel.edges[2].curve.control_points()[0][1];
+            assert_close_f64(across.to_f64().unwrap(), *curvature, &what);
+        }
+    }
+}
+
+#[test]
+fn template_reproduces_sample() {
+    let template = template(TEMPLATE);
+    let sample = self::template(SAMPLE);
+
+    let pattern = template
+        .with_parameters(&sample.default_parameters())
+        .unwrap();
+
+    assert_patterns_eq(&pattern, &sample.pattern);
+}
+
+#[test]
+fn sample_restores_template() {
+    let template = template(TEMPLATE);
+    let sample = self::template(SAMPLE);
+
+    // the sample's defaults aren't 1, so they have to be undone first.
+    let pattern = sample
+        .with_parameters(&template.default_parameters())
+        .unwrap();
+
+    assert_patterns_eq(&pattern, &template.pattern);
+}
+
+#[test]
+fn sample_and_template_agree() {
+    let template = template(TEMPLATE);
+    let sample = self::template(SAMPLE);
+
+    let parameters = parameters(&[
+        ("length", Decimal::new(8, 1)),
+        ("waist", Decimal::new(9, 1)),
+        ("waist_curve", Decimal::new(5, 1)),
+    ]);
+
+    assert_patterns_eq(
+        &sample.with_parameters(&parameters).unwrap(),
+        &template.with_parameters(&parameters).unwrap(),
+    );
+}
+
+#[test]
+fn semantics_are_inferred_from_the_default() {
+    let template = template(TEMPLATE);
+    assert_eq!(
+        template.parameters[&ParameterName::from("waist")].semantics(),
+        Semantics::Multiplicative
+    );
+
+    let template = template_with_parameter(
+        "waist",
+        json!({
+            "influence": [{"panel": "front", "edge_list": [2]}],
+            "range": [-10, 10],
+            "type": "length",
+            "value": 0,
+        }),
+    );
+    assert_eq!(
+        template.parameters[&ParameterName::from("waist")].semantics(),
+        Semantics::Additive
+    );
+}
+
+#[test]
+fn additive_length() {
+    let template = template_with_parameter(
+        "waist",
+        json!({
+            "influence": [{"panel": "front", "edge_list": [2]}],
+            "range": [-10, 10],
+            "type": "length",
+            "value": 0,
+            "semantics": "additive",
+        }),
+    );
+
+    let pattern = template
+        .with_parameters(&parameters(&[("waist", Decimal::from(8))]))
+        .unwrap();
+
+    // the waist is curved, so this is more than the 40 between its ends.
+    let before = waist_length(&template.pattern);
+    assert_close_f64(waist_length(&pattern), before + 8.0, "waist length");
+
+    // both ends move by the same amount.
+    let front = &pattern.panels[&PanelId::from("front")];
+    let moved = front.vertices[2].x - Decimal::from(50);
+    assert_close(
+        Decimal::from(10) - front.vertices[3].x,
+        moved,
+        "front vertex 3",
+    );
+    assert_eq!(front.vertices[2].y, Decimal::from(60));
+    assert_eq!(front.vertices[3].y, Decimal::from(60));
+}
+
+#[test]
+fn additive_length_with_default() {
+    let additive = template_with_parameter(
+        "waist",
+        json!({
+            "influence": [{"panel": "front", "edge_list": [2]}],
+            "range": [-10, 10],
+            "type": "length",
+            "value": 4,
+            "semantics": "additive",
+        }),
+    );
+
+    // the template is at 4, so 8 adds another 4.
+    let pattern = additive
+        .with_parameters(&parameters(&[("waist", Decimal::from(8))]))
+        .unwrap();
+
+    let before = waist_length(&additive.pattern);
+    assert_close_f64(waist_length(&pattern), before + 4.0, "waist length");
+}
+
+#[test]
+fn additive_curve() {
+    let template = template_with_parameter(
+        "waist_curve",
+        json!({
+            "influence": [{"panel": "front", "edge_list": [2]}],
+            "range": [-1, 1],
+            "type": "curve",
+            "value": 0,
+        }),
+    );
+
+    let pattern = template
+        .with_parameters(&parameters(&[("waist_curve", Decimal::new(15, 2))]))
+        .unwrap();
+
+    let front = &pattern.panels[&PanelId::from("front")];
+    assert_eq!(
+        front.edges[2].curve,
+        Curve::Quadratic([Decimal::new(5, 1), Decimal::new(25, 2)])
+    );
+}
+
+#[test]
+fn multiplicative_zero_default() {
+    let template = template_with_parameter(
+        "waist",
+        json!({
+            "influence": [{"panel": "front", "edge_list": [2]}],
+            "range": [0, 2],
+            "type": "length",
+            "value": 0,
+            "semantics": "multiplicative",
+        }),
+    );
+
+    let result = template.with_parameters(&parameters(&[("waist", Decimal::ONE)]));
+    assert!(matches!(result, Err(RenderError::ZeroDefault { .. })));
+}